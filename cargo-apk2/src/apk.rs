@@ -1,21 +1,39 @@
 use {
     crate::{
         error::Error,
-        manifest::{Inheritable, Manifest, Root},
+        lockfile::{self, LOCKFILE},
+        manifest::{Inheritable, JniCheck, Manifest, Root},
+        size::{check_budget, print_size, read as read_size},
+        verify::verify,
     },
     cargo_subcommand::{Artifact, ArtifactType, CrateType, Profile, Subcommand},
     ndk_build2::{
-        apk::{Apk, ApkConfig},
+        aar::Aar,
+        apk::{Apk, ApkConfig, R8Config, UnalignedApk},
         cargo::{VersionCode, cargo_ndk},
+        classfile::NativeMethod,
+        desugar::CoreLibraryDesugaring,
         dylibs::get_libs_search_paths,
         error::NdkError,
+        icon::LAUNCHER_ICON,
+        jni::{check_bindings, read_native_methods, rust_stubs},
+        maven::{Dependency, Repository, ResolvedArtifact, Resolver},
         ndk::{Key, Ndk},
+        resource_ids::ResourceIds,
+        strings::{LOCALES_CONFIG, LocalizedStrings},
         target::Target,
+        verify::ExpectedManifest,
+        zip::{ZipArchive, ZipWriter},
     },
     std::{
-        env::var,
+        cell::OnceCell,
+        collections::{BTreeMap, BTreeSet},
+        env::{join_paths, var},
         ffi::OsStr,
-        fs::{copy, create_dir_all, read_dir, remove_dir_all},
+        fs::{
+            copy, create_dir_all, read, read_dir, read_to_string, remove_dir_all, remove_file,
+            write,
+        },
         path::{Path, PathBuf},
         process::{Command, Stdio},
     },
@@ -33,6 +51,11 @@ pub struct ApkBuilder<'a> {
     classes_dir: PathBuf,
     build_targets: Vec<Target>,
     device_serial: Option<String>,
+    version: VersionCode,
+    /// 由 [`Self::dependencies`] 在第一次使用时解析
+    dependencies: OnceCell<Vec<ResolvedArtifact>>,
+    /// 由 [`Self::aars`] 在第一次使用时解压
+    aars: OnceCell<Vec<Aar>>,
 }
 
 impl<'a> ApkBuilder<'a> {
@@ -94,7 +117,8 @@ impl<'a> ApkBuilder<'a> {
             }
             Inheritable::Inherited { workspace: false } => return Err(Error::InheritedFalse),
         };
-        let version_code = VersionCode::from_semver(&package_version)?.to_code(1);
+        let version = VersionCode::from_semver(&package_version)?;
+        let version_code = version.to_code(1);
 
         // 设置默认 Android 清单值
         if manifest
@@ -127,6 +151,32 @@ impl<'a> ApkBuilder<'a> {
             .debuggable
             .get_or_insert_with(|| *cmd.profile() == Profile::Dev);
 
+        if !manifest.strings.is_empty() {
+            let application = &mut manifest.android_manifest.application;
+            // `android:localeConfig` 在 API 33 中加入，更早的 android.jar 中没有这个属性
+            if target_sdk_version >= 33 {
+                application
+                    .locale_config
+                    .get_or_insert_with(|| LOCALES_CONFIG.into());
+            }
+            if application.label.is_empty()
+                && manifest
+                    .strings
+                    .get(&manifest.default_locale)
+                    .is_some_and(|strings| strings.contains_key("app_name"))
+            {
+                application.label = "@string/app_name".into();
+            }
+        }
+
+        if manifest.icon.is_some() {
+            manifest
+                .android_manifest
+                .application
+                .icon
+                .get_or_insert_with(|| LAUNCHER_ICON.into());
+        }
+
         // 检查是否有Activity定义
         if manifest.android_manifest.application.activities.is_empty() {
             eprintln!(
@@ -183,6 +233,9 @@ impl<'a> ApkBuilder<'a> {
             classes_dir,
             build_targets,
             device_serial,
+            version,
+            dependencies: OnceCell::new(),
+            aars: OnceCell::new(),
         })
     }
 
@@ -237,6 +290,94 @@ impl<'a> ApkBuilder<'a> {
 
     const PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
 
+    /// 编译 JVM 源文件时的 classpath：`android.jar`、已编译的类与 `dependencies` 中的 jar
+    fn compile_classpath(&self, android_jar: &Path) -> Result<String, Error> {
+        let mut classpath = vec![android_jar.to_path_buf(), self.classes_dir.clone()];
+        classpath.extend(self.dependency_jars()?);
+        Ok(classpath
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(&Self::PATH_SEPARATOR.to_string()))
+    }
+
+    /// 传递解析 `dependencies`，解析结果记录在 `Cargo.toml` 旁边的 [`LOCKFILE`] 中
+    fn dependencies(&self) -> Result<&[ResolvedArtifact], Error> {
+        if let Some(artifacts) = self.dependencies.get() {
+            return Ok(artifacts);
+        }
+        let artifacts = if self.manifest.dependencies.is_empty() {
+            Vec::new()
+        } else {
+            let dependencies = self
+                .manifest
+                .dependencies
+                .iter()
+                .map(|coordinate| Dependency::parse(coordinate))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut repositories = Repository::maven_local().into_iter().collect::<Vec<_>>();
+            for location in &self.manifest.maven_repositories {
+                repositories.push(match Repository::new(location)? {
+                    Repository::Local(dir) => {
+                        Repository::Local(self.crate_relative(Some(&dir)).unwrap())
+                    }
+                    repository => repository,
+                });
+            }
+            // 从 HTTP 镜像下载的文件在同一目标目录的所有配置文件之间共享
+            let download_dir = dunce::simplified(self.cmd.target_dir()).join("maven");
+            let mut resolver = Resolver::new(repositories, download_dir);
+            let crate_dir = self.cmd.manifest().parent().expect("invalid manifest path");
+            lockfile::resolve(&mut resolver, &dependencies, &crate_dir.join(LOCKFILE))?
+        };
+        Ok(self.dependencies.get_or_init(|| artifacts))
+    }
+
+    /// 解压 `aars` 与 `dependencies` 中的 Android 库
+    fn aars(&self) -> Result<&[Aar], Error> {
+        if let Some(aars) = self.aars.get() {
+            return Ok(aars);
+        }
+        let aar_dir = self.build_dir.join("aar");
+        let mut aars = Vec::new();
+        // 解压目录以库的绝对路径或 Maven 坐标区分，同名的库不会互相覆盖
+        for path in self.crate_relative_all(&self.manifest.aars) {
+            let name = path
+                .file_stem()
+                .expect("invalid aar path")
+                .to_string_lossy();
+            let key = dunce::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            let dir = Aar::unpack_dir(&aar_dir, &name, &key.to_string_lossy());
+            aars.push(Aar::unpack(&path, &dir)?);
+        }
+        for artifact in self.dependencies()? {
+            if artifact.packaging == "aar" {
+                let dependency = &artifact.dependency;
+                let key = format!(
+                    "{}:{}:{}",
+                    dependency.group, dependency.artifact, dependency.version
+                );
+                let dir = Aar::unpack_dir(&aar_dir, &dependency.artifact, &key);
+                aars.push(Aar::unpack(&artifact.path, &dir)?);
+            }
+        }
+        Ok(self.aars.get_or_init(|| aars))
+    }
+
+    /// `dependencies` 中打包为 jar 的依赖与 Android 库中的 jar
+    fn dependency_jars(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut jars = self
+            .dependencies()?
+            .iter()
+            .filter(|artifact| artifact.packaging == "jar")
+            .map(|artifact| artifact.path.clone())
+            .collect::<Vec<_>>();
+        for aar in self.aars()? {
+            jars.extend(aar.jars()?);
+        }
+        Ok(jars)
+    }
+
     pub fn compile_java_sources<P>(&self, java_sources: P) -> Result<(), Error>
     where
         P: AsRef<Path>,
@@ -256,17 +397,14 @@ impl<'a> ApkBuilder<'a> {
                 .join("bin")
                 .join("javac"),
         );
+        // 保留参数名称，供 `jni-stubs` 使用
         javac
             .stdin(Stdio::null())
+            .arg("-parameters")
             .arg("-d")
             .arg(&self.classes_dir)
             .arg("-classpath")
-            .arg(format!(
-                "{}{}{}",
-                android_jar.display(),
-                Self::PATH_SEPARATOR,
-                self.classes_dir.display()
-            ));
+            .arg(self.compile_classpath(&android_jar)?);
 
         // 添加所有Java源文件
         let mut has_java_files = false;
@@ -312,12 +450,7 @@ impl<'a> ApkBuilder<'a> {
             .arg("-d")
             .arg(&self.classes_dir)
             .arg("-classpath")
-            .arg(format!(
-                "{}{}{}",
-                android_jar.display(),
-                Self::PATH_SEPARATOR,
-                self.classes_dir.display()
-            ));
+            .arg(self.compile_classpath(&android_jar)?);
 
         // 添加所有Kotlin源文件
         let mut has_kotlin_files = false;
@@ -438,11 +571,9 @@ impl<'a> ApkBuilder<'a> {
             .arg(&self.classes_dir)
             .arg("-classpath")
             .arg(format!(
-                "{}{}{}{}",
-                Self::PATH_SEPARATOR,
-                android_jar.display(),
+                "{}{}",
                 Self::PATH_SEPARATOR,
-                self.classes_dir.display()
+                self.compile_classpath(&android_jar)?
             ));
 
         // 添加所有Scala源文件
@@ -492,12 +623,7 @@ impl<'a> ApkBuilder<'a> {
             .arg("-d")
             .arg(&self.classes_dir)
             .arg("-classpath")
-            .arg(format!(
-                "{}{}{}",
-                android_jar.display(),
-                Self::PATH_SEPARATOR,
-                self.classes_dir.display()
-            ))
+            .arg(self.compile_classpath(&android_jar)?)
             .arg("--compile-static");
 
         // 添加所有Groovy源文件
@@ -523,36 +649,101 @@ impl<'a> ApkBuilder<'a> {
         Ok(())
     }
 
+    /// 在进程内打包 `classes_dir` 与依赖 jar 中的类，条目按路径排序且使用固定的时间戳，
+    /// 使 jar（以及由其生成的 dex）可重现。与编译出的类同名的依赖类被忽略
     pub fn create_jar<P>(&self, path: P) -> Result<(), Error>
     where
         P: AsRef<Path>,
     {
-        // 使用 java_home 查找 jar.exe 创建 jar 文件
-        let mut jar = Command::new(
-            self.java_home
-                .as_ref()
-                .ok_or(Error::JdkNotFound)?
-                .join("bin")
-                .join(if cfg!(windows) { "jar.exe" } else { "jar" }),
-        );
-        jar.stdin(Stdio::null())
-            .arg("--create")
-            .arg("--file")
-            .arg(path.as_ref())
-            .arg("-C")
-            .arg(&self.classes_dir)
-            .arg(".");
+        let mut files = Vec::new();
+        Self::collect_files_recursive(&self.classes_dir, &mut files)?;
+
+        let mut entries = BTreeMap::new();
+        for file in files {
+            let name = file
+                .strip_prefix(&self.classes_dir)
+                .unwrap()
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            entries.insert(name, read(&file)?);
+        }
+        for dependency in self.dependency_jars()? {
+            let archive = ZipArchive::open(&dependency)?;
+            for entry in archive.entries() {
+                // 多版本 jar 中 `META-INF/versions` 下的类与 `module-info.class` 不能转换为 dex
+                if !entry.name.ends_with(".class")
+                    || entry.name.starts_with("META-INF/")
+                    || entry.name.ends_with("module-info.class")
+                    || entries.contains_key(&entry.name)
+                {
+                    continue;
+                }
+                entries.insert(entry.name.clone(), archive.read(entry)?);
+            }
+        }
 
-        let output = jar.output()?;
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!("jar failed:\n{stderr}");
-            return Err(Error::CmdFailed(Box::new(jar)));
+        let mut jar = ZipWriter::new();
+        for (name, data) in entries {
+            jar.add_entry(&name, &data, true, 1)?;
         }
+        write(path.as_ref(), jar.finish()?)?;
 
         Ok(())
     }
 
+    fn collect_files_recursive(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+        for entry in read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                Self::collect_files_recursive(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录 cargo-apk2 编译到 `classes_dir` 中的文件，下次构建前只删除这些文件
+    fn compiled_classes_record(&self) -> PathBuf {
+        self.build_dir.join("compiled-classes.txt")
+    }
+
+    /// `classes_dir` 中的所有文件
+    fn class_dir_files(&self) -> Result<BTreeSet<PathBuf>, Error> {
+        let mut files = Vec::new();
+        if self.classes_dir.exists() {
+            Self::collect_files_recursive(&self.classes_dir, &mut files)?;
+        }
+        Ok(files.into_iter().collect())
+    }
+
+    /// 将 `existing` 之外新出现在 `classes_dir` 中的文件追加到编译记录
+    fn record_compiled_classes(&self, existing: &BTreeSet<PathBuf>) -> Result<(), Error> {
+        let path = self.compiled_classes_record();
+        let mut record = read_to_string(&path).unwrap_or_default();
+        for file in self.class_dir_files()?.difference(existing) {
+            let name = file.strip_prefix(&self.classes_dir).unwrap();
+            record.push_str(&name.to_string_lossy());
+            record.push('\n');
+        }
+        write(&path, record).map_err(|e| NdkError::IoPathError(path, e))?;
+        Ok(())
+    }
+
+    /// 删除上次构建编译到 `classes_dir` 中的文件。构建脚本也会向其中写入类，
+    /// 而 cargo 不一定重新运行构建脚本，因此不能清空整个目录
+    fn remove_compiled_classes(&self) {
+        let path = self.compiled_classes_record();
+        if let Ok(record) = read_to_string(&path) {
+            for name in record.lines() {
+                let _ = remove_file(self.classes_dir.join(name));
+            }
+        }
+        let _ = remove_file(path);
+    }
+
     // 递归检查是否有 class 文件
     fn has_class_files_recursive(dir: &Path) -> Result<bool, NdkError> {
         if !dir.exists() {
@@ -574,9 +765,32 @@ impl<'a> ApkBuilder<'a> {
         Ok(false)
     }
 
-    //noinspection SpellCheckingInspection
-    pub fn build(&self, artifact: &Artifact) -> Result<Apk, Error> {
-        // 设置工件特定的清单默认值。
+    /// 将 `[package.metadata.android]` 中相对于 `Cargo.toml` 的路径转换为绝对路径
+    fn crate_relative(&self, path: Option<&PathBuf>) -> Option<PathBuf> {
+        let crate_path = self.cmd.manifest().parent().expect("invalid manifest path");
+        path.map(|path| dunce::simplified(&crate_path.join(path)).to_owned())
+    }
+
+    fn crate_relative_all(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        paths
+            .iter()
+            .filter_map(|path| self.crate_relative(Some(path)))
+            .collect()
+    }
+
+    /// APK 文件名（不含 ABI 后缀与扩展名），默认为工件名称
+    fn apk_name(&self, artifact: &Artifact) -> String {
+        self.manifest
+            .apk_name
+            .clone()
+            .unwrap_or_else(|| artifact.name.to_string())
+    }
+
+    /// 设置工件特定的清单默认值，并生成打包配置。
+    ///
+    /// `split` 为 `Some` 时生成该 ABI 的拆分 APK 配置：文件名带有 ABI 后缀，
+    /// 版本号的最高字节为 [`Target`] 的值，使设备优先安装与其 ABI 匹配的 APK。
+    fn apk_config(&self, artifact: &Artifact, split: Option<Target>) -> ApkConfig {
         let mut manifest = self.manifest.android_manifest.clone();
         let apk_package = &mut manifest.package;
 
@@ -593,87 +807,191 @@ impl<'a> ApkBuilder<'a> {
             manifest.application.label = artifact.name.to_string();
         }
 
-        let crate_path = self.cmd.manifest().parent().expect("invalid manifest path");
-
-        let is_debug_profile = *self.cmd.profile() == Profile::Dev;
-
-        let assets = self
-            .manifest
-            .assets
-            .as_ref()
-            .map(|assets| dunce::simplified(&crate_path.join(assets)).to_owned());
-        let resources = self
-            .manifest
-            .resources
-            .as_ref()
-            .map(|res| dunce::simplified(&crate_path.join(res)).to_owned());
-        let java_sources = self
-            .manifest
-            .java_sources
-            .as_ref()
-            .map(|src| dunce::simplified(&crate_path.join(src)).to_owned());
-        let kotlin_sources = self
-            .manifest
-            .kotlin_sources
-            .as_ref()
-            .map(|src| dunce::simplified(&crate_path.join(src)).to_owned());
-        let scala_sources = self
-            .manifest
-            .scala_sources
-            .as_ref()
-            .map(|src| dunce::simplified(&crate_path.join(src)).to_owned());
-        let groovy_sources = self
-            .manifest
-            .groovy_sources
-            .as_ref()
-            .map(|src| dunce::simplified(&crate_path.join(src)).to_owned());
-        let runtime_libs = self
-            .manifest
-            .runtime_libs
-            .as_ref()
-            .map(|libs| dunce::simplified(&crate_path.join(libs)).to_owned());
-        let manifest_override = self
-            .manifest
-            .manifest_override
-            .as_ref()
-            .map(|xml| dunce::simplified(&crate_path.join(xml)).to_owned());
-        let apk_name = self
-            .manifest
-            .apk_name
-            .clone()
-            .unwrap_or_else(|| artifact.name.to_string());
-        let apk_package = apk_package.to_owned();
-        let use_aapt2 = self.manifest.use_aapt2.unwrap_or(true);
-        let build_dir = self.build_dir.join(artifact.build_dir());
-        let gen_java_dir = build_dir.join("java");
+        let mut apk_name = self.apk_name(artifact);
+        if let Some(target) = split {
+            apk_name = format!("{}-{}", apk_name, target.android_abi());
+            manifest.version_code = Some(self.version.to_code(target as u8));
+        } else if self.manifest.abi_splits {
+            // 通用 APK 使用最小的 apk id，确保拆分 APK 的版本号总是更高
+            apk_name = format!("{}-universal", apk_name);
+            manifest.version_code = Some(self.version.to_code(0));
+        }
 
-        let config = ApkConfig {
+        ApkConfig {
             ndk: self.ndk.clone(),
-            build_dir,
-            apk_name: apk_name.clone(),
-            use_aapt2,
-            assets: assets.clone(),
-            resources: resources.clone(),
+            build_dir: self.build_dir.join(artifact.build_dir()),
+            apk_name,
+            use_aapt2: self.manifest.use_aapt2.unwrap_or(true),
+            assets: self.crate_relative_all(&self.manifest.assets),
+            resources: self.crate_relative_all(&self.manifest.resources),
+            resource_ids: None,
             manifest,
-            disable_aapt_compression: is_debug_profile,
+            disable_aapt_compression: *self.cmd.profile() == Profile::Dev,
+            no_compress: self.manifest.no_compress.clone(),
+            bundletool_version: self.manifest.bundletool_version.clone(),
             strip: self.manifest.strip,
             reverse_port_forward: self.manifest.reverse_port_forward.clone(),
-            manifest_override: manifest_override.clone(),
-        };
-        let mut apk = config.create_apk(&gen_java_dir)?;
+            manifest_override: self.crate_relative(self.manifest.manifest_override.as_ref()),
+            use_apksigner: self.manifest.use_apksigner,
+            // 启用 `use_r8` 时，发布构建使用 R8 移除未使用的代码（包括打包的 Kotlin 标准库）并混淆名称；
+            // 通过反射访问的类需要额外的保留规则，因此默认不启用
+            r8: (*self.cmd.profile() != Profile::Dev && self.manifest.use_r8).then(|| R8Config {
+                proguard_rules: self.crate_relative_all(&self.manifest.proguard_rules),
+            }),
+            // 调试构建只重新转换变化的类
+            incremental_dex: *self.cmd.profile() == Profile::Dev,
+            core_library_desugaring: self.manifest.core_library_desugaring.then(|| {
+                CoreLibraryDesugaring {
+                    runtime_jar: self.crate_relative(self.manifest.desugar_jdk_libs.as_ref()),
+                    configuration: self
+                        .crate_relative(self.manifest.desugar_jdk_libs_configuration.as_ref()),
+                }
+            }),
+            // 由 `prepare_resources` 根据解压的 Android 库设置
+            extra_packages: Vec::new(),
+            library_manifests: Vec::new(),
+        }
+    }
 
-        // 创建临时目录用于编译Java/Kotlin/Scala/Groovy
+    /// 构建脚本为 `target` 生成 assets（`assets` 子目录）与资源（`res` 子目录）的目录。
+    ///
+    /// 该目录位于 cargo 的输出目录中并在多次构建之间保留，因为 cargo 不会重新运行没有变化的构建脚本。
+    fn generated_dir(&self, target: Target) -> PathBuf {
+        self.cmd
+            .build_dir(Some(target.rust_triple()))
+            .join("apk")
+            .join("generated")
+    }
+
+    /// 将构建脚本生成的非空 assets 与资源目录追加到 `config` 中，覆盖用户配置的同名文件与资源；
+    /// `split` 为 `Some` 时只添加该目标的目录
+    fn add_generated_dirs(&self, config: &mut ApkConfig, split: Option<Target>) {
+        for target in self
+            .build_targets
+            .iter()
+            .filter(|target| split.is_none_or(|split| split == **target))
+        {
+            let generated_dir = self.generated_dir(*target);
+            for (name, dirs) in [
+                ("assets", &mut config.assets),
+                ("res", &mut config.resources),
+            ] {
+                let dir = generated_dir.join(name);
+                if read_dir(&dir).is_ok_and(|mut entries| entries.next().is_some()) {
+                    dirs.push(dir);
+                }
+            }
+        }
+    }
+
+    /// 在 `build_dir` 中生成 `icon` 与 `strings` 配置的资源，返回资源目录
+    fn metadata_resources(&self, build_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut dirs = Vec::new();
+        if let Some(icon) = &self.manifest.icon {
+            let crate_path = self.cmd.manifest().parent().expect("invalid manifest path");
+            let res_dir = build_dir.join("icon");
+            icon.write_resources(crate_path, &res_dir)?;
+            dirs.push(res_dir);
+        }
+        if !self.manifest.strings.is_empty() {
+            let strings = LocalizedStrings {
+                default_locale: self.manifest.default_locale.clone(),
+                strings: self.manifest.strings.clone(),
+            };
+            let res_dir = build_dir.join("strings");
+            strings.write_resources(&res_dir)?;
+            dirs.push(res_dir);
+        }
+        Ok(dirs)
+    }
+
+    /// 在编译动态库之前添加由配置生成的资源，并预先链接一次以确定资源 ID，生成 `R.rs` 并返回其路径；
+    /// 同时生成并编译 `R.java`，使构建脚本能在 `CARGO_APK2_CLASSES_DIR` 中找到 `R` 类。
+    ///
+    /// 之后链接 APK 时通过 `--stable-ids` 保持这些 ID 不变，构建脚本生成的资源不会改变它们。
+    /// 只有使用 aapt2 且有资源时才会生成 `R.rs`。
+    fn prepare_resources(&self, configs: &mut [ApkConfig]) -> Result<Option<PathBuf>, Error> {
+        let build_dir = configs[0].build_dir.clone();
+        // 每次构建都重新生成 `R.java` 并编译所有类，不留下已删除的资源或源文件对应的类
+        let gen_java_dir = build_dir.join("java");
+        let _ = remove_dir_all(&gen_java_dir);
+        self.remove_compiled_classes();
         create_dir_all(&self.classes_dir)?;
 
+        let metadata_dirs = self.metadata_resources(&build_dir)?;
+        let aars = self.aars()?;
+        for config in configs.iter_mut() {
+            // 由配置生成的资源作为最底层，用户的资源可以覆盖它们
+            config.resources.splice(0..0, metadata_dirs.iter().cloned());
+            // 库的资源与资产位于应用的之下
+            config
+                .resources
+                .splice(0..0, aars.iter().filter_map(Aar::res_dir));
+            config
+                .assets
+                .splice(0..0, aars.iter().filter_map(Aar::assets_dir));
+            config.extra_packages = aars
+                .iter()
+                .filter(|aar| aar.res_dir().is_some())
+                .map(|aar| aar.package.clone())
+                .collect();
+            config.library_manifests = aars.iter().map(Aar::manifest).collect();
+            if let Some(r8) = &mut config.r8 {
+                r8.proguard_rules
+                    .extend(aars.iter().filter_map(Aar::proguard_rules));
+            }
+        }
+        if configs[0].resources.is_empty() {
+            return Ok(None);
+        }
+
+        let r_rs = if configs[0].use_aapt2 {
+            let ids = build_dir.join("resource_ids.txt");
+            configs[0].emit_resource_ids(&gen_java_dir, &ids)?;
+            let r_rs = build_dir.join("R.rs");
+            ResourceIds::read(&ids)?.write_rust(&r_rs)?;
+            for config in configs {
+                config.resource_ids = Some(ids.clone());
+            }
+            Some(r_rs)
+        } else {
+            // aapt 不能固定资源 ID，构建脚本生成的资源可能改变它们；链接 APK 后会重新编译 `R.java`
+            configs[0].create_apk(&gen_java_dir)?;
+            None
+        };
         if gen_java_dir.exists() {
+            println!("Compiling R.java...");
+            let existing = self.class_dir_files()?;
             self.compile_java_sources(&gen_java_dir)?;
+            self.record_compiled_classes(&existing)?;
         }
+        Ok(r_rs)
+    }
 
-        // 编译动态库
+    /// 编译各目标的动态库，构建脚本可以在此期间向 [`Self::generated_dir`] 写入文件
+    //noinspection SpellCheckingInspection
+    fn compile_libs(
+        &self,
+        artifact: &Artifact,
+        config: &ApkConfig,
+        r_rs: Option<&Path>,
+    ) -> Result<Vec<TargetLibs>, Error> {
+        let apk_name = self.apk_name(artifact);
+        let apk_package = config.manifest.package.clone();
+        let assets = self.crate_relative_all(&self.manifest.assets);
+        let resources = self.crate_relative_all(&self.manifest.resources);
+        let runtime_libs = self.crate_relative(self.manifest.runtime_libs.as_ref());
+
+        let mut libs = Vec::new();
         for target in &self.build_targets {
             let triple = target.rust_triple();
             let build_dir = self.cmd.build_dir(Some(triple));
             let artifact = self.cmd.artifact(artifact, Some(triple), CrateType::Cdylib);
+            let generated_dir = self.generated_dir(*target);
+            let generated_assets = generated_dir.join("assets");
+            let generated_resources = generated_dir.join("res");
+            create_dir_all(&generated_assets)?;
+            create_dir_all(&generated_resources)?;
 
             let mut cargo = cargo_ndk(
                 &self.ndk,
@@ -684,16 +1002,22 @@ impl<'a> ApkBuilder<'a> {
             cargo.env("CARGO_APK2_APK_NAME", &apk_name);
             cargo.env("CARGO_APK2_PACKAGE", &apk_package);
             cargo.env("CARGO_APK2_ARTIFACT", &artifact);
-            if let Some(p) = assets.as_ref()
-                && let Some(p) = p.to_str()
+            // 有多个目录时以平台的路径分隔符连接，可用 `std::env::split_paths` 拆分
+            if !assets.is_empty()
+                && let Ok(p) = join_paths(&assets)
             {
                 cargo.env("CARGO_APK2_ASSETS_DIR", p);
             }
-            if let Some(p) = resources.as_ref()
-                && let Some(p) = p.to_str()
+            if !resources.is_empty()
+                && let Ok(p) = join_paths(&resources)
             {
                 cargo.env("CARGO_APK2_RESOURCES_DIR", p);
             }
+            cargo.env("CARGO_APK2_GENERATED_ASSETS_DIR", &generated_assets);
+            cargo.env("CARGO_APK2_GENERATED_RESOURCES_DIR", &generated_resources);
+            if let Some(p) = r_rs {
+                cargo.env("CARGO_APK2_R_RS", p);
+            }
             if let Some(p) = self.classes_dir.to_str() {
                 cargo.env("CARGO_APK2_CLASSES_DIR", p);
             }
@@ -735,10 +1059,9 @@ impl<'a> ApkBuilder<'a> {
             {
                 cargo.env("CARGO_APK2_PLATFORM_DIR", p);
             }
-            cargo.env(
-                "CARGO_APK2_BUILD_TOOLS_VERSION",
-                self.ndk.build_tools_version(),
-            );
+            if let Some(version) = self.ndk.build_tools_version() {
+                cargo.env("CARGO_APK2_BUILD_TOOLS_VERSION", version);
+            }
             cargo.env(
                 "CARGO_APK2_MIN_SDK_VERSION",
                 self.min_sdk_version().to_string(),
@@ -761,17 +1084,7 @@ impl<'a> ApkBuilder<'a> {
             let mut libs_search_paths =
                 get_libs_search_paths(self.cmd.target_dir(), triple, self.cmd.profile().as_ref())?;
             libs_search_paths.push(build_dir.join("deps"));
-
-            let libs_search_paths = libs_search_paths
-                .iter()
-                .map(|path| path.as_path())
-                .collect::<Vec<_>>();
-
-            apk.add_lib_recursively(&artifact, *target, libs_search_paths.as_slice())?;
-
-            if let Some(runtime_libs) = &runtime_libs {
-                apk.add_runtime_libs(runtime_libs, *target, libs_search_paths.as_slice())?;
-            }
+            let mut libcxx_dir = None;
 
             // === [FLAG] LEGACY FSEEK FIX ===
             // 64-bit fseek and ftell breaks builds for 32-bit architectures,
@@ -829,7 +1142,7 @@ impl<'a> ApkBuilder<'a> {
                     // Pass the parent directory (libcxx_build_dir).
                     // ndk-build2 automatically appends the ABI name to the path.
                     // So passing '.../libcxx' makes it look in '.../libcxx/arm64-v8a'.
-                    apk.add_runtime_libs(&libcxx_build_dir, *target, libs_search_paths.as_slice())?;
+                    libcxx_dir = Some(libcxx_build_dir);
 
                     println!(
                         "Included libc++_shared.so for {} from {:?}",
@@ -843,12 +1156,39 @@ impl<'a> ApkBuilder<'a> {
                     );
                 }
             }
+
+            libs.push(TargetLibs {
+                target: *target,
+                artifact,
+                search_paths: libs_search_paths,
+                libcxx_dir,
+            });
         }
 
+        Ok(libs)
+    }
+
+    /// 编译 JVM 源文件（包括链接 APK 时重新生成的 `R.java`，其中含有构建脚本生成的资源），
+    /// 返回 `classes.jar`（如果有）
+    //noinspection SpellCheckingInspection
+    fn compile_classes(&self, config: &ApkConfig) -> Result<Option<PathBuf>, Error> {
+        let gen_java_dir = config.build_dir.join("java");
+        let java_sources = self.crate_relative(self.manifest.java_sources.as_ref());
+        let kotlin_sources = self.crate_relative(self.manifest.kotlin_sources.as_ref());
+        let scala_sources = self.crate_relative(self.manifest.scala_sources.as_ref());
+        let groovy_sources = self.crate_relative(self.manifest.groovy_sources.as_ref());
+
+        // 创建临时目录用于编译Java/Kotlin/Scala/Groovy
+        create_dir_all(&self.classes_dir)?;
+        let existing = self.class_dir_files()?;
+
         // 编译Java源文件
         if gen_java_dir.exists() || java_sources.is_some() {
             println!("Compiling Java sources...");
         }
+        if gen_java_dir.exists() {
+            self.compile_java_sources(&gen_java_dir)?;
+        }
         if let Some(java_sources) = java_sources {
             self.compile_java_sources(java_sources)?;
         }
@@ -871,11 +1211,82 @@ impl<'a> ApkBuilder<'a> {
             self.compile_groovy_sources(groovy_sources)?;
         }
 
-        let profile_name = match self.cmd.profile() {
-            Profile::Dev => "dev",
-            Profile::Release => "release",
-            Profile::Custom(c) => c.as_str(),
-        };
+        self.record_compiled_classes(&existing)?;
+
+        // 创建 jar 文件
+        let combined_jar_file = self.build_dir.join("classes.jar");
+
+        if Self::has_class_files_recursive(&self.classes_dir)?
+            || !self.dependency_jars()?.is_empty()
+        {
+            println!("Creating classes.jar from compiled Java sources...");
+            self.create_jar(&combined_jar_file)?;
+            return Ok(Some(combined_jar_file));
+        }
+
+        Ok(None)
+    }
+
+    /// 将编译产物添加到 APK 或 App Bundle 中；`split` 为 `Some` 时只添加该目标的动态库
+    fn add_artifacts(
+        &self,
+        apk: &mut UnalignedApk,
+        libs: &[TargetLibs],
+        jar: Option<&Path>,
+        split: Option<Target>,
+    ) -> Result<(), Error> {
+        let runtime_libs = self.crate_relative(self.manifest.runtime_libs.as_ref());
+
+        for libs in libs
+            .iter()
+            .filter(|libs| split.is_none_or(|target| target == libs.target))
+        {
+            // 库中的动态库可能依赖同一个库中的其他动态库
+            let aar_jni_dirs = self
+                .aars()?
+                .iter()
+                .filter_map(Aar::jni_dir)
+                .filter(|dir| dir.join(libs.target.android_abi()).is_dir())
+                .collect::<Vec<_>>();
+            let aar_search_paths = aar_jni_dirs
+                .iter()
+                .map(|dir| dir.join(libs.target.android_abi()))
+                .collect::<Vec<_>>();
+            let search_paths = libs
+                .search_paths
+                .iter()
+                .chain(&aar_search_paths)
+                .map(|path| path.as_path())
+                .collect::<Vec<_>>();
+
+            apk.add_lib_recursively(&libs.artifact, libs.target, &search_paths)?;
+
+            for jni_dir in &aar_jni_dirs {
+                apk.add_runtime_libs(jni_dir, libs.target, &search_paths)?;
+            }
+
+            if let Some(runtime_libs) = &runtime_libs {
+                apk.add_runtime_libs(runtime_libs, libs.target, &search_paths)?;
+            }
+
+            if let Some(libcxx_dir) = &libs.libcxx_dir {
+                apk.add_runtime_libs(libcxx_dir, libs.target, &search_paths)?;
+            }
+        }
+
+        // 将 jar 转换为 dex 并添加到 APK
+        if let Some(jar) = jar {
+            apk.put_jar(jar)?;
+        }
+
+        Ok(())
+    }
+
+    /// 按配置文件选择签名密钥：环境变量优先，其次是清单中的 `signing`，`dev` 配置文件回退到调试密钥
+    fn signing_key(&self) -> Result<Key, Error> {
+        let crate_path = self.cmd.manifest().parent().expect("invalid manifest path");
+        let is_debug_profile = *self.cmd.profile() == Profile::Dev;
+        let profile_name = self.profile_name();
 
         let keystore_env = format!(
             "CARGO_APK_{}_KEYSTORE",
@@ -887,7 +1298,11 @@ impl<'a> ApkBuilder<'a> {
         let password = var(&password_env).ok();
 
         let signing_key = match (path, password) {
-            (Ok(path), Some(password)) => Key { path, password },
+            (Ok(path), Some(password)) => Key {
+                path,
+                password,
+                ..Default::default()
+            },
             (Ok(path), None) if is_debug_profile => {
                 eprintln!(
                     "{} not specified, falling back to default password",
@@ -896,6 +1311,7 @@ impl<'a> ApkBuilder<'a> {
                 Key {
                     path,
                     password: ndk_build2::ndk::DEFAULT_DEV_KEYSTORE_PASSWORD.to_owned(),
+                    ..Default::default()
                 }
             }
             (Ok(path), None) => {
@@ -912,6 +1328,9 @@ impl<'a> ApkBuilder<'a> {
                     Key {
                         path: crate_path.join(&msk.path),
                         password: msk.keystore_password.clone(),
+                        alias: msk.key_alias.clone(),
+                        key_password: msk.key_password.clone(),
+                        keystore_type: msk.keystore_type,
                     }
                 } else if is_debug_profile {
                     self.ndk.debug_key()?
@@ -921,24 +1340,259 @@ impl<'a> ApkBuilder<'a> {
             }
         };
 
-        // 创建 jar 文件并添加到 APK
-        let combined_jar_file = self.build_dir.join("classes.jar");
+        Ok(signing_key)
+    }
 
-        if Self::has_class_files_recursive(&self.classes_dir)? {
-            println!("Creating classes.jar from compiled Java sources...");
-            self.create_jar(&combined_jar_file)?;
-            // 将 jar 转换为 dex 并添加到 APK
-            apk.put_jar(&combined_jar_file)?;
+    /// 当前配置文件的名称，用于查找 `signing` 等按配置文件区分的设置
+    fn profile_name(&self) -> &str {
+        match self.cmd.profile() {
+            Profile::Dev => "dev",
+            Profile::Release => "release",
+            Profile::Custom(c) => c.as_str(),
         }
+    }
 
-        let unsigned = apk.add_pending_libs_and_align()?;
+    /// 读取编译出的类中的 `native` 方法；当前配置文件不检查 JNI 绑定或没有编译任何类时返回 `None`
+    fn native_methods(&self, jar: Option<&Path>) -> Result<Option<BTreeSet<NativeMethod>>, Error> {
+        let check = self.manifest.jni_check.get(self.profile_name());
+        if jar.is_none() || check == Some(&JniCheck::Allow) {
+            return Ok(None);
+        }
+        Ok(Some(read_native_methods(&self.classes_dir)?))
+    }
+
+    /// 比较 `methods` 与 `path` 中动态库导出的 JNI 函数，按当前配置文件打印警告或返回错误
+    fn check_jni(&self, methods: &BTreeSet<NativeMethod>, path: &Path) -> Result<(), Error> {
+        let bindings = check_bindings(methods, path)?;
+        if bindings.is_ok() {
+            return Ok(());
+        }
+        let check = self.manifest.jni_check.get(self.profile_name());
+        if check == Some(&JniCheck::Deny) {
+            return Err(Error::JniBindings {
+                path: path.into(),
+                problems: bindings.problems().cloned().collect(),
+            });
+        }
+        for problem in bindings.problems() {
+            eprintln!("Warning: {problem}");
+        }
+        Ok(())
+    }
+
+    /// 编译并签名 APK，返回每个 APK 及其包含的 ABI（`None` 表示包含所有 ABI）。
+    ///
+    /// 启用 `abi_splits` 时为每个目标生成一个 APK，`universal_apk` 决定是否额外生成通用 APK。
+    //noinspection SpellCheckingInspection
+    pub fn build(&self, artifact: &Artifact) -> Result<Vec<(Option<Target>, Apk)>, Error> {
+        let splits = self.splits();
+        let mut configs = splits
+            .iter()
+            .map(|split| self.apk_config(artifact, *split))
+            .collect::<Vec<_>>();
+        let r_rs = self.prepare_resources(&mut configs)?;
+        let libs = self.compile_libs(artifact, &configs[0], r_rs.as_deref())?;
+        // APK 的资源在构建脚本运行之后才链接，以包含它们生成的文件
+        for (config, split) in configs.iter_mut().zip(&splits) {
+            self.add_generated_dirs(config, *split);
+        }
+        let apks = configs
+            .iter()
+            .map(|config| config.create_apk(config.build_dir.join("java")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let jar = self.compile_classes(&configs[0])?;
+        let native_methods = self.native_methods(jar.as_deref())?;
+        let signing_key = self.signing_key()?;
+
+        let mut signed = Vec::with_capacity(apks.len());
+        // 所有 APK 共享打包目录，jar 只需转换一次。最后创建的 APK 的清单仍在打包目录中，
+        // 它是通用 APK 时还包含所有 ABI 的动态库，因此由它转换，R8 据此保留清单中的组件与
+        // 通过 JNI 访问的类；其余 APK 直接添加已经转换的 dex
+        for (split, mut apk) in splits.into_iter().zip(apks).rev() {
+            let reuse_dex = jar.is_some() && !signed.is_empty();
+            let dex_jar = jar.as_deref().filter(|_| !reuse_dex);
+            self.add_artifacts(&mut apk, &libs, dex_jar, split)?;
+            if reuse_dex {
+                apk.add_pending_dex()?;
+            }
+            let path = apk.config().apk();
+            let unsigned = apk.add_pending_libs_and_align()?;
+
+            println!(
+                "Signing `{}` with keystore `{}`",
+                path.display(),
+                signing_key.path.display()
+            );
+            let apk = unsigned.sign(signing_key.clone())?;
+            if let Some(methods) = &native_methods {
+                self.check_jni(methods, apk.path())?;
+            }
+            check_budget(&read_size(apk.path())?, &self.manifest.size_budget)?;
+            signed.push((split, apk));
+        }
+        signed.reverse();
+        Ok(signed)
+    }
+
+    /// [`Self::build`] 生成的 APK 的路径
+    pub fn apk_paths(&self, artifact: &Artifact) -> Vec<PathBuf> {
+        self.splits()
+            .into_iter()
+            .map(|split| self.apk_config(artifact, split).apk())
+            .collect()
+    }
+
+    /// 校验 [`Self::build`] 生成的 APK，清单中的包名与版本号须与 `Cargo.toml` 一致
+    pub fn verify(&self, artifact: &Artifact) -> Result<(), Error> {
+        let mut result = Ok(());
+        for split in self.splits() {
+            let config = self.apk_config(artifact, split);
+            let expected = ExpectedManifest {
+                package: config.manifest.package.clone(),
+                version_code: config.manifest.version_code,
+                version_name: config.manifest.version_name.clone(),
+            };
+            // 校验所有 APK 后再报告第一个失败
+            if let Err(e) = verify(&config.apk(), Some(&expected)) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// 打印 [`Self::build`] 生成的 APK 的空间占用并检查 `size_budget`。
+    ///
+    /// APK 中的动态库没有符号表时，使用 cargo 输出目录中未剥离的库按 crate 细分。
+    pub fn size(&self, artifact: &Artifact, json: bool) -> Result<(), Error> {
+        let mut result = Ok(());
+        for path in self.apk_paths(artifact) {
+            let mut info = read_size(&path)?;
+            for target in &self.build_targets {
+                let unstripped =
+                    self.cmd
+                        .artifact(artifact, Some(target.rust_triple()), CrateType::Cdylib);
+                let Some(file_name) = unstripped.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                if let Some(lib) = info
+                    .abis
+                    .get_mut(target.android_abi())
+                    .and_then(|abi| abi.libs.iter_mut().find(|lib| lib.name == file_name))
+                    && lib.crates.is_empty()
+                    && unstripped.exists()
+                {
+                    lib.attribute_symbols(&unstripped)?;
+                }
+            }
+            print_size(&info, json)?;
+            // 报告所有 APK 后再返回第一个超出预算的错误
+            if let Err(e) = check_budget(&info, &self.manifest.size_budget) {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+
+    /// 需要生成的 APK，`None` 表示包含所有 ABI 的 APK
+    fn splits(&self) -> Vec<Option<Target>> {
+        let mut splits = Vec::new();
+        if self.manifest.abi_splits {
+            splits.extend(self.build_targets.iter().copied().map(Some));
+        }
+        if !self.manifest.abi_splits || self.manifest.universal_apk {
+            splits.push(None);
+        }
+        splits
+    }
+
+    /// 从干净的打包目录连续构建两次，并逐字节比较生成的 APK。
+    ///
+    /// Rust 代码的编译结果由 cargo 缓存，因此这里检查的是打包（资源、dex、ZIP 与签名）是否可重现。
+    pub fn verify_reproducible(&self, artifact: &Artifact) -> Result<(), Error> {
+        let mut builds = Vec::new();
+        for round in 1..=2 {
+            println!("Reproducibility check: build {round} of 2");
+            let _ = remove_dir_all(self.build_dir.join(artifact.build_dir()));
+            let apks = self
+                .build(artifact)?
+                .into_iter()
+                .map(|(_, apk)| {
+                    let path = apk.path().to_owned();
+                    read(&path).map(|data| (path, data))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            builds.push(apks);
+        }
+
+        let second = builds.pop().unwrap();
+        for ((path, first), (_, second)) in builds.pop().unwrap().into_iter().zip(second) {
+            if first != second {
+                return Err(Error::NotReproducible {
+                    entries: differing_entries(first, second)?,
+                    path,
+                });
+            }
+            println!("`{}` is reproducible", path.display());
+        }
+        Ok(())
+    }
+
+    /// 编译 JVM 源文件，为其中的 `native` 方法生成 Rust 函数签名并写入 `output`
+    /// （默认为打包目录中的 `jni_stubs.rs`），返回写入的路径
+    pub fn jni_stubs(
+        &self,
+        artifact: &Artifact,
+        output: Option<PathBuf>,
+    ) -> Result<PathBuf, Error> {
+        let mut config = self.apk_config(artifact, None);
+        // 先链接资源生成 `R.java`，JVM 源文件可能引用它
+        self.prepare_resources(std::slice::from_mut(&mut config))?;
+        self.compile_classes(&config)?;
+        let methods = read_native_methods(&self.classes_dir)?;
+        println!("Found {} native method(s)", methods.len());
+        let output = output.unwrap_or_else(|| self.build_dir.join("jni_stubs.rs"));
+        write(&output, rust_stubs(&methods))
+            .map_err(|e| NdkError::IoPathError(output.clone(), e))?;
+        Ok(output)
+    }
+
+    /// 编译 APK，并从中选出适合安装到当前设备的一个
+    fn build_for_device(&self, artifact: &Artifact) -> Result<Apk, Error> {
+        let mut apks = self.build(artifact)?;
+        let index = if apks.len() > 1 {
+            let abi = self.ndk.detect_abi(self.device_serial.as_deref()).ok();
+            apks.iter()
+                .position(|(split, _)| abi.is_some() && *split == abi)
+                .or_else(|| apks.iter().position(|(split, _)| split.is_none()))
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        Ok(apks.swap_remove(index).1)
+    }
+
+    /// 生成用于发布到 Google Play 的 Android App Bundle（`.aab`），返回其路径
+    pub fn bundle(&self, artifact: &Artifact) -> Result<PathBuf, Error> {
+        let mut config = self.apk_config(artifact, None);
+        let r_rs = self.prepare_resources(std::slice::from_mut(&mut config))?;
+        let libs = self.compile_libs(artifact, &config, r_rs.as_deref())?;
+        self.add_generated_dirs(&mut config, None);
+        let mut bundle = config.create_bundle(config.build_dir.join("java"))?;
+        let jar = self.compile_classes(&config)?;
+        self.add_artifacts(&mut bundle, &libs, jar.as_deref(), None)?;
+        let signing_key = self.signing_key()?;
+        let unsigned = bundle.add_pending_libs_and_bundle()?;
 
         println!(
             "Signing `{}` with keystore `{}`",
-            config.apk().display(),
+            config.aab().display(),
             signing_key.path.display()
         );
-        Ok(unsigned.sign(signing_key)?)
+        let path = unsigned.sign(signing_key)?;
+        if let Some(methods) = self.native_methods(jar.as_deref())? {
+            self.check_jni(&methods, &path)?;
+        }
+        Ok(path)
     }
 
     pub fn run(
@@ -947,7 +1601,7 @@ impl<'a> ApkBuilder<'a> {
         no_logcat: bool,
         show_logcat_time: bool,
     ) -> Result<(), Error> {
-        let apk = self.build(artifact)?;
+        let apk = self.build_for_device(artifact)?;
         apk.reverse_port_forwarding(self.device_serial.as_deref())?;
         apk.install(self.device_serial.as_deref())?;
 
@@ -999,7 +1653,7 @@ impl<'a> ApkBuilder<'a> {
     }
 
     pub fn gdb(&self, artifact: &Artifact) -> Result<(), Error> {
-        let apk = self.build(artifact)?;
+        let apk = self.build_for_device(artifact)?;
         apk.install(self.device_serial.as_deref())?;
 
         let target_dir = self.build_dir.join(artifact.build_dir());
@@ -1063,3 +1717,41 @@ impl<'a> ApkBuilder<'a> {
             .unwrap_or(self.ndk.default_target_platform())
     }
 }
+
+/// 某个目标编译出的动态库，以及解析其依赖时使用的搜索路径
+struct TargetLibs {
+    target: Target,
+    artifact: PathBuf,
+    search_paths: Vec<PathBuf>,
+    libcxx_dir: Option<PathBuf>,
+}
+
+/// 列出两个 APK 中内容或元数据不同的条目
+fn differing_entries(first: Vec<u8>, second: Vec<u8>) -> Result<Vec<String>, NdkError> {
+    let first = ZipArchive::from_bytes(first)?;
+    let second = ZipArchive::from_bytes(second)?;
+    let mut names = first
+        .entries()
+        .iter()
+        .chain(second.entries())
+        .map(|entry| entry.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    let mut differing = names
+        .into_iter()
+        .filter(|name| match (first.entry(name), second.entry(name)) {
+            (Some(a), Some(b)) => {
+                a.method != b.method
+                    || a.header_offset() != b.header_offset()
+                    || first.raw_data(a) != second.raw_data(b)
+            }
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+    if differing.is_empty() {
+        // 条目完全相同时，差异只可能来自签名块或中央目录
+        differing.push("<APK Signing Block>".to_owned());
+    }
+    Ok(differing)
+}
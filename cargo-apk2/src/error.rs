@@ -44,6 +44,8 @@ pub enum Error {
     ScalaNotFound,
     #[error(transparent)]
     Io(#[from] IoError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
     #[error("Configure a release keystore via `[package.metadata.android.signing.{0}]`")]
     MissingReleaseKey(String),
     #[error("`workspace=false` is unsupported")]
@@ -56,6 +58,35 @@ pub enum Error {
     NoArtifactAvailable,
     #[error("Many artifacts are given ({0}), only support single artifact currently.")]
     OnlySupportSingleArtifact(String),
+    #[error("`{path:?}` is not reproducible, differing entries: {}", entries.join(", "))]
+    NotReproducible { path: PathBuf, entries: Vec<String> },
+    #[error("`{path:?}` failed verification with {errors} error(s)")]
+    VerificationFailed { path: PathBuf, errors: usize },
+    #[error("`{path:?}` has mismatched JNI bindings: {}", problems.join("; "))]
+    JniBindings {
+        path: PathBuf,
+        problems: Vec<String>,
+    },
+    #[error(
+        "Checksum of `{artifact}` in `{path:?}` does not match `Android.lock`: \
+        expected {expected}, found {found}"
+    )]
+    ChecksumMismatch {
+        artifact: String,
+        path: PathBuf,
+        expected: String,
+        found: String,
+    },
+    #[error("`{flag}` is required: set `{env}` or run the command in a terminal to be prompted")]
+    PasswordRequired {
+        flag: &'static str,
+        env: &'static str,
+    },
+    #[error("`{path:?}` exceeds its size budget: {}", violations.join("; "))]
+    SizeBudgetExceeded {
+        path: PathBuf,
+        violations: Vec<String>,
+    },
 }
 
 impl Error {
@@ -0,0 +1,146 @@
+use {
+    crate::error::Error,
+    ndk_build2::inspect::{ApkInfo, EntryInfo},
+    std::path::Path,
+};
+
+/// 打印 APK 的内容，`json` 为真时输出 JSON，否则输出便于阅读的表格
+pub fn inspect(path: &Path, json: bool) -> Result<(), Error> {
+    if !path.exists() {
+        return Err(Error::PathNotFound(path.into()));
+    }
+    let info = ApkInfo::read(path)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    println!(
+        "APK:      {} ({})",
+        info.path.display(),
+        format_size(info.size)
+    );
+    if let Some(package) = &info.package {
+        println!("Package:  {package}");
+    }
+    if info.version_name.is_some() || info.version_code.is_some() {
+        println!(
+            "Version:  {} (code {})",
+            info.version_name.as_deref().unwrap_or("-"),
+            info.version_code
+                .map_or_else(|| "-".into(), |code| code.to_string())
+        );
+    }
+    if info.min_sdk_version.is_some() || info.target_sdk_version.is_some() {
+        let sdk = |version: Option<i64>| version.map_or_else(|| "-".into(), |v| v.to_string());
+        println!(
+            "SDK:      min {}, target {}",
+            sdk(info.min_sdk_version),
+            sdk(info.target_sdk_version)
+        );
+    }
+
+    println!();
+    println!("Signers:");
+    if info.signers.is_empty() {
+        println!("  (unsigned)");
+    }
+    for signer in &info.signers {
+        println!("  {}", signer.schemes.join(", "));
+        println!("    SHA-256: {}", signer.sha256);
+        println!("    SHA-1:   {}", signer.sha1);
+    }
+
+    println!();
+    println!("Native libraries:");
+    if info.libs.is_empty() {
+        println!("  (none)");
+    }
+    for (abi, libs) in &info.libs {
+        println!("  {abi}");
+        let rows = libs
+            .iter()
+            .map(|lib| {
+                vec![
+                    lib.entry.name.rsplit('/').next().unwrap().to_owned(),
+                    format_size(lib.entry.size),
+                    format_size(lib.entry.compressed_size),
+                    lib.needed.join(", "),
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(&["Name", "Size", "Compressed", "Needed"], &rows);
+    }
+
+    println!();
+    println!("Dex files: {}", info.dex.len());
+    print_table(&["Name", "Size", "Compressed"], &entry_rows(&info.dex));
+
+    println!();
+    println!("Assets: {}", info.assets.len());
+    print_table(&["Name", "Size", "Compressed"], &entry_rows(&info.assets));
+
+    if let Some(manifest) = &info.manifest {
+        println!();
+        println!("Manifest:");
+        for line in manifest.lines() {
+            println!("  {line}");
+        }
+    }
+    Ok(())
+}
+
+fn entry_rows(entries: &[EntryInfo]) -> Vec<Vec<String>> {
+    entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.name.clone(),
+                format_size(entry.size),
+                format_size(entry.compressed_size),
+            ]
+        })
+        .collect()
+}
+
+/// 以四个空格缩进打印对齐的表格，没有行时不输出任何内容
+pub(crate) fn print_table(header: &[&str], rows: &[Vec<String>]) {
+    if rows.is_empty() {
+        return;
+    }
+    let mut widths = header.iter().map(|h| h.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("    {}", line.trim_end());
+    };
+    print_row(header.to_vec());
+    for row in rows {
+        print_row(row.iter().map(String::as_str).collect());
+    }
+}
+
+/// 以二进制单位格式化字节数，例如 `1.5 MiB`
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
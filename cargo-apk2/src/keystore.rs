@@ -0,0 +1,253 @@
+use {
+    crate::{error::Error, inspect::print_table},
+    ndk_build2::{
+        error::NdkError,
+        keystore::{KeyGenOptions, Keystore, KeystoreEntry},
+    },
+    std::{
+        env,
+        io::{self, IsTerminal, Write},
+        path::Path,
+    },
+};
+
+/// `keystore` 命令读取 keystore 密码的环境变量
+pub const KEYSTORE_PASSWORD_ENV: &str = "CARGO_APK2_KEYSTORE_PASSWORD";
+/// `keystore new` 读取密钥密码的环境变量
+pub const KEY_PASSWORD_ENV: &str = "CARGO_APK2_KEY_PASSWORD";
+
+/// 读取 `keystore` 命令的密码。命令行参数会出现在进程列表与 shell 历史中，因此依次使用环境变量 `env`、
+/// 参数 `arg`（同时打印警告）以及终端中不回显的输入（`prompt` 为 `None` 时不提示）；`confirm` 为真时要求输入两次。
+/// 都不可用时返回 `None`
+pub fn read_password(
+    env: &str,
+    flag: &str,
+    arg: Option<String>,
+    prompt: Option<&str>,
+    confirm: bool,
+) -> Result<Option<String>, Error> {
+    if let Ok(password) = env::var(env) {
+        return Ok(Some(password));
+    }
+    if let Some(password) = arg {
+        eprintln!(
+            "Warning: `{flag}` is visible to other users and processes, \
+            prefer setting `{env}` or entering the password when prompted"
+        );
+        return Ok(Some(password));
+    }
+    let Some(prompt) = prompt.filter(|_| io::stdin().is_terminal()) else {
+        return Ok(None);
+    };
+    loop {
+        let password = prompt_password(prompt)?;
+        if !confirm || prompt_password("Re-enter to confirm")? == password {
+            return Ok(Some(password));
+        }
+        eprintln!("The passwords don't match, try again.");
+    }
+}
+
+/// 在终端中提示输入一行，输入不回显
+fn prompt_password(prompt: &str) -> Result<String, Error> {
+    eprint!("{prompt}: ");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    let read = {
+        let _echo = EchoOff::new();
+        io::stdin().read_line(&mut line)
+    };
+    // 关闭回显时换行符也不会显示
+    eprintln!();
+    read?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+/// 关闭终端回显，离开作用域时恢复
+#[cfg(not(windows))]
+struct EchoOff;
+
+#[cfg(not(windows))]
+impl EchoOff {
+    fn new() -> Self {
+        Self::stty("-echo");
+        Self
+    }
+
+    // `stty` 作用于其 stdin，因此需要继承终端
+    fn stty(arg: &str) {
+        let _ = std::process::Command::new("stty").arg(arg).status();
+    }
+}
+
+#[cfg(not(windows))]
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        Self::stty("echo");
+    }
+}
+
+/// 关闭终端回显，离开作用域时恢复
+#[cfg(windows)]
+struct EchoOff(Option<u32>);
+
+#[cfg(windows)]
+mod console {
+    use std::ffi::c_void;
+
+    pub const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    pub const ENABLE_ECHO_INPUT: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        pub fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        pub fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        pub fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+}
+
+#[cfg(windows)]
+impl EchoOff {
+    fn new() -> Self {
+        // SAFETY: 只读写标准输入句柄的控制台模式
+        unsafe {
+            let handle = console::GetStdHandle(console::STD_INPUT_HANDLE);
+            let mut mode = 0;
+            if console::GetConsoleMode(handle, &mut mode) == 0 {
+                return Self(None);
+            }
+            console::SetConsoleMode(handle, mode & !console::ENABLE_ECHO_INPUT);
+            Self(Some(mode))
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for EchoOff {
+    fn drop(&mut self) {
+        if let Some(mode) = self.0 {
+            // SAFETY: 恢复 `new` 中读取的控制台模式
+            unsafe {
+                console::SetConsoleMode(console::GetStdHandle(console::STD_INPUT_HANDLE), mode);
+            }
+        }
+    }
+}
+
+/// 生成新的 keystore（或向已有 keystore 添加条目），并打印新条目与对应的签名配置
+pub fn new_keystore(options: &KeyGenOptions) -> Result<(), Error> {
+    options.generate()?;
+    let keystore = Keystore::load(&options.path, &options.keystore_password)?;
+    if let Some(entry) = keystore
+        .entries
+        .iter()
+        .find(|entry| entry.alias.eq_ignore_ascii_case(&options.alias))
+    {
+        println!();
+        print_entry(entry)?;
+    }
+
+    println!();
+    println!("To sign release builds with this key, add to Cargo.toml:");
+    println!();
+    println!("[package.metadata.android.signing.release]");
+    println!("path = {:?}", options.path.display().to_string());
+    println!("keystore_password = \"...\"");
+    println!("key_alias = {:?}", options.alias);
+    if options.key_password.is_some() {
+        println!("key_password = \"...\"");
+    }
+    println!("keystore_type = \"{}\"", options.keystore_type);
+    Ok(())
+}
+
+/// 列出 keystore 中的所有条目及其证书的 SHA-256 指纹
+pub fn list_keystore(path: &Path, password: &str) -> Result<(), Error> {
+    let keystore = load(path, password)?;
+    println!(
+        "Keystore: {} ({}, {} {})",
+        path.display(),
+        keystore.keystore_type,
+        keystore.entries.len(),
+        if keystore.entries.len() == 1 {
+            "entry"
+        } else {
+            "entries"
+        }
+    );
+    let rows = keystore
+        .entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.alias.clone(),
+                entry_type(entry).to_owned(),
+                entry
+                    .certificates
+                    .first()
+                    .map_or_else(String::new, |cert| cert.sha256_fingerprint()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(&["Alias", "Type", "SHA-256"], &rows);
+    Ok(())
+}
+
+/// 显示 keystore 条目的证书详情，未指定 `alias` 时显示所有条目
+pub fn show_keystore(path: &Path, password: &str, alias: Option<&str>) -> Result<(), Error> {
+    let keystore = load(path, password)?;
+    let entries = keystore
+        .entries
+        .iter()
+        .filter(|entry| alias.is_none_or(|alias| entry.alias.eq_ignore_ascii_case(alias)))
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        return Err(NdkError::KeyNotFound {
+            path: path.into(),
+            alias: alias.map(ToOwned::to_owned),
+        }
+        .into());
+    }
+    println!("Keystore: {} ({})", path.display(), keystore.keystore_type);
+    for entry in entries {
+        println!();
+        print_entry(entry)?;
+    }
+    Ok(())
+}
+
+fn load(path: &Path, password: &str) -> Result<Keystore, Error> {
+    if !path.exists() {
+        return Err(Error::PathNotFound(path.into()));
+    }
+    Ok(Keystore::load(path, password)?)
+}
+
+fn entry_type(entry: &KeystoreEntry) -> &'static str {
+    if entry.is_private_key() {
+        "PrivateKeyEntry"
+    } else {
+        "trustedCertEntry"
+    }
+}
+
+fn print_entry(entry: &KeystoreEntry) -> Result<(), Error> {
+    println!("Alias:       {}", entry.alias);
+    println!("Entry type:  {}", entry_type(entry));
+    if entry.certificates.len() > 1 {
+        println!("Chain:       {} certificates", entry.certificates.len());
+    }
+    let Some(certificate) = entry.certificates.first() else {
+        return Ok(());
+    };
+    let (not_before, not_after) = certificate.validity()?;
+    println!("Owner:       {}", certificate.subject_name());
+    println!("Issuer:      {}", certificate.issuer_name());
+    println!("Serial:      {}", certificate.serial_number_hex());
+    println!("Valid from:  {not_before} UTC");
+    println!("Valid until: {not_after} UTC");
+    println!("Key:         {}", certificate.key_algorithm()?);
+    println!("SHA-1:       {}", certificate.sha1_fingerprint());
+    println!("SHA-256:     {}", certificate.sha256_fingerprint());
+    Ok(())
+}
@@ -1,6 +1,18 @@
 mod apk;
 mod error;
+mod inspect;
+mod keystore;
+mod lockfile;
 mod manifest;
+mod size;
+mod verify;
 
 pub use apk::ApkBuilder;
 pub use error::Error;
+pub use inspect::inspect;
+pub use keystore::{
+    KEY_PASSWORD_ENV, KEYSTORE_PASSWORD_ENV, list_keystore, new_keystore, read_password,
+    show_keystore,
+};
+pub use size::size;
+pub use verify::verify;
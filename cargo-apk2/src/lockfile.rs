@@ -0,0 +1,149 @@
+//! `Android.lock`：记录 `dependencies` 解析出的所有 JVM 依赖的版本与 SHA-256，
+//! 声明的依赖没有变化时直接使用其中的版本，不再重新解析。
+
+use {
+    crate::error::Error,
+    ndk_build2::maven::{Dependency, ResolvedArtifact, Resolver},
+    serde::Deserialize,
+    std::{
+        fmt::Write as _,
+        fs::{read_to_string, write},
+        path::Path,
+    },
+    toml::Value,
+};
+
+/// 锁文件的名称，位于 `Cargo.toml` 旁边
+pub(crate) const LOCKFILE: &str = "Android.lock";
+
+#[derive(Debug, Default, Deserialize)]
+struct Lockfile {
+    /// 声明的依赖，按坐标排序
+    dependencies: Vec<String>,
+    #[serde(default, rename = "artifact")]
+    artifacts: Vec<LockedArtifact>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedArtifact {
+    /// `group:artifact`
+    name: String,
+    version: String,
+    packaging: String,
+    sha256: String,
+}
+
+/// 解析 `dependencies`。锁文件 `path` 记录的声明与之相同时使用其中的版本并校验文件的 SHA-256，
+/// 否则传递解析并重写锁文件
+pub(crate) fn resolve(
+    resolver: &mut Resolver,
+    dependencies: &[Dependency],
+    path: &Path,
+) -> Result<Vec<ResolvedArtifact>, Error> {
+    let mut declared = dependencies
+        .iter()
+        .map(|dependency| dependency.to_string())
+        .collect::<Vec<_>>();
+    declared.sort();
+
+    if let Ok(text) = read_to_string(path) {
+        let lockfile = toml::from_str::<Lockfile>(&text)?;
+        if lockfile.dependencies == declared {
+            let mut artifacts = Vec::new();
+            for locked in lockfile.artifacts {
+                let dependency = Dependency::parse(&format!("{}:{}", locked.name, locked.version))?;
+                let artifact = resolver.fetch(&dependency, &locked.packaging)?;
+                let sha256 = artifact.sha256()?;
+                if sha256 != locked.sha256 {
+                    return Err(Error::ChecksumMismatch {
+                        artifact: dependency.to_string(),
+                        path: artifact.path,
+                        expected: locked.sha256,
+                        found: sha256,
+                    });
+                }
+                artifacts.push(artifact);
+            }
+            return Ok(artifacts);
+        }
+    }
+
+    println!("Resolving JVM dependencies...");
+    let artifacts = resolver.resolve(dependencies)?;
+    let mut text = String::from(
+        "# This file is automatically @generated by cargo-apk2.\n\
+        # It is not intended for manual editing.\n\
+        dependencies = [\n",
+    );
+    for dependency in &declared {
+        writeln!(text, "    {},", Value::from(dependency.as_str())).unwrap();
+    }
+    text.push_str("]\n");
+    for artifact in &artifacts {
+        let dependency = &artifact.dependency;
+        let name = format!("{}:{}", dependency.group, dependency.artifact);
+        writeln!(
+            text,
+            "\n[[artifact]]\nname = {}\nversion = {}\npackaging = {}\nsha256 = {}",
+            Value::from(name),
+            Value::from(dependency.version.as_str()),
+            Value::from(artifact.packaging.as_str()),
+            Value::from(artifact.sha256()?),
+        )
+        .unwrap();
+    }
+    write(path, text)?;
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::resolve,
+        crate::error::Error,
+        ndk_build2::{
+            maven::{Dependency, Repository, Resolver},
+            testing::TempDir,
+        },
+        std::fs,
+    };
+
+    #[test]
+    fn lockfile_round_trip() {
+        let dir = TempDir::new("cargo-apk2-lockfile");
+        let artifact_dir = dir.join("repo/com/example/lib/1.0");
+        fs::create_dir_all(&artifact_dir).unwrap();
+        fs::write(
+            artifact_dir.join("lib-1.0.pom"),
+            "<project><groupId>com.example</groupId><artifactId>lib</artifactId>\
+            <version>1.0</version></project>",
+        )
+        .unwrap();
+        fs::write(artifact_dir.join("lib-1.0.jar"), "jar").unwrap();
+
+        let resolver = || {
+            Resolver::new(
+                vec![Repository::Local(dir.join("repo"))],
+                dir.join("downloads"),
+            )
+        };
+        let dependencies = [Dependency::parse("com.example:lib:1.0").unwrap()];
+        let lockfile = dir.join("Android.lock");
+        let resolved = resolve(&mut resolver(), &dependencies, &lockfile).unwrap();
+        let text = fs::read_to_string(&lockfile).unwrap();
+        assert!(text.contains("dependencies = [\n    \"com.example:lib:1.0\",\n]"));
+        assert!(
+            text.contains("name = \"com.example:lib\"\nversion = \"1.0\"\npackaging = \"jar\"")
+        );
+        assert_eq!(
+            resolve(&mut resolver(), &dependencies, &lockfile).unwrap(),
+            resolved
+        );
+
+        fs::write(artifact_dir.join("lib-1.0.jar"), "changed").unwrap();
+        assert!(matches!(
+            resolve(&mut resolver(), &dependencies, &lockfile),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+    }
+}
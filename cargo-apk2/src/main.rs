@@ -1,8 +1,12 @@
 use {
-    cargo_apk2::{ApkBuilder, Error},
+    cargo_apk2::{
+        ApkBuilder, Error, KEY_PASSWORD_ENV, KEYSTORE_PASSWORD_ENV, inspect, list_keystore,
+        new_keystore, read_password, show_keystore, size, verify,
+    },
     cargo_subcommand::{Artifact, ArtifactType, Subcommand},
     clap::{CommandFactory, FromArgMatches, Parser},
-    std::collections::HashMap,
+    ndk_build2::keystore::{KeyGenOptions, KeystoreType},
+    std::{collections::HashMap, path::PathBuf},
 };
 
 #[derive(Parser)]
@@ -36,6 +40,59 @@ enum ApkSubCmd {
     Build {
         #[clap(flatten)]
         args: Args,
+        /// Build twice from a clean packaging directory and check that the apks are byte-identical
+        #[clap(long)]
+        verify_reproducible: bool,
+        /// Verify the signatures, alignment, manifest and native library dependencies of the apks
+        #[clap(long)]
+        verify: bool,
+    },
+    /// Compile the current package and create an Android App Bundle (aab) for Google Play
+    Bundle {
+        #[clap(flatten)]
+        args: Args,
+    },
+    /// Compile the Java/Kotlin/Scala/Groovy sources and write Rust stubs for their `native` methods
+    JniStubs {
+        #[clap(flatten)]
+        args: Args,
+        /// Path of the generated Rust file (default: `jni_stubs.rs` in the apk build directory)
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Show the manifest, native libraries, dex files, assets and signers of an apk
+    Inspect {
+        #[clap(flatten)]
+        args: Args,
+        /// Path to the apk to inspect (default: the apk built for the current package)
+        apk: Option<PathBuf>,
+        /// Print the result as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Show how much each ABI, crate, dex file, resource table and asset adds to an apk
+    Size {
+        #[clap(flatten)]
+        args: Args,
+        /// Path to the apk to measure (default: the apks built for the current package, which
+        /// are also checked against `size_budget`)
+        apk: Option<PathBuf>,
+        /// Print the result as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Verify the signatures, alignment, manifest and native library dependencies of an apk
+    Verify {
+        #[clap(flatten)]
+        args: Args,
+        /// Path to the apk to verify (default: the apks built for the current package, whose
+        /// manifest is also checked against Cargo.toml)
+        apk: Option<PathBuf>,
+    },
+    /// Create keystores and show their certificates
+    Keystore {
+        #[clap(subcommand)]
+        cmd: KeystoreSubCmd,
     },
     /// Invoke `cargo` under the detected NDK environment
     #[clap(name = "--")]
@@ -68,6 +125,78 @@ enum ApkSubCmd {
     },
 }
 
+#[derive(clap::Subcommand)]
+enum KeystoreSubCmd {
+    /// Generate a key pair, creating the keystore if it doesn't exist (`keytool` is only needed for JKS
+    /// keystores, non-RSA keys or existing keystores)
+    New {
+        /// Path to the keystore
+        path: PathBuf,
+        /// Password of the keystore. Prefer `CARGO_APK2_KEYSTORE_PASSWORD` or the prompt, as
+        /// arguments are visible to other processes
+        #[clap(long)]
+        keystore_password: Option<String>,
+        /// Alias of the new key
+        #[clap(long, default_value = "key0")]
+        alias: String,
+        /// Password of the new key (default: the keystore password), JKS keystores only. Prefer
+        /// `CARGO_APK2_KEY_PASSWORD`, as arguments are visible to other processes
+        #[clap(long)]
+        key_password: Option<String>,
+        /// Distinguished name of the certificate, e.g. `CN=Example,O=Example Inc.,C=US`
+        #[clap(long)]
+        dname: String,
+        /// Keystore format, `JKS` or `PKCS12`
+        #[clap(long, default_value = "PKCS12")]
+        keystore_type: KeystoreType,
+        /// Key algorithm, `RSA`, `EC` or `DSA` (only RSA keys can be used by the built-in signer)
+        #[clap(long, default_value = "RSA")]
+        key_algorithm: String,
+        /// Key size in bits (default: 3072 for RSA, chosen by `keytool` otherwise)
+        #[clap(long)]
+        key_size: Option<u32>,
+        /// Validity of the certificate in days
+        #[clap(long, default_value_t = 10000)]
+        validity: u32,
+    },
+    /// List the entries of a keystore with their SHA-256 fingerprints
+    List {
+        /// Path to the keystore
+        path: PathBuf,
+        /// Password of the keystore. Prefer `CARGO_APK2_KEYSTORE_PASSWORD` or the prompt, as
+        /// arguments are visible to other processes
+        #[clap(long)]
+        keystore_password: Option<String>,
+    },
+    /// Show the certificates of a keystore
+    Show {
+        /// Path to the keystore
+        path: PathBuf,
+        /// Password of the keystore. Prefer `CARGO_APK2_KEYSTORE_PASSWORD` or the prompt, as
+        /// arguments are visible to other processes
+        #[clap(long)]
+        keystore_password: Option<String>,
+        /// Only show the entry with this alias
+        #[clap(long)]
+        alias: Option<String>,
+    },
+}
+
+/// 从环境变量、`--keystore-password` 或终端提示中读取 keystore 密码
+fn keystore_password_or_prompt(arg: Option<String>, confirm: bool) -> Result<String, Error> {
+    read_password(
+        KEYSTORE_PASSWORD_ENV,
+        "--keystore-password",
+        arg,
+        Some("Keystore password"),
+        confirm,
+    )?
+    .ok_or(Error::PasswordRequired {
+        flag: "--keystore-password",
+        env: KEYSTORE_PASSWORD_ENV,
+    })
+}
+
 fn split_apk_and_cargo_args(input: Vec<String>) -> (Args, Vec<String>) {
     // Clap 不支持正确解析未知参数：
     // https://github.com/clap-rs/clap/issues/1404
@@ -152,13 +281,119 @@ fn main() -> anyhow::Result<()> {
             let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
             builder.check()?;
         }
-        ApkSubCmd::Build { args } => {
+        ApkSubCmd::Build {
+            args,
+            verify_reproducible,
+            verify,
+        } => {
+            let cmd = Subcommand::new(args.subcommand_args)?;
+            let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
+            for artifact in cmd.artifacts() {
+                if verify_reproducible {
+                    builder.verify_reproducible(artifact)?;
+                } else {
+                    builder.build(artifact)?;
+                }
+                if verify {
+                    builder.verify(artifact)?;
+                }
+            }
+        }
+        ApkSubCmd::Bundle { args } => {
             let cmd = Subcommand::new(args.subcommand_args)?;
             let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
             for artifact in cmd.artifacts() {
-                builder.build(artifact)?;
+                let aab = builder.bundle(artifact)?;
+                println!("Bundle written to `{}`", aab.display());
             }
         }
+        ApkSubCmd::JniStubs { args, output } => {
+            let cmd = Subcommand::new(args.subcommand_args)?;
+            let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
+            let stubs = builder.jni_stubs(&get_single_artifact(&cmd)?, output)?;
+            println!("JNI stubs written to `{}`", stubs.display());
+        }
+        ApkSubCmd::Inspect { args, apk, json } => {
+            let apks = match apk {
+                Some(apk) => vec![apk],
+                None => {
+                    let cmd = Subcommand::new(args.subcommand_args)?;
+                    let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
+                    builder.apk_paths(&get_single_artifact(&cmd)?)
+                }
+            };
+            for apk in apks {
+                inspect(&apk, json)?;
+            }
+        }
+        ApkSubCmd::Size { args, apk, json } => match apk {
+            Some(apk) => size(&apk, json)?,
+            None => {
+                let cmd = Subcommand::new(args.subcommand_args)?;
+                let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
+                builder.size(&get_single_artifact(&cmd)?, json)?;
+            }
+        },
+        ApkSubCmd::Verify { args, apk } => match apk {
+            Some(apk) => verify(&apk, None)?,
+            None => {
+                let cmd = Subcommand::new(args.subcommand_args)?;
+                let builder = ApkBuilder::from_subcommand(&cmd, args.device)?;
+                builder.verify(&get_single_artifact(&cmd)?)?;
+            }
+        },
+        ApkSubCmd::Keystore { cmd } => match cmd {
+            KeystoreSubCmd::New {
+                path,
+                keystore_password,
+                alias,
+                key_password,
+                dname,
+                keystore_type,
+                key_algorithm,
+                key_size,
+                validity,
+            } => {
+                let keystore_password = keystore_password_or_prompt(keystore_password, true)?;
+                // PKCS12 keystore 的密钥密码必须与 keystore 密码相同，无需提示
+                let key_password = read_password(
+                    KEY_PASSWORD_ENV,
+                    "--key-password",
+                    key_password,
+                    (keystore_type == KeystoreType::Jks)
+                        .then_some("Key password (empty to use the keystore password)"),
+                    true,
+                )?
+                .filter(|password| !password.is_empty());
+                new_keystore(&KeyGenOptions {
+                    path,
+                    keystore_type,
+                    keystore_password,
+                    alias,
+                    key_password,
+                    dname,
+                    key_algorithm,
+                    key_size,
+                    validity,
+                })?
+            }
+            KeystoreSubCmd::List {
+                path,
+                keystore_password,
+            } => list_keystore(
+                &path,
+                &keystore_password_or_prompt(keystore_password, false)?,
+            )?,
+            KeystoreSubCmd::Show {
+                path,
+                keystore_password,
+                alias,
+            } => show_keystore(
+                &path,
+                &keystore_password_or_prompt(keystore_password, false)?,
+                alias.as_deref(),
+            )?,
+        },
         ApkSubCmd::Ndk {
             cargo_cmd,
             cargo_args,
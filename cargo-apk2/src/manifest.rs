@@ -1,8 +1,11 @@
 use crate::error::Error;
-use ndk_build2::{apk::StripConfig, manifest::AndroidManifest, target::Target};
+use ndk_build2::{
+    apk::StripConfig, icon::LauncherIcon, keystore::KeystoreType, manifest::AndroidManifest,
+    target::Target,
+};
 use serde::Deserialize;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
 };
 
@@ -19,15 +22,45 @@ pub(crate) struct Manifest {
     pub(crate) apk_name: Option<String>,
     /// 使用aapt2编译和处理资源（默认开启）
     pub use_aapt2: Option<bool>,
+    /// 使用SDK中的apksigner签名（默认使用内置签名器）
+    pub(crate) use_apksigner: bool,
     pub(crate) android_manifest: AndroidManifest,
     pub(crate) build_targets: Vec<Target>,
-    pub(crate) assets: Option<PathBuf>,
-    pub(crate) resources: Option<PathBuf>,
+    /// 为每个目标生成单独的 APK
+    pub(crate) abi_splits: bool,
+    /// 启用 `abi_splits` 时额外生成包含所有 ABI 的通用 APK
+    pub(crate) universal_apk: bool,
+    /// 资产目录，后面目录中的同名文件覆盖前面的
+    pub(crate) assets: Vec<PathBuf>,
+    /// 资源目录，后面的目录作为覆盖层
+    pub(crate) resources: Vec<PathBuf>,
+    /// 启动器图标，生成各密度的图标资源
+    pub(crate) icon: Option<LauncherIcon>,
+    /// 按语言区域分组的字符串资源
+    pub(crate) strings: BTreeMap<String, BTreeMap<String, String>>,
+    /// 写入默认资源 `values/strings.xml` 的语言区域
+    pub(crate) default_locale: String,
     pub(crate) java_sources: Option<PathBuf>,
     pub(crate) kotlin_sources: Option<PathBuf>,
     pub(crate) scala_sources: Option<PathBuf>,
     pub(crate) groovy_sources: Option<PathBuf>,
     pub(crate) runtime_libs: Option<PathBuf>,
+    /// `dev` 以外的配置文件使用 R8 转换 dex（默认关闭）
+    pub(crate) use_r8: bool,
+    /// 发布构建中传给 R8 的 ProGuard 规则文件
+    pub(crate) proguard_rules: Vec<PathBuf>,
+    /// 启用 Java 8+ API 脱糖
+    pub(crate) core_library_desugaring: bool,
+    /// 脱糖运行时 jar，未设置时在本地 Maven 仓库与 Gradle 缓存中查找
+    pub(crate) desugar_jdk_libs: Option<PathBuf>,
+    /// 脱糖配置 jar 或 `desugar.json`，未设置时同样查找
+    pub(crate) desugar_jdk_libs_configuration: Option<PathBuf>,
+    /// JVM 依赖的坐标 `group:artifact:version`
+    pub(crate) dependencies: Vec<String>,
+    /// 在 `~/.m2/repository` 之后查找依赖的 Maven 仓库：本地目录或 `http://` 镜像
+    pub(crate) maven_repositories: Vec<String>,
+    /// 本地 Android 库（`.aar`）的路径
+    pub(crate) aars: Vec<PathBuf>,
     /// legacy support fixes (Android 5): include c++ shared from NDK, replaces 64bit fseek with 32 bit
     pub(crate) include_cplusplus_shared: bool,
     pub(crate) legacy_fseek_fix: bool,
@@ -35,7 +68,15 @@ pub(crate) struct Manifest {
     pub(crate) signing: HashMap<String, Signing>,
     pub(crate) reverse_port_forward: HashMap<String, String>,
     pub(crate) strip: StripConfig,
+    /// 不压缩存储的文件扩展名
+    pub(crate) no_compress: Vec<String>,
+    /// 写入 App Bundle 配置的 bundletool 版本
+    pub(crate) bundletool_version: Option<String>,
     pub(crate) manifest_override: Option<PathBuf>,
+    /// 超出时 `build` 失败的大小上限
+    pub(crate) size_budget: SizeBudget,
+    /// 将配置文件映射到 JNI 绑定问题的处理方式
+    pub(crate) jni_check: HashMap<String, JniCheck>,
 }
 
 impl Manifest {
@@ -55,21 +96,39 @@ impl Manifest {
             version: package.version,
             apk_name: metadata.apk_name,
             use_aapt2: metadata.use_aapt2,
+            use_apksigner: metadata.use_apksigner,
             android_manifest: metadata.android_manifest,
             build_targets: metadata.build_targets,
+            abi_splits: metadata.abi_splits,
+            universal_apk: metadata.universal_apk,
             assets: metadata.assets,
             resources: metadata.resources,
+            icon: metadata.icon,
+            strings: metadata.strings,
+            default_locale: metadata.default_locale,
             java_sources: metadata.java_sources,
             kotlin_sources: metadata.kotlin_sources,
             scala_sources: metadata.scala_sources,
             groovy_sources: metadata.groovy_sources,
             runtime_libs: metadata.runtime_libs,
+            use_r8: metadata.use_r8,
+            proguard_rules: metadata.proguard_rules,
+            core_library_desugaring: metadata.core_library_desugaring,
+            desugar_jdk_libs: metadata.desugar_jdk_libs,
+            desugar_jdk_libs_configuration: metadata.desugar_jdk_libs_configuration,
+            dependencies: metadata.dependencies,
+            maven_repositories: metadata.maven_repositories,
+            aars: metadata.aars,
             include_cplusplus_shared: metadata.include_cplusplus_shared,
             legacy_fseek_fix: metadata.legacy_fseek_fix,
             signing: metadata.signing,
             reverse_port_forward: metadata.reverse_port_forward,
             strip: metadata.strip,
+            no_compress: metadata.no_compress,
+            bundletool_version: metadata.bundletool_version,
             manifest_override: metadata.manifest_override,
+            size_budget: metadata.size_budget,
+            jni_check: metadata.jni_check,
         })
     }
 }
@@ -115,13 +174,34 @@ struct AndroidMetadata {
     apk_name: Option<String>,
     /// 使用aapt2编译和处理资源（默认开启）
     use_aapt2: Option<bool>,
+    /// 使用SDK中的apksigner签名（默认使用内置签名器）
+    #[serde(default)]
+    use_apksigner: bool,
     manifest_override: Option<PathBuf>,
     #[serde(flatten)]
     android_manifest: AndroidManifest,
     #[serde(default)]
     build_targets: Vec<Target>,
-    assets: Option<PathBuf>,
-    resources: Option<PathBuf>,
+    /// Build one APK per target instead of a single APK containing all ABIs
+    #[serde(default)]
+    abi_splits: bool,
+    /// Also build an APK containing all ABIs when `abi_splits` is enabled
+    #[serde(default)]
+    universal_apk: bool,
+    /// One directory or a list of directories, later directories override earlier ones
+    #[serde(default, deserialize_with = "one_or_many")]
+    assets: Vec<PathBuf>,
+    /// One directory or a list of directories, later directories are linked as overlays
+    #[serde(default, deserialize_with = "one_or_many")]
+    resources: Vec<PathBuf>,
+    /// An image, or the foreground and background layers of an adaptive icon
+    icon: Option<LauncherIcon>,
+    /// String resources by locale, e.g. `[package.metadata.android.strings.zh-CN]`
+    #[serde(default)]
+    strings: BTreeMap<String, BTreeMap<String, String>>,
+    /// Locale of the strings written to `values/strings.xml`
+    #[serde(default = "default_locale")]
+    default_locale: String,
     /// Java源文件的路径
     java_sources: Option<PathBuf>,
     /// Kotlin源文件的路径
@@ -131,6 +211,28 @@ struct AndroidMetadata {
     /// Groovy源文件的路径
     groovy_sources: Option<PathBuf>,
     runtime_libs: Option<PathBuf>,
+    /// Use R8 instead of D8 `--release` for profiles other than `dev` (defaults to false)
+    #[serde(default)]
+    use_r8: bool,
+    /// ProGuard rule files passed to R8 when `use_r8` is enabled
+    #[serde(default)]
+    proguard_rules: Vec<PathBuf>,
+    /// Desugar Java 8+ APIs such as `java.time` and streams for devices below their API level
+    #[serde(default)]
+    core_library_desugaring: bool,
+    /// Path of the `desugar_jdk_libs` jar, looked up in the local Maven repository and the Gradle cache if unset
+    desugar_jdk_libs: Option<PathBuf>,
+    /// Path of the `desugar_jdk_libs_configuration` jar or its `desugar.json`, looked up like `desugar_jdk_libs` if unset
+    desugar_jdk_libs_configuration: Option<PathBuf>,
+    /// JVM dependencies, either `["group:artifact:version"]` or a `"group:artifact" = "version"` table
+    #[serde(default, deserialize_with = "coordinates")]
+    dependencies: Vec<String>,
+    /// Maven repositories searched after `~/.m2/repository`: directories or `http://` mirrors
+    #[serde(default)]
+    maven_repositories: Vec<String>,
+    /// Local Android libraries (`.aar`), in addition to those resolved from `dependencies`
+    #[serde(default)]
+    aars: Vec<PathBuf>,
     /// Automatically bundle libc++_shared.so from NDK
     #[serde(default)]
     include_cplusplus_shared: bool,
@@ -145,10 +247,164 @@ struct AndroidMetadata {
     reverse_port_forward: HashMap<String, String>,
     #[serde(default)]
     strip: StripConfig,
+    /// File extensions stored uncompressed in the APK, e.g. `["ogg", "ktx2"]`
+    #[serde(default)]
+    no_compress: Vec<String>,
+    /// bundletool version recorded in the `BundleConfig.pb` of `cargo apk2 bundle` (defaults to 1.17.2)
+    bundletool_version: Option<String>,
+    /// Fail the build when an APK or the native libraries of an ABI grow past these sizes
+    #[serde(default)]
+    size_budget: SizeBudget,
+    /// Maps profiles to how mismatched JNI bindings are reported, e.g. `release = "deny"`
+    #[serde(default)]
+    jni_check: HashMap<String, JniCheck>,
+}
+
+fn default_locale() -> String {
+    "en".into()
+}
+
+/// 接受单个路径或路径列表
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(path) => vec![path],
+        OneOrMany::Many(paths) => paths,
+    })
+}
+
+/// 接受坐标列表，或组与名称到版本的表
+fn coordinates<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Coordinates {
+        List(Vec<String>),
+        Table(BTreeMap<String, String>),
+    }
+
+    Ok(match Coordinates::deserialize(deserializer)? {
+        Coordinates::List(coordinates) => coordinates,
+        Coordinates::Table(versions) => versions
+            .into_iter()
+            .map(|(name, version)| format!("{name}:{version}"))
+            .collect(),
+    })
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
 pub(crate) struct Signing {
     pub(crate) path: PathBuf,
     pub(crate) keystore_password: String,
+    /// 未指定时使用 keystore 中的第一个私钥条目
+    pub(crate) key_alias: Option<String>,
+    /// 未指定时与 keystore 密码相同
+    pub(crate) key_password: Option<String>,
+    /// `JKS` 或 `PKCS12`，未指定时根据文件头自动识别
+    pub(crate) keystore_type: Option<KeystoreType>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+pub(crate) struct SizeBudget {
+    /// 单个 APK 文件的最大大小
+    pub(crate) apk: Option<ByteSize>,
+    /// 单个 ABI 的所有动态库在 APK 中（压缩后）的最大大小
+    pub(crate) abi: Option<ByteSize>,
+}
+
+/// `native` 方法与动态库导出的 JNI 函数不一致时的处理方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum JniCheck {
+    /// 不检查
+    Allow,
+    /// 打印警告
+    #[default]
+    Warn,
+    /// 构建失败
+    Deny,
+}
+
+/// 字节数，可以写成整数或带单位的字符串，例如 `"20 MiB"`、`"500 kB"`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "ByteSizeValue")]
+pub(crate) struct ByteSize(pub(crate) u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ByteSizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<ByteSizeValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: ByteSizeValue) -> Result<Self, Self::Error> {
+        match value {
+            ByteSizeValue::Bytes(bytes) => Ok(Self(bytes)),
+            ByteSizeValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = (s[..split].trim(), s[split..].trim());
+        let number = number
+            .parse::<f64>()
+            .map_err(|_| format!("invalid size `{s}`"))?;
+        let multiplier = match unit {
+            "" | "B" => 1,
+            "kB" | "KB" | "K" => 1000,
+            "MB" | "M" => 1000 * 1000,
+            "GB" | "G" => 1000 * 1000 * 1000,
+            "KiB" => 1 << 10,
+            "MiB" => 1 << 20,
+            "GiB" => 1 << 30,
+            _ => {
+                return Err(format!(
+                    "unknown size unit `{unit}` in `{s}`, expected B, kB, MB, GB, KiB, MiB or GiB"
+                ));
+            }
+        };
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteSize, SizeBudget};
+
+    #[test]
+    fn parse_byte_size() {
+        assert_eq!("1024".parse(), Ok(ByteSize(1024)));
+        assert_eq!("20 MiB".parse(), Ok(ByteSize(20 << 20)));
+        assert_eq!("1.5MB".parse(), Ok(ByteSize(1_500_000)));
+        assert_eq!("500 kB".parse(), Ok(ByteSize(500_000)));
+        assert!("20 mb".parse::<ByteSize>().is_err());
+        assert!("MiB".parse::<ByteSize>().is_err());
+
+        let budget = toml::from_str::<SizeBudget>("apk = \"20 MiB\"\nabi = 1000").unwrap();
+        assert_eq!(budget.apk, Some(ByteSize(20 << 20)));
+        assert_eq!(budget.abi, Some(ByteSize(1000)));
+        assert!(toml::from_str::<SizeBudget>("apk = \"20 mb\"").is_err());
+    }
 }
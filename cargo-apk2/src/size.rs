@@ -0,0 +1,129 @@
+use {
+    crate::{
+        error::Error,
+        inspect::{format_size, print_table},
+        manifest::SizeBudget,
+    },
+    ndk_build2::size::ApkSize,
+    std::path::Path,
+};
+
+/// 每个动态库最多列出的 crate 数量，其余合并为一行
+const MAX_CRATES: usize = 20;
+
+/// 打印 APK 的空间占用，`json` 为真时输出 JSON
+pub fn size(path: &Path, json: bool) -> Result<(), Error> {
+    print_size(&read(path)?, json)
+}
+
+pub(crate) fn read(path: &Path) -> Result<ApkSize, Error> {
+    if !path.exists() {
+        return Err(Error::PathNotFound(path.into()));
+    }
+    Ok(ApkSize::read(path)?)
+}
+
+pub(crate) fn print_size(info: &ApkSize, json: bool) -> Result<(), Error> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(info)?);
+        return Ok(());
+    }
+
+    println!("APK: {} ({})", info.path.display(), format_size(info.size));
+    let share = |size: u64| format!("{:.1}%", size as f64 * 100.0 / info.size.max(1) as f64);
+    let rows = info
+        .parts
+        .iter()
+        .map(|part| {
+            vec![
+                part.name.clone(),
+                format_size(part.size),
+                format_size(part.compressed_size),
+                share(part.compressed_size),
+            ]
+        })
+        .collect::<Vec<_>>();
+    print_table(&["Part", "Size", "Compressed", "Share"], &rows);
+
+    for (abi, abi_size) in &info.abis {
+        println!();
+        println!(
+            "{abi}: {} ({} compressed)",
+            format_size(abi_size.size),
+            format_size(abi_size.compressed_size)
+        );
+        let rows = abi_size
+            .libs
+            .iter()
+            .map(|lib| {
+                vec![
+                    lib.name.clone(),
+                    format_size(lib.size),
+                    format_size(lib.compressed_size),
+                ]
+            })
+            .collect::<Vec<_>>();
+        print_table(&["Library", "Size", "Compressed"], &rows);
+
+        for lib in abi_size.libs.iter().filter(|lib| !lib.crates.is_empty()) {
+            println!("  {} by crate (uncompressed):", lib.name);
+            let lib_share =
+                |size: u64| format!("{:.1}%", size as f64 * 100.0 / lib.size.max(1) as f64);
+            let mut rows = lib
+                .crates
+                .iter()
+                .take(MAX_CRATES)
+                .map(|c| vec![c.name.clone(), format_size(c.size), lib_share(c.size)])
+                .collect::<Vec<_>>();
+            if lib.crates.len() > MAX_CRATES {
+                let rest = &lib.crates[MAX_CRATES..];
+                let size = rest.iter().map(|c| c.size).sum();
+                rows.push(vec![
+                    format!("({} more)", rest.len()),
+                    format_size(size),
+                    lib_share(size),
+                ]);
+            }
+            print_table(&["Crate", "Size", "Share"], &rows);
+        }
+    }
+    let mut libs = info.abis.values().flat_map(|abi| &abi.libs);
+    if !info.abis.is_empty() && libs.all(|lib| lib.crates.is_empty()) {
+        println!();
+        println!("No symbol tables found, build without `strip` to see sizes by crate");
+    }
+    Ok(())
+}
+
+/// 检查 APK 及其中每个 ABI 的动态库是否超出 `budget`
+pub(crate) fn check_budget(info: &ApkSize, budget: &SizeBudget) -> Result<(), Error> {
+    let mut violations = Vec::new();
+    if let Some(limit) = budget.apk
+        && info.size > limit.0
+    {
+        violations.push(format!(
+            "the APK is {}, budget is {}",
+            format_size(info.size),
+            format_size(limit.0)
+        ));
+    }
+    if let Some(limit) = budget.abi {
+        for (abi, abi_size) in &info.abis {
+            if abi_size.compressed_size > limit.0 {
+                violations.push(format!(
+                    "{abi} native libraries are {}, budget is {}",
+                    format_size(abi_size.compressed_size),
+                    format_size(limit.0)
+                ));
+            }
+        }
+    }
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::SizeBudgetExceeded {
+            path: info.path.clone(),
+            violations,
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use {
+    crate::error::Error,
+    ndk_build2::verify::{ExpectedManifest, verify_apk},
+    std::path::Path,
+};
+
+/// 校验 APK 并打印结果，存在错误时返回 [`Error::VerificationFailed`]
+pub fn verify(path: &Path, expected: Option<&ExpectedManifest>) -> Result<(), Error> {
+    if !path.exists() {
+        return Err(Error::PathNotFound(path.into()));
+    }
+    let verification = verify_apk(path, expected)?;
+    println!("Verifying `{}`", path.display());
+    if verification.schemes.is_empty() {
+        println!("  Verified signatures: none");
+    } else {
+        println!("  Verified signatures: {}", verification.schemes.join(", "));
+    }
+    for warning in &verification.warnings {
+        println!("  warning: {warning}");
+    }
+    for error in &verification.errors {
+        println!("  error: {error}");
+    }
+    if !verification.is_ok() {
+        return Err(Error::VerificationFailed {
+            path: path.into(),
+            errors: verification.errors.len(),
+        });
+    }
+    println!("  OK");
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! Android 库（`.aar`）：解压后其中的类、资源、资产、清单与动态库分别加入应用的构建。
+
+use {
+    crate::{axml::Element, error::NdkError, zip::ZipArchive},
+    sha2::{Digest, Sha256},
+    std::{
+        fs::{create_dir_all, read, read_dir, read_to_string, remove_dir_all, write},
+        path::{Component, Path, PathBuf},
+    },
+};
+
+/// 解压目录中记录库文件 SHA-256 的文件，内容一致时跳过解压
+const STAMP: &str = ".aar.sha256";
+
+/// 解压到目录中的 Android 库
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aar {
+    /// 解压目录
+    pub dir: PathBuf,
+    /// 库清单中的包名，库资源的 `R` 类生成在此包中
+    pub package: String,
+}
+
+impl Aar {
+    /// `root` 下用于解压某个库的目录：`<name>-<key 的哈希>`，
+    /// `key` 应唯一标识该库（如文件的绝对路径或 Maven 坐标），以免同名的库互相覆盖
+    pub fn unpack_dir(root: &Path, name: &str, key: &str) -> PathBuf {
+        root.join(format!("{name}-{}", &hex(&Sha256::digest(key))[..16]))
+    }
+
+    /// 将 `aar` 解压到 `dir`（先清空该目录），并读取其清单中的包名；
+    /// 若该目录已由内容相同的库解压得到，则直接复用
+    pub fn unpack(aar: &Path, dir: &Path) -> Result<Self, NdkError> {
+        let data = read(aar).map_err(|e| NdkError::IoPathError(aar.to_path_buf(), e))?;
+        let digest = hex(&Sha256::digest(&data));
+        let stamp = dir.join(STAMP);
+        if read_to_string(&stamp).ok().as_deref() != Some(&*digest) {
+            extract(aar, data, dir)?;
+            write(&stamp, digest).map_err(|e| NdkError::IoPathError(stamp, e))?;
+        }
+
+        let manifest = dir.join("AndroidManifest.xml");
+        let text =
+            read_to_string(&manifest).map_err(|_| invalid(aar, "missing `AndroidManifest.xml`"))?;
+        let package = Element::parse(&text)?
+            .attribute("package")
+            .map(|attr| attr.value.to_string())
+            .filter(|package| !package.is_empty())
+            .ok_or_else(|| invalid(aar, "`AndroidManifest.xml` has no `package`"))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            package,
+        })
+    }
+
+    /// 库的类：`classes.jar` 与 `libs` 中的 jar
+    pub fn jars(&self) -> Result<Vec<PathBuf>, NdkError> {
+        let mut jars = Vec::new();
+        let classes = self.dir.join("classes.jar");
+        if classes.is_file() {
+            jars.push(classes);
+        }
+        let libs = self.dir.join("libs");
+        if libs.is_dir() {
+            let mut libs = read_dir(&libs)
+                .map_err(|e| NdkError::IoPathError(libs, e))?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            libs.retain(|path| path.extension().is_some_and(|ext| ext == "jar"));
+            libs.sort();
+            jars.extend(libs);
+        }
+        Ok(jars)
+    }
+
+    /// 资源目录，库中没有资源时为 `None`
+    pub fn res_dir(&self) -> Option<PathBuf> {
+        non_empty_dir(self.dir.join("res"))
+    }
+
+    /// 资产目录，库中没有资产时为 `None`
+    pub fn assets_dir(&self) -> Option<PathBuf> {
+        non_empty_dir(self.dir.join("assets"))
+    }
+
+    /// 动态库目录，其中按 ABI 分为子目录（`jni/<abi>/*.so`），库中没有动态库时为 `None`
+    pub fn jni_dir(&self) -> Option<PathBuf> {
+        non_empty_dir(self.dir.join("jni"))
+    }
+
+    /// 库的清单，合并到应用的清单中
+    pub fn manifest(&self) -> PathBuf {
+        self.dir.join("AndroidManifest.xml")
+    }
+
+    /// 库要求使用者在缩减代码时应用的 ProGuard 规则
+    pub fn proguard_rules(&self) -> Option<PathBuf> {
+        Some(self.dir.join("proguard.txt")).filter(|path| path.is_file())
+    }
+}
+
+/// 清空 `dir` 并将库的全部条目解压到其中
+fn extract(aar: &Path, data: Vec<u8>, dir: &Path) -> Result<(), NdkError> {
+    let archive = ZipArchive::from_bytes(data)?;
+    let _ = remove_dir_all(dir);
+    create_dir_all(dir)?;
+    for entry in archive.entries() {
+        // 拒绝会写到解压目录之外的条目
+        let name = Path::new(&entry.name);
+        if !name
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(invalid(aar, &format!("invalid entry `{}`", entry.name)));
+        }
+        let out = dir.join(name);
+        if entry.is_dir() {
+            create_dir_all(&out)?;
+            continue;
+        }
+        create_dir_all(out.parent().unwrap())?;
+        write(&out, archive.read(entry)?).map_err(|e| NdkError::IoPathError(out, e))?;
+    }
+    Ok(())
+}
+
+fn non_empty_dir(dir: PathBuf) -> Option<PathBuf> {
+    read_dir(&dir)
+        .is_ok_and(|mut entries| entries.next().is_some())
+        .then_some(dir)
+}
+
+fn invalid(aar: &Path, msg: &str) -> NdkError {
+    NdkError::InvalidAar(aar.to_path_buf(), msg.into())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Aar,
+        crate::{error::NdkError, testing::TempDir, zip::ZipWriter},
+        std::fs,
+    };
+
+    #[test]
+    fn unpack() {
+        let dir = TempDir::new("ndk-build2-aar");
+        let library = |extra: &[(&str, &[u8])]| {
+            let mut writer = ZipWriter::new();
+            writer
+                .add_entry(
+                    "AndroidManifest.xml",
+                    br#"<manifest xmlns:android="http://schemas.android.com/apk/res/android" package="com.example.lib" />"#,
+                    true,
+                    1,
+                )
+                .unwrap();
+            writer.add_entry("classes.jar", b"jar", true, 1).unwrap();
+            writer.add_entry("libs/extra.jar", b"jar", true, 1).unwrap();
+            writer
+                .add_entry("res/values/values.xml", b"<resources />", true, 1)
+                .unwrap();
+            writer
+                .add_entry("jni/arm64-v8a/libfoo.so", b"so", true, 1)
+                .unwrap();
+            for (name, data) in extra {
+                writer.add_entry(name, data, true, 1).unwrap();
+            }
+            writer.finish().unwrap()
+        };
+        let path = dir.join("lib.aar");
+        fs::write(&path, library(&[])).unwrap();
+
+        let aar = Aar::unpack(&path, &dir.join("lib")).unwrap();
+        assert_eq!(aar.package, "com.example.lib");
+        assert_eq!(
+            aar.jars().unwrap(),
+            [dir.join("lib/classes.jar"), dir.join("lib/libs/extra.jar")]
+        );
+        assert_eq!(aar.res_dir(), Some(dir.join("lib/res")));
+        assert_eq!(aar.jni_dir(), Some(dir.join("lib/jni")));
+        assert_eq!(aar.assets_dir(), None);
+        assert_eq!(aar.proguard_rules(), None);
+
+        // 内容未变时复用解压目录，变化后重新解压
+        fs::write(dir.join("lib/marker"), "").unwrap();
+        assert_eq!(Aar::unpack(&path, &dir.join("lib")).unwrap(), aar);
+        assert!(dir.join("lib/marker").exists());
+        fs::write(&path, library(&[("proguard.txt", b"-dontwarn")])).unwrap();
+        let aar = Aar::unpack(&path, &dir.join("lib")).unwrap();
+        assert!(!dir.join("lib/marker").exists());
+        assert_eq!(aar.proguard_rules(), Some(dir.join("lib/proguard.txt")));
+
+        assert_ne!(
+            Aar::unpack_dir(&dir, "lib", "/a/lib.aar"),
+            Aar::unpack_dir(&dir, "lib", "/b/lib.aar")
+        );
+
+        let mut writer = ZipWriter::new();
+        writer.add_entry("../evil", b"", true, 1).unwrap();
+        fs::write(&path, writer.finish().unwrap()).unwrap();
+        assert!(matches!(
+            Aar::unpack(&path, &dir.join("evil")),
+            Err(NdkError::InvalidAar(..))
+        ));
+    }
+}
@@ -1,15 +1,27 @@
 use {
     crate::{
+        arsc::{string_resource_id, string_table},
+        axml::{self, ANDROID_NAMESPACE, Element, Value},
+        classfile::ClassFile,
+        desugar::{CoreLibraryDesugaring, write_configuration},
         error::NdkError,
+        jni::keep_rules,
         manifest::AndroidManifest,
+        manifest_merger::{component_names, merge_manifests},
         ndk::{Key, Ndk},
+        readelf::check_page_alignment,
+        signer::Signer,
         target::Target,
+        zip::{ZipArchive, ZipWriter, default_alignment},
     },
     serde::{Deserialize, Serialize},
     std::{
-        collections::{HashMap, HashSet},
+        collections::{BTreeMap, BTreeSet, HashMap},
         ffi::OsStr,
-        fs::{copy, create_dir_all, read_dir, remove_dir_all, remove_file, rename},
+        fs::{
+            copy, create_dir_all, hard_link, read, read_dir, read_to_string, remove_dir_all,
+            remove_file, rename, write,
+        },
         io::Error as IoError,
         path::{Path, PathBuf},
         process::{Command, Stdio},
@@ -36,18 +48,53 @@ pub enum StripConfig {
     Split,
 }
 
+/// 以 R8 代替 D8 生成 dex 时的配置：移除未使用的代码并混淆名称。
+///
+/// 清单（包括合并的库清单）中声明的组件类与通过 JNI 访问的类（见 [`crate::jni::keep_rules`]）总是会被保留。
+#[derive(Clone, Debug, Default)]
+pub struct R8Config {
+    /// 用户的 ProGuard 规则文件
+    pub proguard_rules: Vec<PathBuf>,
+}
+
+/// 未指定 [`ApkConfig::bundletool_version`] 时写入 `BundleConfig.pb` 的 bundletool 版本
+pub const DEFAULT_BUNDLETOOL_VERSION: &str = "1.17.2";
+
 pub struct ApkConfig {
     pub ndk: Ndk,
     pub build_dir: PathBuf,
     pub apk_name: String,
     pub use_aapt2: bool,
-    pub assets: Option<PathBuf>,
-    pub resources: Option<PathBuf>,
+    /// 资产目录，后面目录中的同名文件覆盖前面的
+    pub assets: Vec<PathBuf>,
+    /// 资源目录，后面的目录作为覆盖层（overlay），其中的同名资源覆盖前面的
+    pub resources: Vec<PathBuf>,
+    /// 由 [`Self::emit_resource_ids`] 写入的资源 ID，aapt2 链接时保持其中的 ID 不变（`--stable-ids`）
+    pub resource_ids: Option<PathBuf>,
     pub manifest: AndroidManifest,
     pub disable_aapt_compression: bool,
+    /// 不压缩存储的文件扩展名（不含 `.`，不区分大小写），
+    /// 例如需要通过 `AAsset_openFileDescriptor` 打开的资产
+    pub no_compress: Vec<String>,
+    /// 写入 App Bundle 的 `BundleConfig.pb` 的 bundletool 版本，默认为 [`DEFAULT_BUNDLETOOL_VERSION`]
+    pub bundletool_version: Option<String>,
     pub strip: StripConfig,
     pub reverse_port_forward: HashMap<String, String>,
+    /// 合并到生成的清单之上的清单，其中的声明优先，见 [`crate::manifest_merger`]
     pub manifest_override: Option<PathBuf>,
+    /// 使用 SDK 中的 `apksigner` 代替内置的签名器
+    pub use_apksigner: bool,
+    /// 为 `Some` 时 [`UnalignedApk::put_jar`] 使用 R8 代替 D8
+    pub r8: Option<R8Config>,
+    /// 未使用 R8 时逐个类增量转换 dex，只重新转换变化的类，用于调试构建；
+    /// 为 `false` 时 D8 以 `--release` 模式一次转换所有类
+    pub incremental_dex: bool,
+    /// 为 `Some` 时启用 Java 8+ API 脱糖，并将脱糖运行时转换为 dex 一起打包
+    pub core_library_desugaring: Option<CoreLibraryDesugaring>,
+    /// 额外生成 `R` 类的包名，即资源目录中包含的 Android 库的包名
+    pub extra_packages: Vec<String>,
+    /// 合并到应用清单中的 Android 库清单，见 [`crate::manifest_merger`]
+    pub library_manifests: Vec<PathBuf>,
 }
 
 impl ApkConfig {
@@ -58,6 +105,29 @@ impl ApkConfig {
         Ok(cmd)
     }
 
+    /// 写入 APK 时是否压缩名为 `name` 的条目。
+    ///
+    /// 调试构建不压缩任何条目；`extract_native_libs` 为 `false` 时系统直接从 APK 中映射动态库，
+    /// `.so` 必须不压缩存储；扩展名在 `no_compress` 中的条目同样不压缩。
+    pub fn should_compress(&self, name: &str) -> bool {
+        if self.disable_aapt_compression {
+            return false;
+        }
+        if name.starts_with("lib/")
+            && name.ends_with(".so")
+            && self.manifest.application.extract_native_libs == Some(false)
+        {
+            return false;
+        }
+        let name = name.to_ascii_lowercase();
+        !self.no_compress.iter().any(|ext| {
+            name.ends_with(&format!(
+                ".{}",
+                ext.trim_start_matches('.').to_ascii_lowercase()
+            ))
+        })
+    }
+
     fn unaligned_apk(&self) -> PathBuf {
         self.build_dir
             .join(format!("{}-unaligned.apk", self.apk_name))
@@ -69,6 +139,34 @@ impl ApkConfig {
         self.build_dir.join(format!("{}.apk", self.apk_name))
     }
 
+    /// R8 写入的混淆映射，用于还原堆栈跟踪中的名称
+    #[inline]
+    pub fn mapping(&self) -> PathBuf {
+        self.build_dir.join("mapping.txt")
+    }
+
+    /// [`UnalignedApk::put_jar`] 写入 dex 的目录，每次转换前清空
+    #[inline]
+    pub fn dex_dir(&self) -> PathBuf {
+        self.build_dir.join("dex")
+    }
+
+    /// 传给 D8、R8 与 L8 的脱糖配置
+    fn desugar_config(&self) -> PathBuf {
+        self.build_dir.join("desugar.json")
+    }
+
+    /// D8 或 R8 写入的脱糖运行时保留规则，L8 按其移除应用没有用到的部分
+    fn desugar_rules(&self) -> PathBuf {
+        self.build_dir.join("desugar-rules.pro")
+    }
+
+    /// 调用 [`UnsignedBundle::sign`] 时将写入的 Android App Bundle 的路径
+    #[inline]
+    pub fn aab(&self) -> PathBuf {
+        self.build_dir.join(format!("{}.aab", self.apk_name))
+    }
+
     pub fn copy_file(&self, src: &Path, dst: &Path) -> Result<(), NdkError> {
         let dst_path = self.build_dir.join(dst);
         copy(src, dst_path)?;
@@ -79,26 +177,19 @@ impl ApkConfig {
     where
         P: AsRef<Path>,
     {
-        create_dir_all(&self.build_dir)?;
-        if let Some(manifest_override) = &self.manifest_override {
-            self.copy_file(manifest_override, "AndroidManifest.xml".as_ref())?;
-        } else {
-            self.manifest.write_to(&self.build_dir)?;
-        }
+        let target_sdk_version = self.write_manifest()?;
 
-        let target_sdk_version = self
-            .manifest
-            .sdk
-            .target_sdk_version
-            .unwrap_or_else(|| self.ndk.default_target_platform());
-
-        if self.use_aapt2 {
+        if self.link_without_aapt()? {
+            // 清单与资源表已在进程内生成
+        } else if self.use_aapt2 {
             let out_dir = self.build_dir.join("resources");
             self.aapt2_compile(&out_dir)?;
             self.aapt2_link(
                 &out_dir,
                 &gen_java_dir.as_ref().to_path_buf(),
                 target_sdk_version,
+                false,
+                None,
             )?;
             if !self.manifest.application.debuggable.unwrap_or(false) {
                 self.aapt2_optimize()?;
@@ -109,15 +200,199 @@ impl ApkConfig {
 
         Ok(UnalignedApk {
             config: self,
-            pending_libs: HashSet::default(),
+            pending_dex: Vec::new(),
+            pending_libs: BTreeSet::new(),
+            unaligned_libs: Vec::new(),
         })
     }
 
+    /// 与 [`Self::create_apk`] 相同，但资源以 protobuf 格式链接，供生成 Android App Bundle 使用。
+    ///
+    /// 返回的 [`UnalignedApk`] 只能通过 [`UnalignedApk::add_pending_libs_and_bundle`] 完成打包。
+    /// App Bundle 始终使用 aapt2，忽略 `use_aapt2`。
+    pub fn create_bundle<P>(&self, gen_java_dir: P) -> Result<UnalignedApk<'_>, NdkError>
+    where
+        P: AsRef<Path>,
+    {
+        let target_sdk_version = self.write_manifest()?;
+
+        let out_dir = self.build_dir.join("resources");
+        self.aapt2_compile(&out_dir)?;
+        self.aapt2_link(
+            &out_dir,
+            &gen_java_dir.as_ref().to_path_buf(),
+            target_sdk_version,
+            true,
+            None,
+        )?;
+
+        Ok(UnalignedApk {
+            config: self,
+            pending_dex: Vec::new(),
+            pending_libs: BTreeSet::new(),
+            unaligned_libs: Vec::new(),
+        })
+    }
+
+    /// 用 aapt2 编译并链接当前的资源，将分配的资源 ID 写入 `ids`（`--emit-ids` 格式）。
+    ///
+    /// 将 `ids` 设为 [`Self::resource_ids`] 后，之后链接 APK 时这些资源的 ID 保持不变，
+    /// 即使此后又添加了其他资源目录。
+    pub fn emit_resource_ids<P>(&self, gen_java_dir: P, ids: &Path) -> Result<(), NdkError>
+    where
+        P: AsRef<Path>,
+    {
+        let target_sdk_version = self.write_manifest()?;
+        let out_dir = self.build_dir.join("resources");
+        self.aapt2_compile(&out_dir)?;
+        self.aapt2_link(
+            &out_dir,
+            &gen_java_dir.as_ref().to_path_buf(),
+            target_sdk_version,
+            false,
+            Some(ids),
+        )
+    }
+
+    /// 写入生成的 `AndroidManifest.xml`，在其上合并 `manifest_override` 与库的清单，返回目标 SDK 版本
+    fn write_manifest(&self) -> Result<u32, NdkError> {
+        create_dir_all(&self.build_dir)?;
+        self.manifest.write_to(&self.build_dir)?;
+        if self.manifest_override.is_some() || !self.library_manifests.is_empty() {
+            merge_manifests(
+                &self.build_dir.join("AndroidManifest.xml"),
+                self.manifest_override.as_deref(),
+                &self.library_manifests,
+            )?;
+        }
+
+        Ok(self
+            .manifest
+            .sdk
+            .target_sdk_version
+            .unwrap_or_else(|| self.ndk.default_target_platform()))
+    }
+
+    /// 没有 Java 代码与资源时，在进程内把清单编译为二进制 XML，并生成只包含应用名称的资源表，
+    /// 不再需要 aapt。清单中有无法在进程内编译的属性时返回 `false`，此时仍使用 aapt。
+    fn link_without_aapt(&self) -> Result<bool, NdkError> {
+        if !self.resources.is_empty() || self.manifest.application.has_code != Some(false) {
+            return Ok(false);
+        }
+        let path = self.build_dir.join("AndroidManifest.xml");
+        let text = read_to_string(&path).map_err(|e| NdkError::IoPathError(path, e))?;
+        let mut manifest = Element::parse(&text)?;
+
+        // 与 aapt2 生成的 APK 一样，应用名称通过资源表中的字符串引用
+        let mut strings = Vec::new();
+        if let Some(application) = manifest
+            .children
+            .iter_mut()
+            .find(|child| child.name == "application")
+            && let Some(label) = application.attributes.iter_mut().find(|attr| {
+                attr.name == "label" && attr.namespace.as_deref() == Some(ANDROID_NAMESPACE)
+            })
+            && let Value::String(text) = &label.value
+            && !text.starts_with('@')
+        {
+            strings.push(("app_name", text.clone()));
+            label.value = Value::String("@string/app_name".into());
+        }
+        let resolve = |reference: &str| {
+            let name = reference.strip_prefix("string/")?;
+            strings
+                .iter()
+                .position(|(key, _)| *key == name)
+                .map(string_resource_id)
+        };
+        if let Err(attribute) = manifest.compile_android_attributes(&resolve) {
+            println!("`{attribute}` can't be compiled without aapt, falling back to aapt");
+            return Ok(false);
+        }
+
+        println!("Compiling AndroidManifest.xml without aapt...");
+        let package = manifest.attribute("package").map_or_else(
+            || self.manifest.package.clone(),
+            |attr| attr.value.to_string(),
+        );
+        let strings = strings
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect::<Vec<_>>();
+        let mut writer = ZipWriter::new();
+        writer.add_entry(
+            "AndroidManifest.xml",
+            &axml::encode(&manifest),
+            self.should_compress("AndroidManifest.xml"),
+            4,
+        )?;
+        // 自 targetSdkVersion 30 起，resources.arsc 必须不压缩存储并按 4 字节对齐
+        writer.add_entry(
+            "resources.arsc",
+            &string_table(&package, &strings),
+            false,
+            4,
+        )?;
+        for (name, file) in self.asset_files()? {
+            let name = format!("assets/{name}");
+            let data = read(&file).map_err(|e| NdkError::IoPathError(file, e))?;
+            writer.add_entry(
+                &name,
+                &data,
+                self.should_compress(&name),
+                default_alignment(&name),
+            )?;
+        }
+        let apk = self.unaligned_apk();
+        write(&apk, writer.finish()?).map_err(|e| NdkError::IoPathError(apk, e))?;
+        Ok(true)
+    }
+
+    /// 合并所有资产目录，返回以 `/` 分隔的相对路径及其来源文件，后面目录中的同名文件覆盖前面的
+    fn asset_files(&self) -> Result<BTreeMap<String, PathBuf>, NdkError> {
+        let mut merged = BTreeMap::new();
+        for assets in &self.assets {
+            let mut files = Vec::new();
+            collect_asset_files(assets, &mut files)?;
+            for file in files {
+                let name = file
+                    .strip_prefix(assets)
+                    .unwrap()
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                merged.insert(name, file);
+            }
+        }
+        Ok(merged)
+    }
+
+    /// 传给 aapt 的资产目录。有多个资产目录时，按覆盖顺序以硬链接（不支持时复制）合并到构建目录中
+    fn assets_dir(&self) -> Result<Option<PathBuf>, NdkError> {
+        match self.assets.as_slice() {
+            [] => return Ok(None),
+            [assets] => return Ok(Some(assets.clone())),
+            _ => {}
+        }
+        let merged_dir = self.build_dir.join("merged_assets");
+        let _ = remove_dir_all(&merged_dir);
+        for (name, file) in self.asset_files()? {
+            let out = merged_dir.join(&name);
+            create_dir_all(out.parent().unwrap())?;
+            if hard_link(&file, &out).is_err() {
+                copy(&file, &out).map_err(|e| NdkError::IoPathError(file, e))?;
+            }
+        }
+        create_dir_all(&merged_dir)?;
+        Ok(Some(merged_dir))
+    }
+
     fn aapt_package<P>(&self, gen_java_dir: P, target_sdk_version: u32) -> Result<(), NdkError>
     where
         P: AsRef<Path>,
     {
-        if self.resources.is_some() {
+        if !self.resources.is_empty() {
             create_dir_all(&gen_java_dir)?;
         }
 
@@ -138,16 +413,26 @@ impl ApkConfig {
 
         if self.disable_aapt_compression {
             aapt.arg("-0").arg("");
+        } else {
+            for ext in &self.no_compress {
+                aapt.arg("-0").arg(ext.trim_start_matches('.'));
+            }
         }
 
-        if let Some(res) = &self.resources {
+        // aapt 中先出现的 `-S` 目录优先，因此按覆盖顺序倒序传入
+        for res in self.resources.iter().rev() {
             aapt.arg("-S").arg(res);
         }
 
-        if let Some(assets) = &self.assets {
+        if let Some(assets) = self.assets_dir()? {
             aapt.arg("-A").arg(assets);
         }
 
+        if !self.extra_packages.is_empty() {
+            aapt.arg("--extra-packages")
+                .arg(self.extra_packages.join(":"));
+        }
+
         if !aapt.status()?.success() {
             return Err(NdkError::CmdFailed(Box::new(aapt)));
         }
@@ -155,27 +440,33 @@ impl ApkConfig {
         Ok(())
     }
 
+    /// 将每个资源目录分别编译到 `out_dir` 下以其序号命名的子目录中，供 [`Self::aapt2_link`] 按顺序覆盖
     fn aapt2_compile<P>(&self, out_dir: P) -> Result<(), NdkError>
     where
         P: AsRef<Path>,
     {
         let _ = remove_dir_all(&out_dir);
         create_dir_all(&out_dir)?;
+        if !self.resources.is_empty() {
+            println!("Compiling apk resources...");
+        }
 
-        let mut aapt = self.build_tool(bin!("aapt2"))?;
-        println!("Compiling apk resources...");
-        aapt.arg("compile").arg("-o").arg(out_dir.as_ref());
+        for (index, res) in self.resources.iter().enumerate() {
+            let compiled_dir = out_dir.as_ref().join(index.to_string());
+            create_dir_all(&compiled_dir)?;
 
-        if self.disable_aapt_compression {
-            aapt.arg("--no-crunch");
-        }
+            let mut aapt = self.build_tool(bin!("aapt2"))?;
+            aapt.arg("compile").arg("-o").arg(&compiled_dir);
+
+            if self.disable_aapt_compression {
+                aapt.arg("--no-crunch");
+            }
 
-        if let Some(res) = &self.resources {
             aapt.arg("--dir").arg(res);
-        }
 
-        if !aapt.status()?.success() {
-            return Err(NdkError::CmdFailed(Box::new(aapt)));
+            if !aapt.status()?.success() {
+                return Err(NdkError::CmdFailed(Box::new(aapt)));
+            }
         }
 
         Ok(())
@@ -186,6 +477,8 @@ impl ApkConfig {
         compiled_dir: P,
         gen_java_dir: P,
         target_sdk_version: u32,
+        proto_format: bool,
+        emit_ids: Option<&Path>,
     ) -> Result<(), NdkError>
     where
         P: AsRef<Path>,
@@ -202,19 +495,48 @@ impl ApkConfig {
             .arg("--java")
             .arg(gen_java_dir.as_ref());
 
+        if proto_format {
+            aapt.arg("--proto-format");
+        }
+
+        if let Some(ids) = emit_ids {
+            aapt.arg("--emit-ids").arg(ids);
+        } else if let Some(ids) = &self.resource_ids {
+            aapt.arg("--stable-ids").arg(ids);
+        }
+
         if self.disable_aapt_compression {
             aapt.arg("--no-compress");
+        } else {
+            for ext in &self.no_compress {
+                aapt.arg("-0").arg(ext.trim_start_matches('.'));
+            }
         }
 
-        if let Some(assets) = &self.assets {
+        if let Some(assets) = self.assets_dir()? {
             aapt.arg("-A").arg(assets);
         }
 
-        if compiled_dir.as_ref().exists()
-            && let Ok(files) = read_dir(compiled_dir)
-        {
+        // 库的资源与应用的资源一起链接，库代码引用的 `R` 类同样需要生成
+        if !self.extra_packages.is_empty() {
+            aapt.arg("--extra-packages")
+                .arg(self.extra_packages.join(":"));
+        }
+
+        // 第一个资源目录作为基础，其余目录依次作为覆盖层，可以添加新的资源
+        if self.resources.len() > 1 {
+            aapt.arg("--auto-add-overlay");
+        }
+        for index in 0..self.resources.len() {
+            let mut files = read_dir(compiled_dir.as_ref().join(index.to_string()))?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            files.sort();
             for file in files {
-                aapt.arg(file?.path());
+                if index > 0 {
+                    aapt.arg("-R");
+                }
+                aapt.arg(file);
             }
         }
 
@@ -246,9 +568,83 @@ impl ApkConfig {
     }
 }
 
+/// 与 aapt 的默认规则一样，忽略隐藏文件和以 `~` 结尾的备份文件
+fn collect_asset_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), NdkError> {
+    for entry in read_dir(dir).map_err(|e| NdkError::IoPathError(dir.into(), e))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        if name.starts_with('.') || name.ends_with('~') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_asset_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 将父类或接口（包括间接的）在 `classes` 中的类加入 `classes`
+fn add_subclasses(classes: &mut BTreeSet<String>, supertypes: &BTreeMap<String, Vec<String>>) {
+    loop {
+        let subclasses = supertypes
+            .iter()
+            .filter(|(class, supertypes)| {
+                !classes.contains(*class)
+                    && supertypes
+                        .iter()
+                        .any(|supertype| classes.contains(supertype))
+            })
+            .map(|(class, _)| class.clone())
+            .collect::<Vec<_>>();
+        if subclasses.is_empty() {
+            break;
+        }
+        classes.extend(subclasses);
+    }
+}
+
+/// `dir` 中的 `classes.dex` 与 `classesN.dex`，按编号排序
+fn dex_files(dir: &Path) -> Result<Vec<String>, NdkError> {
+    let mut dex_files = Vec::new();
+    for entry in read_dir(dir).map_err(|e| NdkError::IoPathError(dir.into(), e))? {
+        let file_name = entry?.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        let index = match file_name
+            .strip_prefix("classes")
+            .and_then(|name| name.strip_suffix(".dex"))
+        {
+            Some("") => 1,
+            Some(index) if index.chars().all(|ch| ch.is_ascii_digit()) => index.parse::<u32>()?,
+            _ => continue,
+        };
+        dex_files.push((index, file_name.to_owned()));
+    }
+    dex_files.sort();
+    Ok(dex_files.into_iter().map(|(_, name)| name).collect())
+}
+
+/// 递归收集 `dir` 中的所有文件
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), NdkError> {
+    for entry in read_dir(dir).map_err(|e| NdkError::IoPathError(dir.into(), e))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
 pub struct UnalignedApk<'a> {
     config: &'a ApkConfig,
-    pending_libs: HashSet<String>,
+    pending_dex: Vec<String>,
+    pending_libs: BTreeSet<String>,
+    unaligned_libs: Vec<(PathBuf, Target, u64)>,
 }
 
 impl<'a> UnalignedApk<'a> {
@@ -256,7 +652,10 @@ impl<'a> UnalignedApk<'a> {
         self.config
     }
 
-    /// 将jar文件转换为dex并添加到APK中
+    /// 将jar文件转换为dex并添加到APK中。
+    ///
+    /// dex 写入清空后的 [`ApkConfig::dex_dir`]：设置了 [`ApkConfig::r8`] 时使用 R8，
+    /// [`ApkConfig::incremental_dex`] 为真时逐个类增量转换后合并，否则以 `--release` 模式运行 D8。
     pub fn put_jar(&mut self, jar_file: &Path) -> Result<(), NdkError> {
         if !jar_file.exists() {
             return Ok(());
@@ -276,9 +675,41 @@ impl<'a> UnalignedApk<'a> {
             .min_sdk_version
             .unwrap_or_else(|| self.config.ndk.default_target_platform());
 
+        // 不能混入之前构建留下的 dex
+        let dex_dir = self.config.dex_dir();
+        let _ = remove_dir_all(&dex_dir);
+        create_dir_all(&dex_dir)?;
+
+        let runtime_jar = match &self.config.core_library_desugaring {
+            Some(desugaring) => {
+                let (runtime_jar, configuration) = desugaring.resolve()?;
+                write_configuration(&configuration, &self.config.desugar_config())?;
+                // 增量转换不输出保留规则，此时 L8 不移除任何内容
+                let _ = remove_file(self.config.desugar_rules());
+                Some(runtime_jar)
+            }
+            None => None,
+        };
+
+        if let Some(r8) = &self.config.r8 {
+            self.run_r8(r8, jar_file, min_sdk_version, target_sdk_version)?;
+        } else if self.config.incremental_dex {
+            self.dex_incrementally(jar_file, min_sdk_version, target_sdk_version)?;
+        } else {
+            self.run_d8(jar_file, min_sdk_version, target_sdk_version)?;
+        }
+        if let Some(runtime_jar) = runtime_jar {
+            self.run_l8(&runtime_jar, min_sdk_version, target_sdk_version)?;
+        }
+
+        self.add_pending_dex()
+    }
+
+    /// 运行 D8 的命令
+    fn d8(&self) -> Result<Command, NdkError> {
         // 直接通过Java运行d8，绕过shell脚本以避免Linux/macOS上的脚本兼容性问题
         // （如CRLF行尾、bash版本差异等）
-        let mut d8 = match self.config.ndk.java_cmd() {
+        Ok(match self.config.ndk.java_cmd() {
             Ok(mut cmd) => {
                 match self.config.ndk.d8_classpath() {
                     Ok(classpath) => {
@@ -307,17 +738,40 @@ impl<'a> UnalignedApk<'a> {
                 fallback.current_dir(&self.config.build_dir);
                 fallback
             }
-        };
-        d8.arg("--output")
-            .arg(&self.config.build_dir)
-            .arg("--intermediate")
+        })
+    }
+
+    /// 启用脱糖时为 D8 或 R8 传入脱糖配置，`keep_rules` 为真时同时输出 L8 使用的保留规则
+    fn add_desugared_lib(&self, cmd: &mut Command, keep_rules: bool) {
+        if self.config.core_library_desugaring.is_some() {
+            cmd.arg("--desugared-lib").arg(self.config.desugar_config());
+            if keep_rules {
+                cmd.arg("--desugared-lib-pg-conf-output")
+                    .arg(self.config.desugar_rules());
+            }
+        }
+    }
+
+    /// 以 `--release` 模式运行 D8，一次转换整个 `jar_file`
+    fn run_d8(
+        &self,
+        jar_file: &Path,
+        min_sdk_version: u32,
+        target_sdk_version: u32,
+    ) -> Result<(), NdkError> {
+        let dex_dir = self.config.dex_dir();
+        let mut d8 = self.d8()?;
+        d8.arg("--release")
+            .arg("--output")
+            .arg(&dex_dir)
             .arg("--min-api")
             .arg(min_sdk_version.to_string())
             .arg("--lib")
-            .arg(&self.config.ndk.android_jar(target_sdk_version)?)
-            .arg(jar_file);
+            .arg(&self.config.ndk.android_jar(target_sdk_version)?);
+        self.add_desugared_lib(&mut d8, true);
+        d8.arg(jar_file);
 
-        let dex_file = self.config.build_dir.join("classes.dex");
+        let dex_file = dex_dir.join("classes.dex");
         let d8_output = d8.output();
         let success = match d8_output {
             Ok(output) => {
@@ -349,38 +803,295 @@ impl<'a> UnalignedApk<'a> {
         if !success {
             return Err(IoError::other("Failed to convert jar to dex").into());
         }
+        Ok(())
+    }
 
-        // 将classes.dex/classesN.dex文件添加到APK中
-        let mut dex_entries = Vec::new();
-        for entry in read_dir(&self.config.build_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            let Some(file_name) = path.file_name().and_then(|value| value.to_str()) else {
+    /// 将 `jar_file` 中内容有变化的类逐个转换为中间 dex 并缓存，然后把所有类的中间 dex 合并到
+    /// [`ApkConfig::dex_dir`]，没有变化的类不会重新转换。
+    ///
+    /// 脱糖会把父类与接口的内容带入子类的 dex，因此父类变化的类也会重新转换；
+    /// 接口变化时其影响难以追踪（例如默认方法），直接重新转换所有类。
+    fn dex_incrementally(
+        &self,
+        jar_file: &Path,
+        min_sdk_version: u32,
+        target_sdk_version: u32,
+    ) -> Result<(), NdkError> {
+        let android_jar = self.config.ndk.android_jar(target_sdk_version)?;
+        let cache_dir = self.config.build_dir.join("dex-cache");
+        // 上次转换的类文件，与每个类的中间 dex
+        let classes_dir = cache_dir.join("classes");
+        let intermediate_dir = cache_dir.join("intermediate");
+
+        // 转换结果取决于 D8 的版本、min-api、android.jar 与脱糖配置，它们变化时丢弃缓存
+        let key_file = cache_dir.join("key");
+        let mut key = format!(
+            "{}\n{}\n{min_sdk_version}\n{}\n",
+            self.config.ndk.build_tools_version().unwrap_or_default(),
+            self.config.ndk.d8_classpath().unwrap_or_default(),
+            android_jar.display()
+        );
+        if self.config.core_library_desugaring.is_some() {
+            key.push_str(&read_to_string(self.config.desugar_config())?);
+        }
+        if read_to_string(&key_file).ok().as_ref() != Some(&key) {
+            let _ = remove_dir_all(&cache_dir);
+        }
+        create_dir_all(&intermediate_dir)?;
+        write(&key_file, &key).map_err(|e| NdkError::IoPathError(key_file, e))?;
+
+        let jar = ZipArchive::open(jar_file)?;
+        let mut classes = BTreeMap::new();
+        // 每个类的父类与接口
+        let mut supertypes = BTreeMap::new();
+        let mut dirty = BTreeSet::new();
+        let mut interface_changed = false;
+        for entry in jar.entries() {
+            let Some(class) = entry.name.strip_suffix(".class") else {
                 continue;
             };
-            if file_name == "classes.dex"
-                || (file_name.starts_with("classes")
-                    && file_name.ends_with(".dex")
-                    && file_name[7..file_name.len() - 4]
-                        .chars()
-                        .all(|ch| ch.is_ascii_digit()))
+            let data = jar.read(entry)?;
+            let info = ClassFile::parse(Path::new(&entry.name), &data)?;
+            if !(read(classes_dir.join(&entry.name)).is_ok_and(|cached| cached == data)
+                && intermediate_dir.join(format!("{class}.dex")).exists())
             {
-                dex_entries.push(file_name.to_string());
+                interface_changed |= info.is_interface;
+                dirty.insert(class.to_owned());
             }
+            supertypes.insert(
+                class.to_owned(),
+                info.super_class
+                    .into_iter()
+                    .chain(info.interfaces)
+                    .collect(),
+            );
+            classes.insert(class.to_owned(), (entry.name.as_str(), data));
         }
-        dex_entries.sort();
 
-        if !dex_entries.is_empty() {
-            let mut aapt = self.config.build_tool(bin!("aapt"))?;
-            aapt.arg("add").arg(self.config.unaligned_apk());
-            for dex_entry in &dex_entries {
-                aapt.arg(dex_entry);
+        // 删除已经不存在的类的缓存，它们同样使子类需要重新转换
+        let mut cached = Vec::new();
+        collect_files(&intermediate_dir, &mut cached)?;
+        for dex in cached {
+            let class = dex
+                .strip_prefix(&intermediate_dir)
+                .unwrap()
+                .with_extension("");
+            let class = class.to_string_lossy().replace('\\', "/");
+            if !classes.contains_key(&class) {
+                let cached = classes_dir.join(format!("{class}.class"));
+                if let Ok(data) = read(&cached) {
+                    interface_changed |=
+                        ClassFile::parse(&cached, &data).is_ok_and(|info| info.is_interface);
+                }
+                remove_file(&dex)?;
+                let _ = remove_file(cached);
+                dirty.insert(class);
             }
+        }
 
-            if !aapt.status()?.success() {
-                return Err(NdkError::CmdFailed(Box::new(aapt)));
+        if interface_changed {
+            dirty.extend(classes.keys().cloned());
+        } else {
+            add_subclasses(&mut dirty, &supertypes);
+        }
+        let changed = classes
+            .iter()
+            .filter(|(class, _)| dirty.contains(*class))
+            .map(|(_, (name, data))| (*name, data))
+            .collect::<Vec<_>>();
+
+        if !changed.is_empty() {
+            println!("Dexing {} changed class(es)...", changed.len());
+            let changed_jar = cache_dir.join("changed.jar");
+            let mut writer = ZipWriter::new();
+            for (name, data) in &changed {
+                writer.add_entry(name, data, false, default_alignment(name))?;
+            }
+            write(&changed_jar, writer.finish()?)
+                .map_err(|e| NdkError::IoPathError(changed_jar.clone(), e))?;
+
+            // 以完整的 jar 作为 classpath，使脱糖可以看到未变化的父类与接口
+            let mut d8 = self.d8()?;
+            d8.arg("--debug")
+                .arg("--intermediate")
+                .arg("--file-per-class-file")
+                .arg("--output")
+                .arg(&intermediate_dir)
+                .arg("--min-api")
+                .arg(min_sdk_version.to_string())
+                .arg("--lib")
+                .arg(&android_jar)
+                .arg("--classpath")
+                .arg(jar_file);
+            self.add_desugared_lib(&mut d8, false);
+            d8.arg(&changed_jar);
+            if !d8.status()?.success() {
+                return Err(NdkError::CmdFailed(Box::new(d8)));
+            }
+            for (name, data) in changed {
+                let path = classes_dir.join(name);
+                create_dir_all(path.parent().unwrap())?;
+                write(&path, data).map_err(|e| NdkError::IoPathError(path, e))?;
+            }
+        }
+
+        // 合并所有中间 dex
+        let mut intermediate = Vec::new();
+        collect_files(&intermediate_dir, &mut intermediate)?;
+        intermediate.sort();
+        let merge_input = cache_dir.join("intermediate.zip");
+        let mut writer = ZipWriter::new();
+        for dex in intermediate {
+            let name = dex.strip_prefix(&intermediate_dir).unwrap();
+            let name = name.to_string_lossy().replace('\\', "/");
+            let data = read(&dex).map_err(|e| NdkError::IoPathError(dex, e))?;
+            writer.add_entry(&name, &data, false, default_alignment(&name))?;
+        }
+        write(&merge_input, writer.finish()?)
+            .map_err(|e| NdkError::IoPathError(merge_input.clone(), e))?;
+
+        let mut d8 = self.d8()?;
+        d8.arg("--debug")
+            .arg("--output")
+            .arg(self.config.dex_dir())
+            .arg("--min-api")
+            .arg(min_sdk_version.to_string());
+        self.add_desugared_lib(&mut d8, false);
+        d8.arg(&merge_input);
+        if !d8.status()?.success() {
+            return Err(NdkError::CmdFailed(Box::new(d8)));
+        }
+        Ok(())
+    }
+
+    /// 使用 R8 将 `jar_file` 转换为 dex，并将混淆映射写入 [`ApkConfig::mapping`]。
+    ///
+    /// 通过 JNI 访问的类根据已添加的动态库确定，因此须在添加动态库之后调用。
+    fn run_r8(
+        &self,
+        r8: &R8Config,
+        jar_file: &Path,
+        min_sdk_version: u32,
+        target_sdk_version: u32,
+    ) -> Result<(), NdkError> {
+        let mut rules = self
+            .config
+            .manifest
+            .class_names()
+            .iter()
+            .map(|name| format!("-keep class {name} {{ <init>(); }}"))
+            .collect::<Vec<_>>();
+        if self.config.manifest_override.is_some() || !self.config.library_manifests.is_empty() {
+            // `manifest_override` 与库清单中声明的组件只出现在合并后的清单中
+            let path = self.config.build_dir.join("AndroidManifest.xml");
+            let text = read_to_string(&path).map_err(|e| NdkError::IoPathError(path, e))?;
+            for name in component_names(&Element::parse(&text)?) {
+                let rule = format!("-keep class {name} {{ <init>(); }}");
+                if !rules.contains(&rule) {
+                    rules.push(rule);
+                }
             }
         }
+        let jar = ZipArchive::open(jar_file)?;
+        let classes = jar
+            .entries()
+            .iter()
+            .filter(|entry| entry.name.ends_with(".class"))
+            .map(|entry| ClassFile::parse(Path::new(&entry.name), &jar.read(entry)?))
+            .collect::<Result<Vec<_>, _>>()?;
+        let libs = self
+            .pending_libs
+            .iter()
+            .map(|lib| self.config.build_dir.join(lib))
+            .collect::<Vec<_>>();
+        rules.extend(keep_rules(&classes, &libs)?);
+        let rules_file = self.config.build_dir.join("r8-rules.pro");
+        write(&rules_file, rules.join("\n") + "\n")
+            .map_err(|e| NdkError::IoPathError(rules_file.clone(), e))?;
+
+        let mut cmd = self.config.ndk.java_cmd()?;
+        cmd.arg("-classpath")
+            .arg(self.config.ndk.d8_classpath()?)
+            .arg("com.android.tools.r8.R8")
+            .arg("--release")
+            .arg("--output")
+            .arg(self.config.dex_dir())
+            .arg("--min-api")
+            .arg(min_sdk_version.to_string())
+            .arg("--lib")
+            .arg(self.config.ndk.android_jar(target_sdk_version)?)
+            .arg("--pg-map-output")
+            .arg(self.config.mapping())
+            .arg("--pg-conf")
+            .arg(&rules_file);
+        for rules in &r8.proguard_rules {
+            cmd.arg("--pg-conf").arg(rules);
+        }
+        self.add_desugared_lib(&mut cmd, true);
+        cmd.arg(jar_file);
+        println!("Shrinking classes with R8...");
+        if !cmd.status()?.success() {
+            return Err(NdkError::CmdFailed(Box::new(cmd)));
+        }
+        Ok(())
+    }
+
+    /// 使用 L8 将脱糖运行时 `runtime_jar` 转换为 dex，编号接在应用的 dex 之后。
+    ///
+    /// D8 或 R8 输出了保留规则时以 `--release` 模式运行并移除应用没有用到的部分
+    fn run_l8(
+        &self,
+        runtime_jar: &Path,
+        min_sdk_version: u32,
+        target_sdk_version: u32,
+    ) -> Result<(), NdkError> {
+        let out_dir = self.config.build_dir.join("l8");
+        let _ = remove_dir_all(&out_dir);
+        create_dir_all(&out_dir)?;
+
+        let mut cmd = self.config.ndk.java_cmd()?;
+        cmd.arg("-classpath")
+            .arg(self.config.ndk.d8_classpath()?)
+            .arg("com.android.tools.r8.L8")
+            .arg("--output")
+            .arg(&out_dir)
+            .arg("--min-api")
+            .arg(min_sdk_version.to_string())
+            .arg("--lib")
+            .arg(self.config.ndk.android_jar(target_sdk_version)?)
+            .arg("--desugared-lib")
+            .arg(self.config.desugar_config());
+        let rules = self.config.desugar_rules();
+        if rules.exists() {
+            cmd.arg("--release").arg("--pg-conf").arg(&rules);
+        } else {
+            cmd.arg("--debug");
+        }
+        cmd.arg(runtime_jar);
+        println!("Dexing desugared library with L8...");
+        if !cmd.status()?.success() {
+            return Err(NdkError::CmdFailed(Box::new(cmd)));
+        }
+
+        let dex_dir = self.config.dex_dir();
+        let mut index = dex_files(&dex_dir)?.len();
+        for name in dex_files(&out_dir)? {
+            index += 1;
+            let dex = match index {
+                1 => "classes.dex".to_owned(),
+                index => format!("classes{index}.dex"),
+            };
+            rename(out_dir.join(name), dex_dir.join(dex))?;
+        }
+        Ok(())
+    }
+
+    /// 收集 D8、R8 或 L8 写入 [`ApkConfig::dex_dir`] 的 dex 文件，在对齐时写入 APK。
+    ///
+    /// [`Self::put_jar`] 会调用它；打包目录相同的其他 APK 可以直接调用它，使用已经转换的 dex
+    pub fn add_pending_dex(&mut self) -> Result<(), NdkError> {
+        let dex_entries = dex_files(&self.config.dex_dir())?;
+        self.pending_dex.extend(dex_entries);
 
         Ok(())
     }
@@ -397,7 +1108,7 @@ impl<'a> UnalignedApk<'a> {
 
         match self.config.strip {
             StripConfig::Default => {
-                copy(path, out)?;
+                copy(path, &out)?;
             }
             StripConfig::Strip | StripConfig::Split => {
                 let obj_copy = self.config.ndk.toolchain_bin("objcopy", target)?;
@@ -432,7 +1143,7 @@ impl<'a> UnalignedApk<'a> {
                     let mut cmd = Command::new(obj_copy);
                     cmd.stdin(Stdio::null())
                         .arg(format!("--add-gnu-debuglink={}", dwarf_path.display()))
-                        .arg(out);
+                        .arg(&out);
 
                     if !cmd.status()?.success() {
                         return Err(NdkError::CmdFailed(Box::new(cmd)));
@@ -441,11 +1152,16 @@ impl<'a> UnalignedApk<'a> {
             }
         }
 
-        // Pass UNIX path separators to `aapt` on non-UNIX systems, ensuring the resulting separator
-        // is compatible with the target device instead of the host platform.
+        // Use UNIX path separators for the entry name on non-UNIX systems, ensuring the resulting
+        // separator is compatible with the target device instead of the host platform.
         // Otherwise, it results in a runtime error when loading the \NativeActivity `.so` library.
         let lib_path_unix = lib_path.to_str().unwrap().replace('\\', "/");
 
+        if let Some(align) = check_page_alignment(&out, target)? {
+            self.unaligned_libs
+                .push((path.to_path_buf(), target, align));
+        }
+
         self.pending_libs.insert(lib_path_unix);
 
         Ok(())
@@ -468,52 +1184,172 @@ impl<'a> UnalignedApk<'a> {
         Ok(())
     }
 
+    /// 将链接好的APK中的条目与待添加的dex和动态库写入最终的APK，并在写入时完成对齐
     pub fn add_pending_libs_and_align(self) -> Result<UnsignedApk<'a>, NdkError> {
-        let mut aapt = self.config.build_tool(bin!("aapt"))?;
-        aapt.arg("add");
+        let writer = self.write_entries(str::to_owned)?;
+        let apk = self.config.apk();
+        write(&apk, writer.finish()?).map_err(|e| NdkError::IoPathError(apk, e))?;
 
-        if self.config.disable_aapt_compression {
-            aapt.arg("-0").arg("");
-        }
+        Ok(UnsignedApk(self.config))
+    }
 
-        aapt.arg(self.config.unaligned_apk());
+    /// 按 `base` 模块的目录结构写入 Android App Bundle，并添加 `BundleConfig.pb`
+    pub fn add_pending_libs_and_bundle(self) -> Result<UnsignedBundle<'a>, NdkError> {
+        let mut writer = self.write_entries(bundle_entry_name)?;
+        writer.add_entry(
+            "BundleConfig.pb",
+            &bundle_config(
+                self.config
+                    .bundletool_version
+                    .as_deref()
+                    .unwrap_or(DEFAULT_BUNDLETOOL_VERSION),
+                &self.config.no_compress,
+            ),
+            true,
+            4,
+        )?;
+        let aab = self.config.aab();
+        write(&aab, writer.finish()?).map_err(|e| NdkError::IoPathError(aab, e))?;
+
+        Ok(UnsignedBundle(self.config))
+    }
 
-        for lib_path_unix in self.pending_libs {
-            aapt.arg(lib_path_unix);
+    fn write_entries(&self, entry_name: fn(&str) -> String) -> Result<ZipWriter, NdkError> {
+        if !self.unaligned_libs.is_empty() {
+            return Err(NdkError::UnalignedLoadSegments {
+                libs: self.unaligned_libs.clone(),
+            });
         }
 
-        if !aapt.status()?.success() {
-            return Err(NdkError::CmdFailed(Box::new(aapt)));
+        let unaligned_apk = self.config.unaligned_apk();
+        let archive = ZipArchive::open(&unaligned_apk)?;
+        let pending = self
+            .pending_dex
+            .iter()
+            .chain(&self.pending_libs)
+            .collect::<Vec<_>>();
+
+        // 按名称排序写入，使输出与文件系统及链接工具的顺序无关
+        let mut names = archive
+            .entries()
+            .iter()
+            .map(|entry| &entry.name)
+            // 链接产物中的同名条目以新添加的文件为准
+            .filter(|name| !pending.contains(name))
+            .chain(pending.iter().copied())
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let mut writer = ZipWriter::new();
+        for name in names {
+            if let Some(entry) = archive.entry(name).filter(|_| !pending.contains(&name)) {
+                writer.copy_entry_as(
+                    &archive,
+                    entry,
+                    &entry_name(name),
+                    default_alignment(name),
+                )?;
+                continue;
+            }
+            let path = if self.pending_dex.contains(name) {
+                self.config.dex_dir().join(name)
+            } else {
+                self.config.build_dir.join(name)
+            };
+            let data = read(&path).map_err(|e| NdkError::IoPathError(path, e))?;
+            writer.add_entry(
+                &entry_name(name),
+                &data,
+                self.config.should_compress(name),
+                default_alignment(name),
+            )?;
         }
+        Ok(writer)
+    }
+}
 
-        let mut zipalign = self.config.build_tool(bin!("zipalign"))?;
-        zipalign
-            .arg("-f")
-            .arg("-v")
-            .arg("4")
-            .arg(self.config.unaligned_apk())
-            .arg(self.config.apk());
+/// `base` 模块中的条目路径，参见 <https://developer.android.com/guide/app-bundle/app-bundle-format>
+fn bundle_entry_name(name: &str) -> String {
+    if name == "AndroidManifest.xml" {
+        "base/manifest/AndroidManifest.xml".to_owned()
+    } else if name == "resources.pb"
+        || ["res/", "assets/", "lib/"]
+            .iter()
+            .any(|dir| name.starts_with(dir))
+    {
+        format!("base/{name}")
+    } else if !name.contains('/') && name.ends_with(".dex") {
+        format!("base/dex/{name}")
+    } else {
+        format!("base/root/{name}")
+    }
+}
 
-        if !zipalign.status()?.success() {
-            return Err(NdkError::CmdFailed(Box::new(zipalign)));
-        }
+/// 手工编码的 `BundleConfig { bundletool { version } compression { uncompressed_glob } }` protobuf 消息，
+/// `no_compress` 中的扩展名在 Google Play 生成的 APK 中同样不压缩
+fn bundle_config(bundletool_version: &str, no_compress: &[String]) -> Vec<u8> {
+    let mut bundletool = Vec::new();
+    protobuf_field(&mut bundletool, 2, bundletool_version.as_bytes());
+    let mut config = Vec::new();
+    protobuf_field(&mut config, 1, &bundletool);
+
+    let mut compression = Vec::new();
+    for ext in no_compress {
+        let glob = format!("**.{}", ext.trim_start_matches('.'));
+        protobuf_field(&mut compression, 1, glob.as_bytes());
+    }
+    if !compression.is_empty() {
+        protobuf_field(&mut config, 3, &compression);
+    }
+    config
+}
 
-        Ok(UnsignedApk(self.config))
+/// 写入长度前缀的 protobuf 字段（wire type 2）
+fn protobuf_field(out: &mut Vec<u8>, number: u8, value: &[u8]) {
+    out.push(number << 3 | 2);
+    let mut len = value.len();
+    while len >= 0x80 {
+        out.push(len as u8 | 0x80);
+        len >>= 7;
     }
+    out.push(len as u8);
+    out.extend_from_slice(value);
 }
 
 pub struct UnsignedApk<'a>(&'a ApkConfig);
 
 impl<'a> UnsignedApk<'a> {
     pub fn sign(self, key: Key) -> Result<Apk, NdkError> {
+        if !self.0.use_apksigner {
+            let signer = Signer::from_key(&key)?;
+            let path = self.0.apk();
+            let data = read(&path).map_err(|e| NdkError::IoPathError(path.clone(), e))?;
+            // 清单中未声明 minSdkVersion 时，系统按 1 处理
+            let min_sdk_version = self.0.manifest.sdk.min_sdk_version.unwrap_or(1);
+            let signed = signer.sign_apk(data, min_sdk_version)?;
+            write(&path, signed).map_err(|e| NdkError::IoPathError(path, e))?;
+            return Ok(Apk::from_config(self.0));
+        }
+
         let mut apksigner = self.0.build_tool(bat!("apksigner"))?;
         apksigner
             .arg("sign")
             .arg("--ks")
             .arg(&key.path)
             .arg("--ks-pass")
-            .arg(format!("pass:{}", key.password))
-            .arg(self.0.apk());
+            .arg(format!("pass:{}", key.password));
+        if let Some(alias) = &key.alias {
+            apksigner.arg("--ks-key-alias").arg(alias);
+        }
+        if let Some(key_password) = &key.key_password {
+            apksigner
+                .arg("--key-pass")
+                .arg(format!("pass:{key_password}"));
+        }
+        if let Some(keystore_type) = key.keystore_type {
+            apksigner.arg("--ks-type").arg(keystore_type.to_string());
+        }
+        apksigner.arg(self.0.apk());
         if !apksigner.status()?.success() {
             return Err(NdkError::CmdFailed(Box::new(apksigner)));
         }
@@ -521,6 +1357,22 @@ impl<'a> UnsignedApk<'a> {
     }
 }
 
+pub struct UnsignedBundle<'a>(&'a ApkConfig);
+
+impl<'a> UnsignedBundle<'a> {
+    /// 为 App Bundle 添加 JAR 签名（上传密钥签名），返回 `.aab` 的路径。
+    ///
+    /// APK 签名方案 v2/v3 不适用于 App Bundle，因此始终使用内置签名器，忽略 `use_apksigner`。
+    pub fn sign(self, key: Key) -> Result<PathBuf, NdkError> {
+        let signer = Signer::from_key(&key)?;
+        let path = self.0.aab();
+        let data = read(&path).map_err(|e| NdkError::IoPathError(path.clone(), e))?;
+        let signed = signer.sign_jar(data)?;
+        write(&path, signed).map_err(|e| NdkError::IoPathError(path.clone(), e))?;
+        Ok(path)
+    }
+}
+
 pub struct Apk {
     path: PathBuf,
     package_name: String,
@@ -539,6 +1391,11 @@ impl Apk {
         }
     }
 
+    /// 已签名 APK 的路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn reverse_port_forwarding(&self, device_serial: Option<&str>) -> Result<(), NdkError> {
         for (from, to) in &self.reverse_port_forward {
             println!("Reverse port forwarding from {} to {}", from, to);
@@ -627,3 +1484,35 @@ impl Apk {
             .map_err(|e| NdkError::NotAUid(e, uid.to_owned()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subclasses_of_changed_classes() {
+        let supertypes = [
+            ("a/Base", vec!["java/lang/Object"]),
+            ("a/Middle", vec!["a/Base"]),
+            ("a/Leaf", vec!["a/Middle", "a/Listener"]),
+            ("a/Other", vec!["java/lang/Object", "a/Listener"]),
+        ]
+        .into_iter()
+        .map(|(class, supertypes)| {
+            let supertypes = supertypes.into_iter().map(String::from).collect();
+            (class.to_owned(), supertypes)
+        })
+        .collect::<BTreeMap<_, Vec<_>>>();
+
+        let mut classes = BTreeSet::from(["a/Base".to_owned()]);
+        add_subclasses(&mut classes, &supertypes);
+        assert_eq!(
+            classes.iter().collect::<Vec<_>>(),
+            ["a/Base", "a/Leaf", "a/Middle"]
+        );
+
+        let mut classes = BTreeSet::from(["a/Removed".to_owned()]);
+        add_subclasses(&mut classes, &supertypes);
+        assert_eq!(classes.len(), 1);
+    }
+}
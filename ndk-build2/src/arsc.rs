@@ -0,0 +1,85 @@
+//! 生成只包含字符串资源的最小资源表（`resources.arsc`），供不需要 aapt2 的 APK 使用。
+//!
+//! 参见 AOSP `frameworks/base/libs/androidfw/include/androidfw/ResourceTypes.h`。
+
+use crate::axml::{StringPool, chunk, res_value, u32s};
+
+const RES_TABLE_TYPE: u16 = 0x0002;
+const RES_TABLE_PACKAGE_TYPE: u16 = 0x0200;
+const RES_TABLE_TYPE_TYPE: u16 = 0x0201;
+const RES_TABLE_TYPE_SPEC_TYPE: u16 = 0x0202;
+
+const TYPE_STRING: u8 = 0x03;
+/// 应用自身资源的包 ID
+const PACKAGE_ID: u32 = 0x7f;
+/// `ResTable_config` 的大小，所有字段为零表示默认配置
+const CONFIG_SIZE: u32 = 64;
+
+/// 第 `index` 个字符串资源的 ID，即 `@string/<name>`
+pub fn string_resource_id(index: usize) -> u32 {
+    PACKAGE_ID << 24 | 1 << 16 | index as u32
+}
+
+/// 为 `package` 生成包含 `strings`（名称与值）的资源表，资源 ID 见 [`string_resource_id`]
+pub fn string_table(package: &str, strings: &[(&str, &str)]) -> Vec<u8> {
+    let mut values = StringPool::default();
+    let mut keys = StringPool::default();
+    let mut types = StringPool::default();
+    types.add("string");
+
+    let count = strings.len() as u32;
+    let mut entries = Vec::new();
+    for (name, value) in strings {
+        // ResTable_entry：大小、标志与键名
+        entries.extend(8u16.to_le_bytes());
+        entries.extend(0u16.to_le_bytes());
+        entries.extend(keys.add(name).to_le_bytes());
+        entries.extend(res_value(TYPE_STRING, values.add(value)));
+    }
+
+    let mut type_spec_header = vec![1, 0, 0, 0];
+    type_spec_header.extend(count.to_le_bytes());
+    let type_spec = chunk(
+        RES_TABLE_TYPE_SPEC_TYPE,
+        &type_spec_header,
+        &u32s(&vec![0; strings.len()]),
+    );
+
+    // 每个条目占 16 字节，偏移数组之后紧跟条目
+    let mut type_header = vec![1, 0, 0, 0];
+    type_header.extend(count.to_le_bytes());
+    type_header.extend((8 + 12 + CONFIG_SIZE + count * 4).to_le_bytes());
+    let mut config = CONFIG_SIZE.to_le_bytes().to_vec();
+    config.resize(CONFIG_SIZE as usize, 0);
+    type_header.extend(config);
+    let offsets = (0..count).map(|i| i * 16).collect::<Vec<_>>();
+    let mut type_body = u32s(&offsets);
+    type_body.extend(entries);
+    let table_type = chunk(RES_TABLE_TYPE_TYPE, &type_header, &type_body);
+
+    let type_strings = types.encode();
+    let key_strings = keys.encode();
+    let mut name = package.encode_utf16().collect::<Vec<_>>();
+    name.resize(128, 0);
+    let package_header_size = 8 + 4 + 256 + 5 * 4;
+    let mut package_header = PACKAGE_ID.to_le_bytes().to_vec();
+    package_header.extend(name.iter().flat_map(|c| c.to_le_bytes()));
+    package_header.extend(u32s(&[
+        package_header_size,
+        1,
+        package_header_size + type_strings.len() as u32,
+        count,
+        0,
+    ]));
+    let package = chunk(
+        RES_TABLE_PACKAGE_TYPE,
+        &package_header,
+        &[type_strings, key_strings, type_spec, table_type].concat(),
+    );
+
+    chunk(
+        RES_TABLE_TYPE,
+        &1u32.to_le_bytes(),
+        &[values.encode(), package].concat(),
+    )
+}
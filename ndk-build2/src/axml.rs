@@ -0,0 +1,1041 @@
+//! Android 二进制 XML（AXML）格式，即 APK 中编译后的 `AndroidManifest.xml` 与 `res/**/*.xml`。
+//!
+//! 参见 AOSP `frameworks/base/libs/androidfw/include/androidfw/ResourceTypes.h`。
+
+use {
+    crate::error::NdkError,
+    quick_xml::{Reader, XmlVersion, escape::resolve_predefined_entity, events::Event},
+    std::fmt::{self, Display, Formatter},
+};
+
+const RES_STRING_POOL_TYPE: u16 = 0x0001;
+const RES_XML_TYPE: u16 = 0x0003;
+const RES_XML_START_NAMESPACE_TYPE: u16 = 0x0100;
+const RES_XML_END_NAMESPACE_TYPE: u16 = 0x0101;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+const RES_XML_CDATA_TYPE: u16 = 0x0104;
+const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+
+const UTF8_FLAG: u32 = 0x100;
+const NO_INDEX: u32 = 0xffffffff;
+
+const TYPE_REFERENCE: u8 = 0x01;
+const TYPE_ATTRIBUTE: u8 = 0x02;
+const TYPE_STRING: u8 = 0x03;
+const TYPE_FLOAT: u8 = 0x04;
+const TYPE_INT_DEC: u8 = 0x10;
+const TYPE_INT_HEX: u8 = 0x11;
+const TYPE_INT_BOOLEAN: u8 = 0x12;
+const TYPE_INT_COLOR_ARGB8: u8 = 0x1c;
+const TYPE_INT_COLOR_RGB4: u8 = 0x1f;
+
+/// XML 元素
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Element {
+    /// 在此元素上声明的命名空间（前缀与 URI）
+    pub namespaces: Vec<(String, String)>,
+    pub namespace: Option<String>,
+    pub name: String,
+    pub attributes: Vec<Attribute>,
+    pub children: Vec<Element>,
+    /// 元素中的文本内容
+    pub text: Option<String>,
+    /// 元素在源文件中的行号（从 1 开始），未知时为 0
+    pub line: u32,
+}
+
+impl Element {
+    /// 按名称查找属性（忽略命名空间）
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|attr| attr.name == name)
+    }
+
+    /// 按名称查找子元素
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn write(
+        &self,
+        f: &mut Formatter<'_>,
+        prefixes: &mut Vec<(String, String)>,
+        depth: usize,
+    ) -> fmt::Result {
+        let indent = "    ".repeat(depth);
+        let declared = prefixes.len();
+        prefixes.extend(self.namespaces.iter().cloned());
+
+        write!(
+            f,
+            "{indent}<{}",
+            qualified_name(prefixes, &self.namespace, &self.name)
+        )?;
+        for (prefix, uri) in &self.namespaces {
+            write!(f, " xmlns:{prefix}=\"{}\"", escape(uri))?;
+        }
+        for attr in &self.attributes {
+            write!(
+                f,
+                " {}=\"{}\"",
+                qualified_name(prefixes, &attr.namespace, &attr.name),
+                escape(&attr.value.to_string())
+            )?;
+        }
+        if self.children.is_empty() && self.text.is_none() {
+            writeln!(f, " />")?;
+        } else {
+            writeln!(f, ">")?;
+            if let Some(text) = &self.text {
+                writeln!(f, "{indent}    {}", escape(text))?;
+            }
+            for child in &self.children {
+                child.write(f, prefixes, depth + 1)?;
+            }
+            writeln!(
+                f,
+                "{indent}</{}>",
+                qualified_name(prefixes, &self.namespace, &self.name)
+            )?;
+        }
+
+        prefixes.truncate(declared);
+        Ok(())
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+        self.write(f, &mut Vec::new(), 0)
+    }
+}
+
+/// XML 属性
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub namespace: Option<String>,
+    pub name: String,
+    /// 属性名对应的资源 ID，例如 `android:versionCode` 为 `0x0101021b`
+    pub resource_id: Option<u32>,
+    pub value: Value,
+}
+
+/// 编译后的属性值（`Res_value`）
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    /// `@0x7f010000` 形式的资源引用
+    Reference(u32),
+    /// `?0x7f010000` 形式的主题属性引用
+    Attribute(u32),
+    Int(i32),
+    Hex(u32),
+    Bool(bool),
+    Float(f32),
+    /// ARGB 颜色
+    Color(u32),
+    /// 其他类型（尺寸、分数等）的原始数据
+    Other {
+        data_type: u8,
+        data: u32,
+    },
+}
+
+impl Value {
+    /// 整数值，字符串形式的数字也会被解析
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value as i64),
+            Self::Hex(value) => Some(*value as i64),
+            Self::String(value) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(value) => f.write_str(value),
+            Self::Reference(id) => write!(f, "@0x{id:08x}"),
+            Self::Attribute(id) => write!(f, "?0x{id:08x}"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Hex(value) => write!(f, "0x{value:08x}"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Float(value) => write!(f, "{value}"),
+            Self::Color(value) => write!(f, "#{value:08x}"),
+            Self::Other { data_type, data } => write!(f, "(type 0x{data_type:02x})0x{data:08x}"),
+        }
+    }
+}
+
+/// 将二进制 XML 解码为元素树，返回根元素
+pub fn decode(data: &[u8]) -> Result<Element, NdkError> {
+    let (chunk_type, header_size, size) = chunk_header(data, 0)?;
+    if chunk_type != RES_XML_TYPE {
+        return Err(invalid("not a binary XML file"));
+    }
+    let end = size.min(data.len());
+
+    let mut strings = Vec::new();
+    let mut resource_ids = Vec::new();
+    let mut pending_namespaces = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+
+    let mut offset = header_size;
+    while offset + 8 <= end {
+        let (chunk_type, header_size, size) = chunk_header(data, offset)?;
+        if size < 8 || offset + size > end {
+            return Err(invalid("truncated chunk"));
+        }
+        let chunk = &data[offset..offset + size];
+        let string = |index: u32| -> Result<Option<String>, NdkError> {
+            if index == NO_INDEX {
+                return Ok(None);
+            }
+            strings
+                .get(index as usize)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| invalid("string index out of range"))
+        };
+        // 节点头之后的扩展数据
+        let ext = chunk.get(header_size..).unwrap_or_default();
+
+        match chunk_type {
+            RES_STRING_POOL_TYPE => strings = decode_string_pool(chunk)?,
+            RES_XML_RESOURCE_MAP_TYPE => {
+                resource_ids = ext
+                    .chunks_exact(4)
+                    .map(|id| u32::from_le_bytes(id.try_into().unwrap()))
+                    .collect();
+            }
+            RES_XML_START_NAMESPACE_TYPE => {
+                let prefix = string(read_u32(ext, 0)?)?.unwrap_or_default();
+                let uri = string(read_u32(ext, 4)?)?.unwrap_or_default();
+                pending_namespaces.push((prefix, uri));
+            }
+            RES_XML_END_NAMESPACE_TYPE => {}
+            RES_XML_START_ELEMENT_TYPE => {
+                let attribute_start = read_u16(ext, 8)? as usize;
+                let attribute_size = read_u16(ext, 10)? as usize;
+                let attribute_count = read_u16(ext, 12)? as usize;
+                let mut attributes = Vec::with_capacity(attribute_count);
+                for i in 0..attribute_count {
+                    let at = attribute_start + i * attribute_size;
+                    let name_index = read_u32(ext, at + 4)?;
+                    let raw_value = read_u32(ext, at + 8)?;
+                    let data_type = *ext
+                        .get(at + 15)
+                        .ok_or_else(|| invalid("truncated attribute"))?;
+                    let value = read_u32(ext, at + 16)?;
+                    let value = match (raw_value, data_type) {
+                        (raw, _) if raw != NO_INDEX => {
+                            Value::String(string(raw)?.unwrap_or_default())
+                        }
+                        (_, TYPE_STRING) => Value::String(string(value)?.unwrap_or_default()),
+                        (_, TYPE_REFERENCE) => Value::Reference(value),
+                        (_, TYPE_ATTRIBUTE) => Value::Attribute(value),
+                        (_, TYPE_FLOAT) => Value::Float(f32::from_bits(value)),
+                        (_, TYPE_INT_DEC) => Value::Int(value as i32),
+                        (_, TYPE_INT_HEX) => Value::Hex(value),
+                        (_, TYPE_INT_BOOLEAN) => Value::Bool(value != 0),
+                        (_, TYPE_INT_COLOR_ARGB8..=TYPE_INT_COLOR_RGB4) => Value::Color(value),
+                        (_, data_type) => Value::Other {
+                            data_type,
+                            data: value,
+                        },
+                    };
+                    attributes.push(Attribute {
+                        namespace: string(read_u32(ext, at)?)?,
+                        name: string(name_index)?.unwrap_or_default(),
+                        resource_id: resource_ids.get(name_index as usize).copied(),
+                        value,
+                    });
+                }
+                stack.push(Element {
+                    namespaces: std::mem::take(&mut pending_namespaces),
+                    namespace: string(read_u32(ext, 0)?)?,
+                    name: string(read_u32(ext, 4)?)?.unwrap_or_default(),
+                    attributes,
+                    line: read_u32(chunk, 8)?,
+                    ..Default::default()
+                });
+            }
+            RES_XML_END_ELEMENT_TYPE => {
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| invalid("unbalanced end element"))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            }
+            RES_XML_CDATA_TYPE => {
+                if let (Some(text), Some(element)) = (string(read_u32(ext, 0)?)?, stack.last_mut())
+                {
+                    element.text = Some(text);
+                }
+            }
+            _ => {}
+        }
+        offset += size;
+    }
+
+    root.ok_or_else(|| invalid("no root element"))
+}
+
+/// 解码字符串池（`ResStringPool_header` 开头的完整块）
+pub(crate) fn decode_string_pool(chunk: &[u8]) -> Result<Vec<String>, NdkError> {
+    let header_size = read_u16(chunk, 2)? as usize;
+    let string_count = read_u32(chunk, 8)? as usize;
+    let utf8 = read_u32(chunk, 16)? & UTF8_FLAG != 0;
+    let strings_start = read_u32(chunk, 20)? as usize;
+
+    let mut strings = Vec::with_capacity(string_count);
+    for i in 0..string_count {
+        let at = strings_start + read_u32(chunk, header_size + i * 4)? as usize;
+        let string = if utf8 {
+            // UTF-16 长度与 UTF-8 字节长度，各占 1 或 2 字节
+            let (_, at) = utf8_length(chunk, at)?;
+            let (len, at) = utf8_length(chunk, at)?;
+            let bytes = chunk
+                .get(at..at + len)
+                .ok_or_else(|| invalid("truncated string"))?;
+            String::from_utf8_lossy(bytes).into_owned()
+        } else {
+            let mut len = read_u16(chunk, at)? as usize;
+            let mut at = at + 2;
+            if len & 0x8000 != 0 {
+                len = (len & 0x7fff) << 16 | read_u16(chunk, at)? as usize;
+                at += 2;
+            }
+            let units = (0..len)
+                .map(|i| read_u16(chunk, at + i * 2))
+                .collect::<Result<Vec<_>, _>>()?;
+            String::from_utf16_lossy(&units)
+        };
+        strings.push(string);
+    }
+    Ok(strings)
+}
+
+fn utf8_length(data: &[u8], at: usize) -> Result<(usize, usize), NdkError> {
+    let first = *data.get(at).ok_or_else(|| invalid("truncated string"))? as usize;
+    if first & 0x80 == 0 {
+        return Ok((first, at + 1));
+    }
+    let second = *data
+        .get(at + 1)
+        .ok_or_else(|| invalid("truncated string"))? as usize;
+    Ok(((first & 0x7f) << 8 | second, at + 2))
+}
+
+/// 将元素树编码为二进制 XML
+pub fn encode(root: &Element) -> Vec<u8> {
+    // 带资源 ID 的属性名位于字符串池开头，与资源映射一一对应
+    let mut resource_names = Vec::new();
+    collect_resource_names(root, &mut resource_names);
+    let mut pool = StringPool {
+        strings: resource_names
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect(),
+        resource_names,
+    };
+
+    let mut nodes = Vec::new();
+    encode_element(root, &mut pool, &mut nodes, &mut 1);
+
+    let mut body = pool.encode();
+    if !pool.resource_names.is_empty() {
+        let ids = pool
+            .resource_names
+            .iter()
+            .flat_map(|(_, id)| id.to_le_bytes())
+            .collect::<Vec<_>>();
+        body.extend(chunk(RES_XML_RESOURCE_MAP_TYPE, &[], &ids));
+    }
+    body.extend(nodes);
+    chunk(RES_XML_TYPE, &[], &body)
+}
+
+fn collect_resource_names(element: &Element, names: &mut Vec<(String, u32)>) {
+    for attr in &element.attributes {
+        if let Some(id) = attr.resource_id {
+            let name = (attr.name.clone(), id);
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    for child in &element.children {
+        collect_resource_names(child, names);
+    }
+}
+
+fn encode_element(element: &Element, pool: &mut StringPool, out: &mut Vec<u8>, line: &mut u32) {
+    for (prefix, uri) in &element.namespaces {
+        let ext = [pool.add(prefix), pool.add(uri)];
+        out.extend(node(RES_XML_START_NAMESPACE_TYPE, *line, &u32s(&ext)));
+    }
+
+    // 与 aapt2 一致：带资源 ID 的属性按 ID 排序并位于前面
+    let mut attributes = element.attributes.iter().collect::<Vec<_>>();
+    attributes.sort_by_key(|attr| (attr.resource_id.is_none(), attr.resource_id, &attr.name));
+    let namespace = |pool: &mut StringPool, namespace: &Option<String>| {
+        namespace.as_ref().map_or(NO_INDEX, |ns| pool.add(ns))
+    };
+    let mut ext = u32s(&[namespace(pool, &element.namespace), pool.add(&element.name)]);
+    for value in [20, 20, attributes.len() as u16, 0, 0, 0] {
+        ext.extend(value.to_le_bytes());
+    }
+    for attr in attributes {
+        let name = match attr.resource_id {
+            Some(id) => pool.resource_name(&attr.name, id),
+            None => pool.add(&attr.name),
+        };
+        let (data_type, data) = attr.value.encode(pool);
+        let raw = if data_type == TYPE_STRING {
+            data
+        } else {
+            NO_INDEX
+        };
+        ext.extend(u32s(&[namespace(pool, &attr.namespace), name, raw]));
+        ext.extend(res_value(data_type, data));
+    }
+    out.extend(node(RES_XML_START_ELEMENT_TYPE, *line, &ext));
+    *line += 1;
+
+    if let Some(text) = &element.text {
+        let index = pool.add(text);
+        let mut ext = u32s(&[index]);
+        ext.extend(res_value(TYPE_STRING, index));
+        out.extend(node(RES_XML_CDATA_TYPE, *line, &ext));
+    }
+    for child in &element.children {
+        encode_element(child, pool, out, line);
+    }
+
+    let ext = u32s(&[namespace(pool, &element.namespace), pool.add(&element.name)]);
+    out.extend(node(RES_XML_END_ELEMENT_TYPE, *line, &ext));
+    for (prefix, uri) in element.namespaces.iter().rev() {
+        let ext = [pool.add(prefix), pool.add(uri)];
+        out.extend(node(RES_XML_END_NAMESPACE_TYPE, *line, &u32s(&ext)));
+    }
+}
+
+impl Value {
+    /// 编码为 `Res_value` 的类型与数据，字符串会被加入字符串池
+    fn encode(&self, pool: &mut StringPool) -> (u8, u32) {
+        match self {
+            Self::String(value) => (TYPE_STRING, pool.add(value)),
+            Self::Reference(id) => (TYPE_REFERENCE, *id),
+            Self::Attribute(id) => (TYPE_ATTRIBUTE, *id),
+            Self::Int(value) => (TYPE_INT_DEC, *value as u32),
+            Self::Hex(value) => (TYPE_INT_HEX, *value),
+            Self::Bool(value) => (TYPE_INT_BOOLEAN, if *value { 0xffffffff } else { 0 }),
+            Self::Float(value) => (TYPE_FLOAT, value.to_bits()),
+            Self::Color(value) => (TYPE_INT_COLOR_ARGB8, *value),
+            Self::Other { data_type, data } => (*data_type, *data),
+        }
+    }
+}
+
+/// UTF-8 字符串池，相同的字符串只存储一次
+#[derive(Default)]
+pub(crate) struct StringPool {
+    strings: Vec<String>,
+    /// 位于池开头、带资源 ID 的属性名
+    resource_names: Vec<(String, u32)>,
+}
+
+impl StringPool {
+    /// 返回字符串的索引，必要时将其加入池中
+    pub(crate) fn add(&mut self, string: &str) -> u32 {
+        // 资源名称的索引会被映射为资源 ID，普通字符串不能复用这些条目
+        let start = self.resource_names.len();
+        match self.strings[start..].iter().position(|s| s == string) {
+            Some(index) => (start + index) as u32,
+            None => {
+                self.strings.push(string.to_owned());
+                (self.strings.len() - 1) as u32
+            }
+        }
+    }
+
+    fn resource_name(&self, name: &str, id: u32) -> u32 {
+        self.resource_names
+            .iter()
+            .position(|(n, i)| n == name && *i == id)
+            .unwrap() as u32
+    }
+
+    /// 编码为完整的 `ResStringPool` 块
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut offsets = Vec::with_capacity(self.strings.len());
+        let mut data = Vec::new();
+        for string in &self.strings {
+            offsets.push(data.len() as u32);
+            push_utf8_length(&mut data, string.encode_utf16().count());
+            push_utf8_length(&mut data, string.len());
+            data.extend(string.as_bytes());
+            data.push(0);
+        }
+        while data.len() % 4 != 0 {
+            data.push(0);
+        }
+
+        let header_size = 28;
+        let strings_start = header_size + offsets.len() as u32 * 4;
+        let header = u32s(&[offsets.len() as u32, 0, UTF8_FLAG, strings_start, 0]);
+        let mut body = u32s(&offsets);
+        body.extend(data);
+        chunk(RES_STRING_POOL_TYPE, &header, &body)
+    }
+}
+
+fn push_utf8_length(out: &mut Vec<u8>, len: usize) {
+    if len > 0x7f {
+        out.push((len >> 8) as u8 | 0x80);
+    }
+    out.push(len as u8);
+}
+
+/// 组装一个块：`ResChunk_header`、其余头部字段与数据
+pub(crate) fn chunk(chunk_type: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+    let header_size = 8 + header.len();
+    let mut out = Vec::with_capacity(header_size + body.len());
+    out.extend(chunk_type.to_le_bytes());
+    out.extend((header_size as u16).to_le_bytes());
+    out.extend(((header_size + body.len()) as u32).to_le_bytes());
+    out.extend(header);
+    out.extend(body);
+    out
+}
+
+/// XML 节点：`ResXMLTree_node` 头（行号与注释）及扩展数据
+fn node(node_type: u16, line: u32, ext: &[u8]) -> Vec<u8> {
+    chunk(node_type, &u32s(&[line, NO_INDEX]), ext)
+}
+
+pub(crate) fn res_value(data_type: u8, data: u32) -> Vec<u8> {
+    let mut out = 8u16.to_le_bytes().to_vec();
+    out.extend([0, data_type]);
+    out.extend(data.to_le_bytes());
+    out
+}
+
+pub(crate) fn u32s(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Android 框架属性的命名空间
+pub const ANDROID_NAMESPACE: &str = "http://schemas.android.com/apk/res/android";
+
+impl Element {
+    /// 解析文本 XML，所有属性值均为字符串且不带资源 ID
+    pub fn parse(text: &str) -> Result<Self, NdkError> {
+        let mut reader = Reader::from_str(text);
+        // 每个打开的元素及其作用域内声明的命名空间
+        let mut stack: Vec<Element> = Vec::new();
+        let mut root = None;
+        // 已读取部分的行号与末尾位置
+        let (mut line, mut position) = (1, 0);
+        loop {
+            let offset = reader.buffer_position() as usize;
+            line += text.as_bytes()[position..offset]
+                .iter()
+                .filter(|byte| **byte == b'\n')
+                .count() as u32;
+            position = offset;
+            let (start, empty) = match reader.read_event()? {
+                Event::Start(start) => (start, false),
+                Event::Empty(start) => (start, true),
+                Event::End(_) => {
+                    let element = stack.pop().ok_or_else(|| invalid("unbalanced end tag"))?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => root = Some(element),
+                    }
+                    continue;
+                }
+                Event::Text(text) => {
+                    push_text(
+                        &mut stack,
+                        &text.xml10_content().map_err(quick_xml::Error::from)?,
+                    );
+                    continue;
+                }
+                Event::CData(text) => {
+                    push_text(&mut stack, &text.decode().map_err(quick_xml::Error::from)?);
+                    continue;
+                }
+                Event::GeneralRef(reference) => {
+                    let text = match reference.resolve_char_ref()? {
+                        Some(c) => c.to_string(),
+                        None => {
+                            let name = reference.decode().map_err(quick_xml::Error::from)?;
+                            resolve_predefined_entity(&name)
+                                .ok_or_else(|| invalid(&format!("unknown entity `{name}`")))?
+                                .to_owned()
+                        }
+                    };
+                    push_text(&mut stack, &text);
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+
+            let mut element = Element {
+                line,
+                ..Default::default()
+            };
+            let mut attributes = Vec::new();
+            for attr in start.attributes() {
+                let attr = attr.map_err(quick_xml::Error::from)?;
+                let key = std::str::from_utf8(attr.key.as_ref())?.to_owned();
+                let value = attr.normalized_value(XmlVersion::Implicit1_0)?.into_owned();
+                match key.strip_prefix("xmlns:") {
+                    Some(prefix) => element.namespaces.push((prefix.to_owned(), value)),
+                    None => attributes.push((key, value)),
+                }
+            }
+
+            let scope = stack
+                .iter()
+                .flat_map(|parent| &parent.namespaces)
+                .chain(&element.namespaces)
+                .cloned()
+                .collect::<Vec<_>>();
+            let resolve = |name: &str| match name.split_once(':') {
+                Some((prefix, local)) => scope
+                    .iter()
+                    .rev()
+                    .find(|(declared, _)| declared == prefix)
+                    .map(|(_, uri)| (Some(uri.clone()), local.to_owned()))
+                    .ok_or_else(|| invalid(&format!("undeclared namespace prefix `{prefix}`"))),
+                None => Ok((None, name.to_owned())),
+            };
+
+            (element.namespace, element.name) =
+                resolve(std::str::from_utf8(start.name().as_ref())?)?;
+            for (key, value) in attributes {
+                let (namespace, name) = resolve(&key)?;
+                element.attributes.push(Attribute {
+                    namespace,
+                    name,
+                    resource_id: None,
+                    value: Value::String(value),
+                });
+            }
+
+            if empty {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            } else {
+                stack.push(element);
+            }
+        }
+
+        root.ok_or_else(|| invalid("no root element"))
+    }
+
+    /// 像 aapt2 一样为 `android:` 属性设置资源 ID，并按属性格式将字符串转换为类型化的值。
+    ///
+    /// `resolve` 用于解析 `@type/name` 形式的资源引用。遇到无法处理的属性或引用时返回其名称，
+    /// 此时应改用 aapt2 编译清单。
+    pub fn compile_android_attributes(
+        &mut self,
+        resolve: &dyn Fn(&str) -> Option<u32>,
+    ) -> Result<(), String> {
+        for attr in &mut self.attributes {
+            match attr.namespace.as_deref() {
+                None => continue,
+                Some(ANDROID_NAMESPACE) => {}
+                Some(namespace) => return Err(format!("{{{namespace}}}{}", attr.name)),
+            }
+            let unsupported = || format!("android:{}", attr.name);
+            let Some((_, id, format)) = ANDROID_ATTRIBUTES
+                .iter()
+                .find(|(name, _, _)| *name == attr.name)
+            else {
+                return Err(unsupported());
+            };
+            let Value::String(text) = &attr.value else {
+                continue;
+            };
+            let text = text.trim();
+            let reference = || {
+                text.strip_prefix('@')
+                    .and_then(resolve)
+                    .map(Value::Reference)
+            };
+            let value = match format {
+                Format::String => Some(Value::String(text.to_owned())),
+                Format::Int => text.parse().ok().map(Value::Int),
+                Format::Hex => parse_hex(text).map(Value::Hex),
+                Format::Bool => parse_bool(text).map(Value::Bool),
+                Format::Reference => reference(),
+                Format::ReferenceOrString if text.starts_with('@') => reference(),
+                Format::ReferenceOrString => Some(Value::String(text.to_owned())),
+                Format::Enum(values) => values
+                    .iter()
+                    .find(|(name, _)| *name == text)
+                    .map(|(_, value)| Value::Int(*value)),
+                Format::Flags(flags) => text
+                    .split('|')
+                    .try_fold(0u32, |acc, name| {
+                        flags
+                            .iter()
+                            .find(|(flag, _)| *flag == name.trim())
+                            .map(|(_, value)| acc | *value as u32)
+                    })
+                    .map(Value::Hex),
+                Format::Any if text.starts_with('@') || text.starts_with('?') => None,
+                Format::Any => Some(infer_value(text)),
+            };
+            attr.resource_id = Some(*id);
+            attr.value = value.ok_or_else(unsupported)?;
+        }
+        for child in &mut self.children {
+            child.compile_android_attributes(resolve)?;
+        }
+        Ok(())
+    }
+}
+
+fn push_text(stack: &mut [Element], text: &str) {
+    if let Some(element) = stack.last_mut() {
+        let current = element.text.get_or_insert_with(String::new);
+        current.push_str(text);
+        if current.trim().is_empty() {
+            element.text = None;
+        }
+    }
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))?;
+    u32::from_str_radix(digits, 16).ok()
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// 与 aapt2 对 `meta-data` 的 `android:value` 的处理一致：依次尝试颜色、布尔、整数与浮点数，
+/// 都不匹配时作为字符串
+fn infer_value(text: &str) -> Value {
+    if let Some(color) = text.strip_prefix('#')
+        && let Ok(value) = u32::from_str_radix(color, 16)
+    {
+        // #RGB、#ARGB、#RRGGBB 与 #AARRGGBB
+        let expand = |v: u32, digits: u32| {
+            (0..digits).rev().fold(0, |acc, i| {
+                let nibble = v >> (i * 4) & 0xf;
+                acc << 8 | nibble << 4 | nibble
+            })
+        };
+        let argb = match color.len() {
+            3 => Some(0xff000000 | expand(value, 3)),
+            4 => Some(expand(value, 4)),
+            6 => Some(0xff000000 | value),
+            8 => Some(value),
+            _ => None,
+        };
+        if let Some(argb) = argb {
+            return Value::Color(argb);
+        }
+    }
+    if let Some(value) = parse_bool(text) {
+        return Value::Bool(value);
+    }
+    if let Ok(value) = text.parse() {
+        return Value::Int(value);
+    }
+    if let Some(value) = parse_hex(text) {
+        return Value::Hex(value);
+    }
+    if text.chars().any(|c| c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+        && let Ok(value) = text.parse()
+    {
+        return Value::Float(value);
+    }
+    Value::String(text.to_owned())
+}
+
+/// 框架属性的格式，决定字符串值如何编码
+enum Format {
+    String,
+    Int,
+    Hex,
+    Bool,
+    Reference,
+    ReferenceOrString,
+    Enum(&'static [(&'static str, i32)]),
+    Flags(&'static [(&'static str, i32)]),
+    /// `meta-data` 的值，类型由内容推断
+    Any,
+}
+
+const LAUNCH_MODES: &[(&str, i32)] = &[
+    ("standard", 0),
+    ("singleTop", 1),
+    ("singleTask", 2),
+    ("singleInstance", 3),
+    ("singleInstancePerTask", 4),
+];
+
+//noinspection SpellCheckingInspection
+const SCREEN_ORIENTATIONS: &[(&str, i32)] = &[
+    ("unspecified", -1),
+    ("landscape", 0),
+    ("portrait", 1),
+    ("user", 2),
+    ("behind", 3),
+    ("sensor", 4),
+    ("nosensor", 5),
+    ("sensorLandscape", 6),
+    ("sensorPortrait", 7),
+    ("reverseLandscape", 8),
+    ("reversePortrait", 9),
+    ("fullSensor", 10),
+    ("userLandscape", 11),
+    ("userPortrait", 12),
+    ("fullUser", 13),
+    ("locked", 14),
+];
+
+//noinspection SpellCheckingInspection
+const CONFIG_CHANGES: &[(&str, i32)] = &[
+    ("mcc", 0x0001),
+    ("mnc", 0x0002),
+    ("locale", 0x0004),
+    ("touchscreen", 0x0008),
+    ("keyboard", 0x0010),
+    ("keyboardHidden", 0x0020),
+    ("navigation", 0x0040),
+    ("orientation", 0x0080),
+    ("screenLayout", 0x0100),
+    ("uiMode", 0x0200),
+    ("screenSize", 0x0400),
+    ("smallestScreenSize", 0x0800),
+    ("density", 0x1000),
+    ("layoutDirection", 0x2000),
+    ("colorMode", 0x4000),
+    ("grammaticalGender", 0x8000),
+    ("fontWeightAdjustment", 0x10000000),
+    ("fontScale", 0x40000000),
+];
+
+const WINDOW_SOFT_INPUT_MODES: &[(&str, i32)] = &[
+    ("stateUnspecified", 0x00),
+    ("stateUnchanged", 0x01),
+    ("stateHidden", 0x02),
+    ("stateAlwaysHidden", 0x03),
+    ("stateVisible", 0x04),
+    ("stateAlwaysVisible", 0x05),
+    ("adjustUnspecified", 0x00),
+    ("adjustResize", 0x10),
+    ("adjustPan", 0x20),
+    ("adjustNothing", 0x30),
+];
+
+const FOREGROUND_SERVICE_TYPES: &[(&str, i32)] = &[
+    ("dataSync", 0x01),
+    ("mediaPlayback", 0x02),
+    ("phoneCall", 0x04),
+    ("location", 0x08),
+    ("connectedDevice", 0x10),
+    ("mediaProjection", 0x20),
+    ("camera", 0x40),
+    ("microphone", 0x80),
+    ("health", 0x100),
+    ("remoteMessaging", 0x200),
+    ("systemExempted", 0x400),
+    ("shortService", 0x800),
+    ("mediaProcessing", 0x2000),
+    ("specialUse", 0x40000000),
+];
+
+/// [`crate::manifest::AndroidManifest`] 可能用到的框架属性（`android.R.attr`）
+//noinspection SpellCheckingInspection
+const ANDROID_ATTRIBUTES: &[(&str, u32, Format)] = &[
+    ("theme", 0x01010000, Format::Reference),
+    ("label", 0x01010001, Format::ReferenceOrString),
+    ("icon", 0x01010002, Format::Reference),
+    ("name", 0x01010003, Format::String),
+    ("permission", 0x01010006, Format::String),
+    ("sharedUserId", 0x0101000b, Format::String),
+    ("hasCode", 0x0101000c, Format::Bool),
+    ("enabled", 0x0101000e, Format::Bool),
+    ("debuggable", 0x0101000f, Format::Bool),
+    ("exported", 0x01010010, Format::Bool),
+    ("process", 0x01010011, Format::String),
+    ("authorities", 0x01010018, Format::String),
+    ("launchMode", 0x0101001d, Format::Enum(LAUNCH_MODES)),
+    (
+        "screenOrientation",
+        0x0101001e,
+        Format::Enum(SCREEN_ORIENTATIONS),
+    ),
+    ("configChanges", 0x0101001f, Format::Flags(CONFIG_CHANGES)),
+    ("value", 0x01010024, Format::Any),
+    ("resource", 0x01010025, Format::Reference),
+    ("mimeType", 0x01010026, Format::String),
+    ("scheme", 0x01010027, Format::String),
+    ("host", 0x01010028, Format::String),
+    ("port", 0x01010029, Format::String),
+    ("path", 0x0101002a, Format::String),
+    ("pathPrefix", 0x0101002b, Format::String),
+    ("pathPattern", 0x0101002c, Format::String),
+    ("alwaysRetainTaskState", 0x01010203, Format::Bool),
+    ("minSdkVersion", 0x0101020c, Format::Int),
+    ("versionCode", 0x0101021b, Format::Int),
+    ("versionName", 0x0101021c, Format::String),
+    (
+        "windowSoftInputMode",
+        0x0101022b,
+        Format::Flags(WINDOW_SOFT_INPUT_MODES),
+    ),
+    ("targetSdkVersion", 0x01010270, Format::Int),
+    ("maxSdkVersion", 0x01010271, Format::Int),
+    ("glEsVersion", 0x01010281, Format::Hex),
+    ("required", 0x0101028e, Format::Bool),
+    ("extractNativeLibs", 0x010104ea, Format::Bool),
+    ("usesCleartextTraffic", 0x010104ec, Format::Bool),
+    ("resizeableActivity", 0x010104f6, Format::Bool),
+    (
+        "foregroundServiceType",
+        0x01010599,
+        Format::Flags(FOREGROUND_SERVICE_TYPES),
+    ),
+    ("hasFragileUserData", 0x0101059a, Format::Bool),
+    ("requestLegacyExternalStorage", 0x01010603, Format::Bool),
+];
+
+/// 返回块的类型、头部大小与总大小
+pub(crate) fn chunk_header(data: &[u8], offset: usize) -> Result<(u16, usize, usize), NdkError> {
+    Ok((
+        read_u16(data, offset)?,
+        read_u16(data, offset + 2)? as usize,
+        read_u32(data, offset + 4)? as usize,
+    ))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, NdkError> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| invalid("unexpected end of data"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, NdkError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| invalid("unexpected end of data"))
+}
+
+fn invalid(msg: &str) -> NdkError {
+    NdkError::InvalidBinaryXml(msg.into())
+}
+
+fn qualified_name(prefixes: &[(String, String)], namespace: &Option<String>, name: &str) -> String {
+    let prefix = namespace.as_ref().and_then(|uri| {
+        prefixes
+            .iter()
+            .rev()
+            .find(|(_, declared)| declared == uri)
+            .map(|(prefix, _)| prefix)
+    });
+    match prefix {
+        Some(prefix) => format!("{prefix}:{name}"),
+        None => name.to_owned(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_round_trip() {
+        let text = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android" package="com.example.app" android:versionCode="3" android:versionName="1.0">
+            <uses-sdk android:minSdkVersion="23" android:targetSdkVersion="35"/>
+            <application android:hasCode="false" android:label="@string/app_name">
+                <activity android:name="android.app.NativeActivity" android:configChanges="orientation|keyboardHidden" android:launchMode="singleTop"/>
+            </application>
+        </manifest>"#;
+        let mut manifest = Element::parse(text).unwrap();
+        manifest
+            .compile_android_attributes(&|name| (name == "string/app_name").then_some(0x7f010000))
+            .unwrap();
+        let decoded = decode(&encode(&manifest)).unwrap();
+
+        assert_eq!(
+            decoded.attribute("package").unwrap().value.as_str(),
+            Some("com.example.app")
+        );
+        assert_eq!(
+            decoded.attribute("versionCode").unwrap().value.as_int(),
+            Some(3)
+        );
+        let application = decoded.children_named("application").next().unwrap();
+        assert_eq!(
+            application.attribute("label").unwrap().value,
+            Value::Reference(0x7f010000)
+        );
+        assert_eq!(
+            application.attribute("hasCode").unwrap().value,
+            Value::Bool(false)
+        );
+        let activity = application.children_named("activity").next().unwrap();
+        assert_eq!(
+            activity.attribute("configChanges").unwrap().value,
+            Value::Hex(0xa0)
+        );
+        assert_eq!(
+            activity.attribute("launchMode").unwrap().value,
+            Value::Int(1)
+        );
+    }
+}
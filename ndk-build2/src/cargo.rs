@@ -21,6 +21,7 @@ pub fn cargo_ndk(
     cargo.stdin(Stdio::null());
 
     const SEP: &str = "\x1f";
+    const MAX_PAGE_SIZE_FLAG: &str = "-Wl,-z,max-page-size=16384";
 
     // Read initial CARGO_ENCODED_/RUSTFLAGS
     let mut rustflags = match var("CARGO_ENCODED_RUSTFLAGS") {
@@ -75,6 +76,12 @@ pub fn cargo_ndk(
     rustflags.push_str("-Clink-arg=");
     rustflags.push_str(&clang_target);
 
+    // 支持 16 KB 内存页的设备要求 LOAD 段按 16 KB 对齐，NDK r28 之前的链接器默认只按 4 KB 对齐：
+    // https://developer.android.com/guide/practices/page-sizes
+    rustflags.push_str(SEP);
+    rustflags.push_str("-Clink-arg=");
+    rustflags.push_str(MAX_PAGE_SIZE_FLAG);
+
     let ar = ndk.toolchain_bin("ar", target)?;
     cargo.env(format!("AR_{}", triple), &ar);
     cargo.env(cargo_env_target_cfg("AR", triple), &ar);
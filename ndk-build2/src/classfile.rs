@@ -0,0 +1,326 @@
+//! 读取 `.class` 文件的类名、父类与接口以及其中声明的 `native` 方法，并按 JNI 规范生成虚拟机查找的导出函数名。
+
+use {crate::error::NdkError, std::path::Path};
+
+const MAGIC: u32 = 0xcafe_babe;
+const ACC_STATIC: u16 = 0x0008;
+const ACC_NATIVE: u16 = 0x0100;
+const ACC_INTERFACE: u16 = 0x0200;
+
+/// `.class` 文件中声明的 `native` 方法
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NativeMethod {
+    /// 类的内部名称，例如 `com/example/app/MainActivity`
+    pub class: String,
+    pub name: String,
+    /// 方法描述符，例如 `(I[Ljava/lang/String;)V`
+    pub descriptor: String,
+    pub is_static: bool,
+    /// 参数名称，仅当类以 `javac -parameters` 编译时存在，未记录名称的参数为空字符串
+    pub parameters: Vec<String>,
+}
+
+impl NativeMethod {
+    /// 虚拟机首先查找的短名称：`Java_<类>_<方法>`
+    pub fn short_name(&self) -> String {
+        format!("Java_{}_{}", mangle(&self.class), mangle(&self.name))
+    }
+
+    /// 重载的方法使用的长名称：`<短名称>__<参数描述符>`
+    pub fn long_name(&self) -> String {
+        format!(
+            "{}__{}",
+            self.short_name(),
+            mangle(self.split_descriptor().0)
+        )
+    }
+
+    /// 各参数的类型描述符，例如 `["I", "[Ljava/lang/String;"]`
+    pub fn parameter_types(&self) -> Vec<&str> {
+        let mut arguments = self.split_descriptor().0;
+        let mut types = Vec::new();
+        while !arguments.is_empty() {
+            let dimensions = arguments.len() - arguments.trim_start_matches('[').len();
+            let len = match arguments[dimensions..].find(';') {
+                Some(end) if arguments[dimensions..].starts_with('L') => dimensions + end + 1,
+                _ => dimensions + 1,
+            };
+            let len = len.min(arguments.len());
+            types.push(&arguments[..len]);
+            arguments = &arguments[len..];
+        }
+        types
+    }
+
+    /// 返回值的类型描述符，没有返回值时为 `V`
+    pub fn return_type(&self) -> &str {
+        self.split_descriptor().1
+    }
+
+    fn split_descriptor(&self) -> (&str, &str) {
+        self.descriptor
+            .strip_prefix('(')
+            .and_then(|descriptor| descriptor.split_once(')'))
+            .unwrap_or(("", "V"))
+    }
+}
+
+impl std::fmt::Display for NativeMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}{}",
+            self.class.replace('/', "."),
+            self.name,
+            self.descriptor
+        )
+    }
+}
+
+/// 已解析的 `.class` 文件中与 JNI 及类继承关系有关的部分
+#[derive(Clone, Debug)]
+pub struct ClassFile {
+    /// 类的内部名称，例如 `com/example/app/MainActivity`
+    pub name: String,
+    pub is_interface: bool,
+    /// 父类的内部名称，只有 `java/lang/Object` 与 `module-info` 没有父类
+    pub super_class: Option<String>,
+    pub interfaces: Vec<String>,
+    pub native_methods: Vec<NativeMethod>,
+}
+
+impl ClassFile {
+    /// `path` 仅用于错误信息
+    pub fn parse(path: &Path, data: &[u8]) -> Result<Self, NdkError> {
+        parse(data).map_err(|e| NdkError::InvalidClass(path.into(), e))
+    }
+}
+
+/// 常量池中与方法名称有关的条目
+enum Constant {
+    Utf8(String),
+    Class(u16),
+    Other,
+}
+
+fn parse(data: &[u8]) -> Result<ClassFile, String> {
+    let mut reader = Reader { data, offset: 0 };
+    if reader.u32()? != MAGIC {
+        return Err("bad magic".into());
+    }
+    // minor_version 与 major_version
+    reader.skip(4)?;
+
+    let count = reader.u16()?;
+    // 常量池的索引从 1 开始
+    let mut constants = vec![Constant::Other];
+    while constants.len() < count as usize {
+        let tag = reader.u8()?;
+        let constant = match tag {
+            1 => {
+                let len = reader.u16()?;
+                Constant::Utf8(decode_modified_utf8(reader.bytes(len as usize)?)?)
+            }
+            7 => Constant::Class(reader.u16()?),
+            // Long 与 Double 占用两个索引
+            5 | 6 => {
+                reader.skip(8)?;
+                constants.push(Constant::Other);
+                Constant::Other
+            }
+            3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => {
+                reader.skip(4)?;
+                Constant::Other
+            }
+            15 => {
+                reader.skip(3)?;
+                Constant::Other
+            }
+            8 | 16 | 19 | 20 => {
+                reader.skip(2)?;
+                Constant::Other
+            }
+            _ => return Err(format!("unknown constant pool tag {tag}")),
+        };
+        constants.push(constant);
+    }
+    let utf8 = |index: u16| match constants.get(index as usize) {
+        Some(Constant::Utf8(value)) => Ok(value.as_str()),
+        _ => Err(format!("constant {index} is not a UTF-8 string")),
+    };
+
+    let class_name = |index: u16| match constants.get(index as usize) {
+        Some(Constant::Class(name)) => Ok(utf8(*name)?.to_owned()),
+        _ => Err(format!("constant {index} is not a class")),
+    };
+
+    let access_flags = reader.u16()?;
+    let class = class_name(reader.u16()?)?;
+    let super_class = match reader.u16()? {
+        0 => None,
+        index => Some(class_name(index)?),
+    };
+    let interfaces = (0..reader.u16()?)
+        .map(|_| class_name(reader.u16()?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut methods = Vec::new();
+    for is_method in [false, true] {
+        for _ in 0..reader.u16()? {
+            let (flags, name, descriptor) = (reader.u16()?, reader.u16()?, reader.u16()?);
+            let mut parameters = Vec::new();
+            for _ in 0..reader.u16()? {
+                let attribute = reader.u16()?;
+                let len = reader.u32()?;
+                let data = reader.bytes(len as usize)?;
+                if is_method && utf8(attribute) == Ok("MethodParameters") {
+                    let mut reader = Reader { data, offset: 0 };
+                    for _ in 0..reader.u8()? {
+                        // name_index 为 0 表示没有名称，其后为 access_flags
+                        let name = reader.u16()?;
+                        reader.skip(2)?;
+                        parameters.push(match name {
+                            0 => String::new(),
+                            name => utf8(name)?.to_owned(),
+                        });
+                    }
+                }
+            }
+            if is_method && flags & ACC_NATIVE != 0 {
+                methods.push(NativeMethod {
+                    class: class.clone(),
+                    name: utf8(name)?.to_owned(),
+                    descriptor: utf8(descriptor)?.to_owned(),
+                    is_static: flags & ACC_STATIC != 0,
+                    parameters,
+                });
+            }
+        }
+    }
+    Ok(ClassFile {
+        name: class,
+        is_interface: access_flags & ACC_INTERFACE != 0,
+        super_class,
+        interfaces,
+        native_methods: methods,
+    })
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| format!("truncated at offset {}", self.offset))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+}
+
+/// 解码 Java 的修改版 UTF-8：`U+0000` 编码为两个字节，补充平面的字符编码为两个代理项
+fn decode_modified_utf8(bytes: &[u8]) -> Result<String, String> {
+    let invalid = || "invalid modified UTF-8 string".to_owned();
+    let continuation = |index: usize| match bytes.get(index) {
+        Some(b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+        _ => Err(invalid()),
+    };
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let b = bytes[index] as u16;
+        let (unit, len) = match b {
+            0x01..=0x7f => (b, 1),
+            0xc0..=0xdf => ((b & 0x1f) << 6 | continuation(index + 1)?, 2),
+            0xe0..=0xef => (
+                (b & 0x0f) << 12 | continuation(index + 1)? << 6 | continuation(index + 2)?,
+                3,
+            ),
+            _ => return Err(invalid()),
+        };
+        units.push(unit);
+        index += len;
+    }
+    String::from_utf16(&units).map_err(|_| invalid())
+}
+
+/// 按 JNI 规范转义名称：`/` 变为 `_`，`_`、`;` 与 `[` 变为 `_1`、`_2` 与 `_3`，
+/// 其他非 ASCII 字母数字的字符变为 `_0xxxx`（UTF-16 代码单元）
+fn mangle(name: &str) -> String {
+    let mut mangled = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' => mangled.push('_'),
+            '_' => mangled.push_str("_1"),
+            ';' => mangled.push_str("_2"),
+            '[' => mangled.push_str("_3"),
+            c if c.is_ascii_alphanumeric() => mangled.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    mangled.push_str(&format!("_0{unit:04x}"));
+                }
+            }
+        }
+    }
+    mangled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NativeMethod, decode_modified_utf8};
+
+    #[test]
+    fn jni_names() {
+        let method = NativeMethod {
+            class: "com/example/my_app/Main$Inner".into(),
+            name: "get".into(),
+            descriptor: "(I[Ljava/lang/String;)V".into(),
+            is_static: false,
+            parameters: Vec::new(),
+        };
+        assert_eq!(
+            method.short_name(),
+            "Java_com_example_my_1app_Main_00024Inner_get"
+        );
+        assert_eq!(
+            method.long_name(),
+            "Java_com_example_my_1app_Main_00024Inner_get__I_3Ljava_lang_String_2"
+        );
+        assert_eq!(
+            method.to_string(),
+            "com.example.my_app.Main$Inner.get(I[Ljava/lang/String;)V"
+        );
+        assert_eq!(method.parameter_types(), ["I", "[Ljava/lang/String;"]);
+        assert_eq!(method.return_type(), "V");
+    }
+
+    #[test]
+    fn modified_utf8() {
+        assert_eq!(decode_modified_utf8(b"a\xc0\x80").unwrap(), "a\0");
+        assert_eq!(
+            decode_modified_utf8(b"\xed\xa0\xbd\xed\xb8\x80").unwrap(),
+            "\u{1f600}"
+        );
+        assert!(decode_modified_utf8(b"\x00").is_err());
+    }
+}
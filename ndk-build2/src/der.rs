@@ -0,0 +1,223 @@
+//! 读写 keystore、X.509 证书与 PKCS#7 签名所需的最小 DER 编解码实现。
+
+use crate::error::NdkError;
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+
+pub(crate) const OID_DATA: &str = "1.2.840.113549.1.7.1";
+pub(crate) const OID_SIGNED_DATA: &str = "1.2.840.113549.1.7.2";
+pub(crate) const OID_ENCRYPTED_DATA: &str = "1.2.840.113549.1.7.6";
+pub(crate) const OID_RSA_ENCRYPTION: &str = "1.2.840.113549.1.1.1";
+pub(crate) const OID_SHA1: &str = "1.3.14.3.2.26";
+pub(crate) const OID_SHA256: &str = "2.16.840.1.101.3.4.2.1";
+
+/// 一个已解析的 TLV 元素，`content` 与 `raw` 都借用自原始输入
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Der<'a> {
+    pub(crate) tag: u8,
+    pub(crate) content: &'a [u8],
+    pub(crate) raw: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    /// 解析 `input` 开头的一个元素，返回该元素及剩余的字节
+    pub(crate) fn parse(input: &'a [u8]) -> Result<(Self, &'a [u8]), NdkError> {
+        let invalid = || NdkError::InvalidDer("truncated element".into());
+        let tag = *input.first().ok_or_else(invalid)?;
+        let first = *input.get(1).ok_or_else(invalid)?;
+        let (len, header) = if first < 0x80 {
+            (first as usize, 2)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 {
+                return Err(NdkError::InvalidDer(format!(
+                    "unsupported length encoding 0x{first:02x}"
+                )));
+            }
+            let bytes = input.get(2..2 + count).ok_or_else(invalid)?;
+            let len = bytes.iter().fold(0usize, |acc, b| acc << 8 | *b as usize);
+            (len, 2 + count)
+        };
+        let end = header.checked_add(len).ok_or_else(invalid)?;
+        let raw = input.get(..end).ok_or_else(invalid)?;
+        Ok((
+            Self {
+                tag,
+                content: &raw[header..],
+                raw,
+            },
+            &input[end..],
+        ))
+    }
+
+    /// 解析一个完整的元素，并要求其标签为 `tag`
+    pub(crate) fn parse_tagged(input: &'a [u8], tag: u8) -> Result<Self, NdkError> {
+        let (der, _) = Self::parse(input)?;
+        der.expect(tag)
+    }
+
+    pub(crate) fn expect(self, tag: u8) -> Result<Self, NdkError> {
+        if self.tag != tag {
+            return Err(NdkError::InvalidDer(format!(
+                "expected tag 0x{tag:02x}, found 0x{:02x}",
+                self.tag
+            )));
+        }
+        Ok(self)
+    }
+
+    /// 构造类型（SEQUENCE、SET、显式标签等）的子元素
+    pub(crate) fn children(&self) -> Result<Vec<Der<'a>>, NdkError> {
+        let mut children = Vec::new();
+        let mut rest = self.content;
+        while !rest.is_empty() {
+            let (child, next) = Self::parse(rest)?;
+            children.push(child);
+            rest = next;
+        }
+        Ok(children)
+    }
+
+    pub(crate) fn oid(&self) -> Result<String, NdkError> {
+        let content = self.expect(TAG_OID)?.content;
+        let (first, rest) = content
+            .split_first()
+            .ok_or_else(|| NdkError::InvalidDer("empty object identifier".into()))?;
+        let mut arcs = vec![(*first / 40) as u64, (*first % 40) as u64];
+        let mut value = 0u64;
+        for byte in rest {
+            value = value << 7 | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                arcs.push(value);
+                value = 0;
+            }
+        }
+        Ok(arcs
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join("."))
+    }
+
+    /// 将非负 INTEGER 解码为 `u64`
+    pub(crate) fn u64(&self) -> Result<u64, NdkError> {
+        let content = self.expect(TAG_INTEGER)?.content;
+        if content.len() > 9 || (content.len() == 9 && content[0] != 0) {
+            return Err(NdkError::InvalidDer("integer out of range".into()));
+        }
+        Ok(content.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+    }
+}
+
+/// 对 `(tag, content)` 进行 DER 编码
+pub(crate) fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = (len as u32).to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        out.push(0x80 | (4 - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+pub(crate) fn sequence(items: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+pub(crate) fn set(items: &[&[u8]]) -> Vec<u8> {
+    tlv(TAG_SET, &items.concat())
+}
+
+pub(crate) fn octet_string(content: &[u8]) -> Vec<u8> {
+    tlv(TAG_OCTET_STRING, content)
+}
+
+pub(crate) fn integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    let mut content = bytes[skip..].to_vec();
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    tlv(TAG_INTEGER, &content)
+}
+
+pub(crate) fn oid(dotted: &str) -> Vec<u8> {
+    let arcs = dotted
+        .split('.')
+        .map(|arc| arc.parse::<u64>().expect("invalid object identifier"))
+        .collect::<Vec<_>>();
+    let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+    for arc in &arcs[2..] {
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        chunk.reverse();
+        content.extend(chunk);
+    }
+    tlv(TAG_OID, &content)
+}
+
+/// `AlgorithmIdentifier`，参数为 NULL
+pub(crate) fn algorithm(dotted: &str) -> Vec<u8> {
+    sequence(&[&oid(dotted), &tlv(TAG_NULL, &[])])
+}
+
+/// 上下文相关的构造标签，例如 `[0] EXPLICIT`
+pub(crate) fn context(number: u8, content: &[u8]) -> Vec<u8> {
+    tlv(0xa0 | number, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let long = vec![0x5a; 300];
+        let encoded = sequence(&[&integer(0x80), &oid(OID_SHA256), &octet_string(&long)]);
+        assert_eq!(&encoded[..4], [TAG_SEQUENCE, 0x82, 0x01, 0x3f]);
+
+        let (der, rest) = Der::parse(&encoded).unwrap();
+        assert!(rest.is_empty());
+        let children = der.children().unwrap();
+        assert_eq!(children[0].content, [0x00, 0x80]);
+        assert_eq!(children[0].u64().unwrap(), 0x80);
+        assert_eq!(children[1].oid().unwrap(), OID_SHA256);
+        assert_eq!(children[2].expect(TAG_OCTET_STRING).unwrap().content, long);
+        assert!(children[2].expect(TAG_SET).is_err());
+        assert_eq!(integer(0).as_slice(), [TAG_INTEGER, 1, 0]);
+    }
+
+    #[test]
+    fn reject_malformed() {
+        assert!(Der::parse(&[]).is_err());
+        assert!(Der::parse(&[TAG_SEQUENCE, 0x03, 0x05, 0x00]).is_err());
+        assert!(Der::parse(&[TAG_SEQUENCE, 0x85, 0, 0, 0, 0, 1]).is_err());
+        assert!(
+            Der::parse_tagged(&[TAG_OID, 0], TAG_OID)
+                .unwrap()
+                .oid()
+                .is_err()
+        );
+        assert!(
+            Der::parse_tagged(&tlv(TAG_INTEGER, &[1; 10]), TAG_INTEGER)
+                .unwrap()
+                .u64()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Java 8+ API 脱糖（core library desugaring）：查找 `desugar_jdk_libs` 运行时 jar 与其配置，
+//! 使 `java.time`、流等 API 可以在 `min_sdk_version` 较低的设备上使用。
+
+use {
+    crate::{error::NdkError, maven::compare_versions, zip::ZipArchive},
+    std::{
+        env::var_os,
+        fs::{read, read_dir, write},
+        path::{Path, PathBuf},
+    },
+};
+
+const GROUP: &str = "com.android.tools";
+const RUNTIME: &str = "desugar_jdk_libs";
+const CONFIGURATION: &str = "desugar_jdk_libs_configuration";
+/// 配置 jar 中 D8 与 L8 使用的脱糖配置
+const CONFIGURATION_ENTRY: &str = "META-INF/desugar/d8/desugar.json";
+
+/// 脱糖使用的 jar，为 `None` 的路径在本地 Maven 仓库（`~/.m2/repository`）与 Gradle 缓存中查找
+#[derive(Clone, Debug, Default)]
+pub struct CoreLibraryDesugaring {
+    /// `com.android.tools:desugar_jdk_libs` 运行时 jar
+    pub runtime_jar: Option<PathBuf>,
+    /// `com.android.tools:desugar_jdk_libs_configuration` jar，或从中取出的 `desugar.json`
+    pub configuration: Option<PathBuf>,
+}
+
+impl CoreLibraryDesugaring {
+    /// 返回运行时 jar 与配置的路径。两者都需要查找时使用两者都存在的最高版本
+    pub fn resolve(&self) -> Result<(PathBuf, PathBuf), NdkError> {
+        let not_found =
+            |artifact: &str| NdkError::MavenArtifactNotFound(format!("{GROUP}:{artifact}"));
+        match (&self.runtime_jar, &self.configuration) {
+            (Some(runtime_jar), Some(configuration)) => {
+                Ok((runtime_jar.clone(), configuration.clone()))
+            }
+            (Some(runtime_jar), None) => Ok((
+                runtime_jar.clone(),
+                cached_versions(CONFIGURATION)
+                    .pop()
+                    .ok_or_else(|| not_found(CONFIGURATION))?
+                    .1,
+            )),
+            (None, Some(configuration)) => Ok((
+                cached_versions(RUNTIME)
+                    .pop()
+                    .ok_or_else(|| not_found(RUNTIME))?
+                    .1,
+                configuration.clone(),
+            )),
+            (None, None) => {
+                let configurations = cached_versions(CONFIGURATION);
+                cached_versions(RUNTIME)
+                    .into_iter()
+                    .rev()
+                    .find_map(|(version, runtime_jar)| {
+                        let (_, configuration) =
+                            configurations.iter().find(|(other, _)| *other == version)?;
+                        Some((runtime_jar, configuration.clone()))
+                    })
+                    .ok_or_else(|| not_found(RUNTIME))
+            }
+        }
+    }
+}
+
+/// 将脱糖配置写入 `path`：`configuration` 为 jar 时取出其中的 `desugar.json`，否则直接复制
+pub fn write_configuration(configuration: &Path, path: &Path) -> Result<(), NdkError> {
+    let json = if configuration.extension().is_some_and(|ext| ext == "json") {
+        read(configuration).map_err(|e| NdkError::IoPathError(configuration.into(), e))?
+    } else {
+        let archive = ZipArchive::open(configuration)?;
+        let entry = archive.entry(CONFIGURATION_ENTRY).ok_or_else(|| {
+            NdkError::InvalidZip(format!(
+                "`{}` has no `{CONFIGURATION_ENTRY}`",
+                configuration.display()
+            ))
+        })?;
+        archive.read(entry)?
+    };
+    write(path, json).map_err(|e| NdkError::IoPathError(path.into(), e))
+}
+
+/// 本地 Maven 仓库与 Gradle 缓存中 `com.android.tools:<artifact>` 的各个版本及其 jar，按版本升序排列
+fn cached_versions(artifact: &str) -> Vec<(String, PathBuf)> {
+    let mut versions = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        let dir = home
+            .join(".m2")
+            .join("repository")
+            .join(GROUP.replace('.', "/"))
+            .join(artifact);
+        for version in subdirectories(&dir) {
+            let jar = dir.join(&version).join(format!("{artifact}-{version}.jar"));
+            if jar.is_file() {
+                versions.push((version, jar));
+            }
+        }
+    }
+
+    // 布局为 `files-2.1/<group>/<artifact>/<version>/<sha1>/<artifact>-<version>.jar`
+    let gradle_home = var_os("GRADLE_USER_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".gradle")));
+    if let Some(gradle_home) = gradle_home {
+        let dir = gradle_home
+            .join("caches")
+            .join("modules-2")
+            .join("files-2.1")
+            .join(GROUP)
+            .join(artifact);
+        for version in subdirectories(&dir) {
+            let file_name = format!("{artifact}-{version}.jar");
+            let jar = subdirectories(&dir.join(&version))
+                .into_iter()
+                .map(|hash| dir.join(&version).join(hash).join(&file_name))
+                .find(|jar| jar.is_file());
+            if let Some(jar) = jar {
+                versions.push((version, jar));
+            }
+        }
+    }
+
+    versions.sort_by(|(a, _), (b, _)| compare_versions(a, b));
+    versions
+}
+
+fn subdirectories(dir: &Path) -> Vec<String> {
+    let Ok(entries) = read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
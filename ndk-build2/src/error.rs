@@ -1,4 +1,5 @@
 use {
+    crate::{keystore::KeystoreType, target::Target},
     std::{
         io::Error as IoError, num::ParseIntError, path::PathBuf, process::Command, str::Utf8Error,
     },
@@ -51,6 +52,8 @@ pub enum NdkError {
     CmdFailed(Box<Command>),
     #[error(transparent)]
     Serialize(#[from] quick_xml::SeError),
+    #[error(transparent)]
+    Xml(#[from] quick_xml::Error),
     #[error("String `{1}` is not a UID")]
     NotAUid(#[source] ParseIntError, String),
     #[error("Could not find `package:{package}` in output `{output}`")]
@@ -61,4 +64,105 @@ pub enum NdkError {
     Utf8(#[from] Utf8Error),
     #[error(transparent)]
     ParseInt(#[from] ParseIntError),
+    #[error("Invalid DER structure: {0}")]
+    InvalidDer(String),
+    #[error("Invalid zip archive: {0}")]
+    InvalidZip(String),
+    #[error("Keystore `{0:?}` is not supported: {1}")]
+    UnsupportedKeystore(PathBuf, String),
+    #[error("Keystore `{0:?}` was tampered with, or password was incorrect.")]
+    IncorrectKeystorePassword(PathBuf),
+    #[error("Cannot recover key `{0}`, the key password is incorrect.")]
+    IncorrectKeyPassword(String),
+    #[error("Incorrect password.")]
+    IncorrectPassword,
+    #[error("No private key{} found in keystore `{path:?}`.", key_alias(alias))]
+    KeyNotFound {
+        path: PathBuf,
+        alias: Option<String>,
+    },
+    #[error("Keystore `{path:?}` is a {found} keystore, but `keystore_type` is {expected}.")]
+    KeystoreTypeMismatch {
+        path: PathBuf,
+        expected: KeystoreType,
+        found: KeystoreType,
+    },
+    #[error("Key algorithm `{0}` is not supported, only RSA keys can be used for signing.")]
+    UnsupportedKeyAlgorithm(String),
+    #[error("Algorithm `{0}` is not supported.")]
+    UnsupportedAlgorithm(String),
+    #[error(transparent)]
+    Rsa(#[from] rsa::Error),
+    #[error("Invalid binary XML: {0}")]
+    InvalidBinaryXml(String),
+    #[error("Invalid APK signature: {0}")]
+    InvalidApkSignature(String),
+    #[error("Image error on `{0:?}`: {1}")]
+    Image(PathBuf, #[source] image::ImageError),
+    #[error("Invalid color `{0}`, expected `#RGB`, `#ARGB`, `#RRGGBB` or `#AARRGGBB`.")]
+    InvalidColor(String),
+    #[error("No strings are defined for the default locale `{0}`.")]
+    MissingDefaultLocale(String),
+    #[error(
+        "String `{name}` of locale `{locale}` is missing from the default locale `{default_locale}`."
+    )]
+    MissingDefaultString {
+        name: String,
+        locale: String,
+        default_locale: String,
+    },
+    #[error(
+        "Invalid locale `{0}`, expected a BCP 47 language tag such as `en`, `zh-CN` or `sr-Latn`."
+    )]
+    InvalidLocale(String),
+    #[error("Invalid resource ID `{1}` in `{0:?}`.")]
+    InvalidResourceId(PathBuf, String),
+    #[error(
+        "Could not find `{0}` in the Maven repositories or the Gradle cache, \
+        download it or set its path explicitly."
+    )]
+    MavenArtifactNotFound(String),
+    #[error("Invalid Maven coordinate `{0}`, expected `group:artifact:version`.")]
+    InvalidMavenCoordinate(String),
+    #[error("Invalid POM of `{0}`: {1}")]
+    InvalidPom(String, String),
+    #[error("Invalid Android library `{0:?}`: {1}")]
+    InvalidAar(PathBuf, String),
+    #[error("Manifest merger failed:\n{}", .0.join("\n"))]
+    ManifestMergeFailed(Vec<String>),
+    #[error("Unsupported URL `{0}`, only `http://` repositories are supported.")]
+    UnsupportedUrl(String),
+    #[error("HTTP request to `{0}` failed: {1}")]
+    Http(String, String),
+    #[error("Invalid class file `{0:?}`: {1}")]
+    InvalidClass(PathBuf, String),
+    #[error("Invalid ELF file `{0:?}`: {1}")]
+    InvalidElf(PathBuf, String),
+    #[error(
+        "The following native libraries are not compatible with 16 KB page sizes, \
+        their LOAD segments must be aligned to at least 16384 bytes:\n{}\n\
+        Rebuild them with `-Wl,-z,max-page-size=16384`, or with NDK r28 or newer.",
+        unaligned_libs(libs)
+    )]
+    UnalignedLoadSegments { libs: Vec<(PathBuf, Target, u64)> },
+}
+
+fn key_alias(alias: &Option<String>) -> String {
+    alias
+        .as_ref()
+        .map_or_else(String::new, |alias| format!(" `{alias}`"))
+}
+
+fn unaligned_libs(libs: &[(PathBuf, Target, u64)]) -> String {
+    libs.iter()
+        .map(|(path, target, align)| {
+            format!(
+                "  {} ({}): alignment {}",
+                path.display(),
+                target.android_abi(),
+                align
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
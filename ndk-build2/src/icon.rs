@@ -0,0 +1,232 @@
+//! 从一张图片，或自适应图标的前景与背景层，生成各密度的启动器图标资源。
+
+use {
+    crate::error::NdkError,
+    image::{
+        DynamicImage, ImageFormat, Rgba, RgbaImage,
+        imageops::{FilterType, overlay},
+    },
+    serde::Deserialize,
+    std::{
+        fs::{create_dir_all, remove_dir_all, write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// 生成的图标在清单中的引用
+pub const LAUNCHER_ICON: &str = "@mipmap/ic_launcher";
+
+/// 各密度的资源限定符，以及相对于 mdpi 的缩放比例（以 1/4 为单位）
+const DENSITIES: &[(&str, u32)] = &[
+    ("mdpi", 4),
+    ("hdpi", 6),
+    ("xhdpi", 8),
+    ("xxhdpi", 12),
+    ("xxxhdpi", 16),
+];
+/// 传统图标的边长（dp）
+const ICON_DP: u32 = 48;
+/// 自适应图标各层的边长（dp）
+const LAYER_DP: u32 = 108;
+/// 自适应图标各层中可见部分的边长（dp），位于层的中央
+const VISIBLE_DP: u32 = 72;
+
+/// 启动器图标的来源
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "LauncherIconValue")]
+pub enum LauncherIcon {
+    /// 单张图片，缩放到各个密度
+    Image(PathBuf),
+    /// 自适应图标（Android 8.0 及以上）的前景与背景层，每层的边长为 108dp，中央的 72dp 可见。
+    /// 更早的设备使用由两层合成的传统图标
+    Adaptive {
+        foreground: PathBuf,
+        background: IconBackground,
+    },
+}
+
+/// 自适应图标的背景层
+#[derive(Clone, Debug)]
+pub enum IconBackground {
+    Image(PathBuf),
+    /// Android 资源中的颜色，例如 `#3ddc84`
+    Color(String),
+}
+
+/// 配置文件中的图标：图片路径，或前景与背景层；背景层以 `#` 开头时为颜色
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LauncherIconValue {
+    Image(PathBuf),
+    Adaptive {
+        foreground: PathBuf,
+        background: String,
+    },
+}
+
+impl TryFrom<LauncherIconValue> for LauncherIcon {
+    type Error = NdkError;
+
+    fn try_from(value: LauncherIconValue) -> Result<Self, Self::Error> {
+        Ok(match value {
+            LauncherIconValue::Image(path) => Self::Image(path),
+            LauncherIconValue::Adaptive {
+                foreground,
+                background,
+            } => Self::Adaptive {
+                foreground,
+                background: if background.starts_with('#') {
+                    parse_color(&background)?;
+                    IconBackground::Color(background)
+                } else {
+                    IconBackground::Image(background.into())
+                },
+            },
+        })
+    }
+}
+
+impl LauncherIcon {
+    /// 清空资源目录 `res_dir`，写入各密度的 `mipmap-*/ic_launcher.png`；
+    /// 自适应图标还会写入各层的图片与 `mipmap-anydpi-v26/ic_launcher.xml`。
+    ///
+    /// 相对路径相对于 `base_dir`。
+    pub fn write_resources(&self, base_dir: &Path, res_dir: &Path) -> Result<(), NdkError> {
+        let _ = remove_dir_all(res_dir);
+        let legacy = match self {
+            Self::Image(path) => open(&base_dir.join(path))?,
+            Self::Adaptive {
+                foreground,
+                background,
+            } => {
+                let foreground = open(&base_dir.join(foreground))?;
+                write_layer(res_dir, "ic_launcher_foreground", &foreground)?;
+                let size = LAYER_DP * 4;
+                let mut layers = match background {
+                    IconBackground::Image(path) => {
+                        let background = open(&base_dir.join(path))?;
+                        write_layer(res_dir, "ic_launcher_background", &background)?;
+                        background
+                            .resize_to_fill(size, size, FilterType::Lanczos3)
+                            .to_rgba8()
+                    }
+                    IconBackground::Color(color) => {
+                        write_file(
+                            &res_dir.join("values").join("ic_launcher_background.xml"),
+                            format!(
+                                "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                                <resources>\n    \
+                                <color name=\"ic_launcher_background\">{color}</color>\n\
+                                </resources>\n"
+                            ),
+                        )?;
+                        RgbaImage::from_pixel(size, size, parse_color(color)?)
+                    }
+                };
+                let background = match background {
+                    IconBackground::Image(_) => "@mipmap/ic_launcher_background",
+                    IconBackground::Color(_) => "@color/ic_launcher_background",
+                };
+                write_file(
+                    &res_dir.join("mipmap-anydpi-v26").join("ic_launcher.xml"),
+                    format!(
+                        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+                        <adaptive-icon xmlns:android=\"http://schemas.android.com/apk/res/android\">\n    \
+                        <background android:drawable=\"{background}\" />\n    \
+                        <foreground android:drawable=\"@mipmap/ic_launcher_foreground\" />\n\
+                        </adaptive-icon>\n"
+                    ),
+                )?;
+
+                // 传统图标：合成两层，并裁剪出可见部分
+                let foreground = foreground.resize_to_fill(size, size, FilterType::Lanczos3);
+                overlay(&mut layers, &foreground.to_rgba8(), 0, 0);
+                let offset = (LAYER_DP - VISIBLE_DP) * 2;
+                DynamicImage::ImageRgba8(layers).crop_imm(
+                    offset,
+                    offset,
+                    VISIBLE_DP * 4,
+                    VISIBLE_DP * 4,
+                )
+            }
+        };
+        write_densities(res_dir, "ic_launcher", ICON_DP, &legacy)
+    }
+}
+
+fn open(path: &Path) -> Result<DynamicImage, NdkError> {
+    image::open(path).map_err(|e| NdkError::Image(path.into(), e))
+}
+
+fn write_file(path: &Path, contents: String) -> Result<(), NdkError> {
+    create_dir_all(path.parent().unwrap())?;
+    write(path, contents).map_err(|e| NdkError::IoPathError(path.into(), e))
+}
+
+fn write_layer(res_dir: &Path, name: &str, image: &DynamicImage) -> Result<(), NdkError> {
+    write_densities(res_dir, name, LAYER_DP, image)
+}
+
+/// 把 `image` 缩放（并按需居中裁剪为正方形）到边长为 `dp` 的各密度，写入 `mipmap-<density>/<name>.png`
+fn write_densities(
+    res_dir: &Path,
+    name: &str,
+    dp: u32,
+    image: &DynamicImage,
+) -> Result<(), NdkError> {
+    for (density, scale) in DENSITIES {
+        let size = dp * scale / 4;
+        let dir = res_dir.join(format!("mipmap-{density}"));
+        create_dir_all(&dir)?;
+        let path = dir.join(format!("{name}.png"));
+        image
+            .resize_to_fill(size, size, FilterType::Lanczos3)
+            .save_with_format(&path, ImageFormat::Png)
+            .map_err(|e| NdkError::Image(path, e))?;
+    }
+    Ok(())
+}
+
+/// 解析 Android 资源中的颜色：`#RGB`、`#ARGB`、`#RRGGBB` 或 `#AARRGGBB`
+fn parse_color(color: &str) -> Result<Rgba<u8>, NdkError> {
+    let invalid = || NdkError::InvalidColor(color.into());
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let digits = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 | 8 => hex.to_owned(),
+        _ => return Err(invalid()),
+    };
+    let value = u32::from_str_radix(&digits, 16).map_err(|_| invalid())?;
+    let alpha = if digits.len() == 8 { value >> 24 } else { 0xff };
+    Ok(Rgba([
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+        alpha as u8,
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::parse_color, image::Rgba};
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#fff").unwrap(), Rgba([255, 255, 255, 255]));
+        assert_eq!(parse_color("#8f00").unwrap(), Rgba([255, 0, 0, 0x88]));
+        assert_eq!(
+            parse_color("#3DDC84").unwrap(),
+            Rgba([0x3d, 0xdc, 0x84, 255])
+        );
+        assert_eq!(
+            parse_color("#803ddc84").unwrap(),
+            Rgba([0x3d, 0xdc, 0x84, 0x80])
+        );
+        assert!(parse_color("3ddc84").is_err());
+        assert!(parse_color("#3ddc8").is_err());
+        assert!(parse_color("#gggggg").is_err());
+    }
+}
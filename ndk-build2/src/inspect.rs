@@ -0,0 +1,156 @@
+//! 读取已构建 APK 的内容：清单、各 ABI 的动态库、资产、dex 与签名证书。
+
+use {
+    crate::{
+        axml,
+        error::NdkError,
+        readelf::ElfFile,
+        signer::read_signatures,
+        zip::{ZipArchive, ZipEntry},
+    },
+    serde::Serialize,
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// APK 的内容摘要
+#[derive(Debug, Serialize)]
+pub struct ApkInfo {
+    pub path: PathBuf,
+    /// APK 文件大小
+    pub size: u64,
+    pub package: Option<String>,
+    pub version_code: Option<i64>,
+    pub version_name: Option<String>,
+    pub min_sdk_version: Option<i64>,
+    pub target_sdk_version: Option<i64>,
+    /// 解码后的 `AndroidManifest.xml`
+    pub manifest: Option<String>,
+    /// 以 ABI 分组的 `lib/<abi>/*.so`
+    pub libs: BTreeMap<String, Vec<LibInfo>>,
+    pub dex: Vec<EntryInfo>,
+    pub assets: Vec<EntryInfo>,
+    pub signers: Vec<SignerInfo>,
+}
+
+/// APK 中的一个条目
+#[derive(Debug, Serialize)]
+pub struct EntryInfo {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+impl EntryInfo {
+    fn new(entry: &ZipEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            size: entry.uncompressed_size as u64,
+            compressed_size: entry.compressed_size as u64,
+        }
+    }
+}
+
+/// APK 中的一个动态库
+#[derive(Debug, Serialize)]
+pub struct LibInfo {
+    #[serde(flatten)]
+    pub entry: EntryInfo,
+    /// `DT_NEEDED` 中列出的依赖
+    pub needed: Vec<String>,
+}
+
+/// 签名者证书，同一证书用于多个签名方案时只出现一次
+#[derive(Debug, Serialize)]
+pub struct SignerInfo {
+    /// 使用该证书的签名方案，例如 `["v2", "v3"]`
+    pub schemes: Vec<String>,
+    pub sha256: String,
+    pub sha1: String,
+}
+
+impl ApkInfo {
+    pub fn read(path: &Path) -> Result<Self, NdkError> {
+        let archive = ZipArchive::open(path)?;
+        let mut info = Self {
+            path: path.to_owned(),
+            size: archive.data().len() as u64,
+            package: None,
+            version_code: None,
+            version_name: None,
+            min_sdk_version: None,
+            target_sdk_version: None,
+            manifest: None,
+            libs: BTreeMap::new(),
+            dex: Vec::new(),
+            assets: Vec::new(),
+            signers: Vec::new(),
+        };
+
+        if let Some(entry) = archive.entry("AndroidManifest.xml") {
+            let manifest = axml::decode(&archive.read(entry)?)?;
+            let attribute = |element: &axml::Element, name: &str| {
+                element.attribute(name).map(|attr| attr.value.clone())
+            };
+            info.package = attribute(&manifest, "package").map(|value| value.to_string());
+            info.version_code = attribute(&manifest, "versionCode").and_then(|v| v.as_int());
+            info.version_name = attribute(&manifest, "versionName").map(|v| v.to_string());
+            if let Some(sdk) = manifest.children_named("uses-sdk").next() {
+                info.min_sdk_version = attribute(sdk, "minSdkVersion").and_then(|v| v.as_int());
+                info.target_sdk_version =
+                    attribute(sdk, "targetSdkVersion").and_then(|v| v.as_int());
+            }
+            info.manifest = Some(manifest.to_string());
+        }
+
+        for entry in archive.entries() {
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name.as_str();
+            if let Some((abi, file)) = name
+                .strip_prefix("lib/")
+                .and_then(|rest| rest.split_once('/'))
+                && file.ends_with(".so")
+            {
+                let data = archive.read(entry)?;
+                let needed = ElfFile::parse(Path::new(name), &data)?.needed_libs()?;
+                info.libs.entry(abi.to_owned()).or_default().push(LibInfo {
+                    entry: EntryInfo::new(entry),
+                    needed,
+                });
+            } else if name.starts_with("assets/") {
+                info.assets.push(EntryInfo::new(entry));
+            } else if !name.contains('/') && name.ends_with(".dex") {
+                info.dex.push(EntryInfo::new(entry));
+            }
+        }
+
+        let signatures = read_signatures(&archive)?;
+        for (scheme, certificates) in [
+            ("v1", &signatures.v1),
+            ("v2", &signatures.v2),
+            ("v3", &signatures.v3),
+        ] {
+            for certificate in certificates {
+                let sha256 = certificate.sha256_fingerprint();
+                match info
+                    .signers
+                    .iter_mut()
+                    .find(|signer| signer.sha256 == sha256)
+                {
+                    Some(signer) => signer.schemes.push(scheme.to_owned()),
+                    None => info.signers.push(SignerInfo {
+                        schemes: vec![scheme.to_owned()],
+                        sha256,
+                        sha1: certificate.sha1_fingerprint(),
+                    }),
+                }
+            }
+        }
+
+        Ok(info)
+    }
+}
@@ -0,0 +1,358 @@
+//! 比较编译后的 `.class` 文件中的 `native` 方法与 APK 中动态库导出的 JNI 函数，
+//! 在构建时发现运行时才会出现的 `UnsatisfiedLinkError`；也可以为这些方法生成 Rust 函数签名。
+
+use {
+    crate::{
+        classfile::{ClassFile, NativeMethod},
+        error::NdkError,
+        readelf::ElfFile,
+        resource_ids::ident,
+        zip::ZipArchive,
+    },
+    std::{
+        collections::{BTreeMap, BTreeSet, HashMap},
+        ffi::OsStr,
+        fmt::Write as _,
+        fs::{read, read_dir},
+        path::{Path, PathBuf},
+    },
+};
+
+/// 导出后由动态库自行调用 `RegisterNatives` 注册方法的入口
+const JNI_ON_LOAD: &str = "JNI_OnLoad";
+
+/// JNI 绑定的检查结果
+#[derive(Debug, Default)]
+pub struct JniBindings {
+    /// 没有对应导出函数的 `native` 方法，调用时会抛出 `UnsatisfiedLinkError`
+    pub missing: Vec<String>,
+    /// 没有对应 `native` 方法的导出 `Java_` 函数，通常是类名、方法名或包名拼写错误
+    pub orphaned: Vec<String>,
+}
+
+impl JniBindings {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.orphaned.is_empty()
+    }
+
+    /// 所有问题的描述，缺失的绑定在前
+    pub fn problems(&self) -> impl Iterator<Item = &String> {
+        self.missing.iter().chain(&self.orphaned)
+    }
+}
+
+/// 递归读取 `classes_dir` 中所有 `.class` 文件声明的 `native` 方法
+pub fn read_native_methods(classes_dir: &Path) -> Result<BTreeSet<NativeMethod>, NdkError> {
+    Ok(read_classes(classes_dir)?
+        .into_iter()
+        .flat_map(|class| class.native_methods)
+        .collect())
+}
+
+/// 递归读取 `classes_dir` 中的所有 `.class` 文件
+pub fn read_classes(classes_dir: &Path) -> Result<Vec<ClassFile>, NdkError> {
+    let mut classes = Vec::new();
+    if classes_dir.exists() {
+        collect_classes(classes_dir, &mut classes)?;
+    }
+    Ok(classes)
+}
+
+fn collect_classes(dir: &Path, classes: &mut Vec<ClassFile>) -> Result<(), NdkError> {
+    for entry in read_dir(dir).map_err(|e| NdkError::IoPathError(dir.into(), e))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_classes(&path, classes)?;
+        } else if path.extension() == Some(OsStr::new("class")) {
+            let data = read(&path).map_err(|e| NdkError::IoPathError(path.clone(), e))?;
+            classes.push(ClassFile::parse(&path, &data)?);
+        }
+    }
+    Ok(())
+}
+
+/// 为通过 JNI 访问的类生成 R8 保留规则，使其类名与成员名不被移除或混淆：
+/// 声明了 `native` 方法的类，以及内部名称（例如 `com/example/app/Callback`，
+/// 通常传给 `FindClass`）出现在 `libs` 中的类
+pub fn keep_rules(classes: &[ClassFile], libs: &[PathBuf]) -> Result<Vec<String>, NdkError> {
+    let mut kept = classes
+        .iter()
+        .filter(|class| !class.native_methods.is_empty())
+        .map(|class| class.name.as_str())
+        .collect::<BTreeSet<_>>();
+
+    let names = classes
+        .iter()
+        .map(|class| class.name.as_str())
+        .collect::<Vec<_>>();
+    for lib in libs {
+        let data = read(lib).map_err(|e| NdkError::IoPathError(lib.clone(), e))?;
+        kept.extend(find_names(&data, &names));
+    }
+
+    Ok(kept
+        .into_iter()
+        .map(|name| format!("-keep class {} {{ *; }}", name.replace('/', ".")))
+        .collect())
+}
+
+/// 按 ABI 比较 `methods` 与 APK 或 App Bundle 中 `lib/<abi>/*.so` 导出的函数。
+///
+/// 导出了 `JNI_OnLoad` 的 ABI 可能通过 `RegisterNatives` 注册方法，不检查其缺失的绑定。
+pub fn check_bindings(
+    methods: &BTreeSet<NativeMethod>,
+    archive: &Path,
+) -> Result<JniBindings, NdkError> {
+    let archive = ZipArchive::open(archive)?;
+    // ABI 到各动态库导出的函数
+    let mut abis = BTreeMap::<&str, BTreeMap<&str, Vec<String>>>::new();
+    for entry in archive.entries() {
+        // App Bundle 中的动态库位于 `base/lib/<abi>/`
+        let name = entry.name.strip_prefix("base/").unwrap_or(&entry.name);
+        let Some((abi, file)) = name
+            .strip_prefix("lib/")
+            .and_then(|name| name.split_once('/'))
+        else {
+            continue;
+        };
+        if entry.is_dir() || !file.ends_with(".so") {
+            continue;
+        }
+        let data = archive.read(entry)?;
+        let symbols = ElfFile::parse(Path::new(&entry.name), &data)?.dynamic_symbols()?;
+        abis.entry(abi).or_default().insert(
+            file,
+            symbols.into_iter().map(|symbol| symbol.name).collect(),
+        );
+    }
+
+    let mut bindings = JniBindings::default();
+    for (abi, libs) in &abis {
+        let exported = libs
+            .values()
+            .flatten()
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>();
+        let mut bound = BTreeSet::new();
+        for method in methods {
+            let (short_name, long_name) = (method.short_name(), method.long_name());
+            if exported.contains(short_name.as_str()) || exported.contains(long_name.as_str()) {
+                bound.insert(short_name);
+                bound.insert(long_name);
+            } else if !exported.contains(JNI_ON_LOAD) {
+                bindings.missing.push(format!(
+                    "native method `{method}` has neither `{short_name}` nor `{long_name}` in lib/{abi}"
+                ));
+            }
+        }
+        for (file, symbols) in libs {
+            for symbol in symbols {
+                if symbol.starts_with("Java_") && !bound.contains(symbol) {
+                    bindings.orphaned.push(format!(
+                        "`lib/{abi}/{file}` exports `{symbol}`, which matches no native method"
+                    ));
+                }
+            }
+        }
+    }
+    Ok(bindings)
+}
+
+/// `names` 中出现在 `data` 里的名称，忽略短于 4 个字节的名称
+fn find_names<'a>(data: &[u8], names: &[&'a str]) -> BTreeSet<&'a str> {
+    // 按名称的前 4 个字节索引，只需扫描一遍数据
+    let mut prefixes = HashMap::<&[u8], Vec<&str>>::new();
+    for name in names.iter().filter(|name| name.len() >= 4) {
+        prefixes
+            .entry(&name.as_bytes()[..4])
+            .or_default()
+            .push(name);
+    }
+    let mut found = BTreeSet::new();
+    for (offset, window) in data.windows(4).enumerate() {
+        for name in prefixes.get(window).into_iter().flatten() {
+            if data[offset..].starts_with(name.as_bytes()) {
+                found.insert(*name);
+            }
+        }
+    }
+    found
+}
+
+/// 生成 `methods` 对应的 Rust 函数，参数与返回值使用 `jni` crate 中的类型，函数体为 `todo!()`。
+///
+/// 同一个类中重载的方法使用带有参数签名的长名称。
+pub fn rust_stubs(methods: &BTreeSet<NativeMethod>) -> String {
+    let mut objects = BTreeSet::new();
+    let mut primitives = BTreeSet::new();
+    let mut functions = String::new();
+    for method in methods {
+        let overloaded = methods
+            .iter()
+            .filter(|other| other.class == method.class && other.name == method.name)
+            .count()
+            > 1;
+        let name = if overloaded {
+            method.long_name()
+        } else {
+            method.short_name()
+        };
+        let mut parameters = vec![
+            "mut env: EnvUnowned<'local>".to_owned(),
+            if method.is_static {
+                objects.insert("JClass");
+                "class: JClass<'local>".to_owned()
+            } else {
+                objects.insert("JObject");
+                "this: JObject<'local>".to_owned()
+            },
+        ];
+        for (index, descriptor) in method.parameter_types().into_iter().enumerate() {
+            let name = method
+                .parameters
+                .get(index)
+                .filter(|name| is_parameter_name(name))
+                .map_or_else(|| format!("arg{index}"), |name| ident(name));
+            parameters.push(format!(
+                "{name}: {}",
+                rust_type(descriptor, &mut objects, &mut primitives)
+            ));
+        }
+        let return_type = match method.return_type() {
+            "V" => String::new(),
+            descriptor => format!(
+                " -> {}",
+                rust_type(descriptor, &mut objects, &mut primitives)
+            ),
+        };
+
+        write!(
+            functions,
+            "\n/// `{method}`\n\
+            #[unsafe(no_mangle)]\n\
+            #[allow(non_snake_case, unused_mut, unused_variables)]\n\
+            pub extern \"system\" fn {name}<'local>(\n"
+        )
+        .unwrap();
+        for parameter in parameters {
+            writeln!(functions, "    {parameter},").unwrap();
+        }
+        writeln!(functions, "){return_type} {{\n    todo!()\n}}").unwrap();
+    }
+
+    let mut rust = String::from(
+        "// Generated by `cargo apk2 jni-stubs` from the native methods of the compiled classes\n",
+    );
+    if methods.is_empty() {
+        return rust;
+    }
+    rust.push_str("\nuse jni::{\n    EnvUnowned,\n");
+    let join = |names: &BTreeSet<&str>| names.iter().copied().collect::<Vec<_>>().join(", ");
+    writeln!(rust, "    objects::{{{}}},", join(&objects)).unwrap();
+    if !primitives.is_empty() {
+        writeln!(rust, "    sys::{{{}}},", join(&primitives)).unwrap();
+    }
+    rust.push_str("};\n");
+    rust.push_str(&functions);
+    rust
+}
+
+/// 类型描述符对应的 `jni` 类型，并记录需要从 `jni::objects` 或 `jni::sys` 导入的名称
+fn rust_type<'a>(
+    descriptor: &str,
+    objects: &mut BTreeSet<&'a str>,
+    primitives: &mut BTreeSet<&'a str>,
+) -> String {
+    let ty = match descriptor {
+        "Z" => "jboolean",
+        "B" => "jbyte",
+        "C" => "jchar",
+        "S" => "jshort",
+        "I" => "jint",
+        "J" => "jlong",
+        "F" => "jfloat",
+        "D" => "jdouble",
+        "Ljava/lang/String;" => "JString",
+        "Ljava/lang/Class;" => "JClass",
+        "Ljava/lang/Throwable;" => "JThrowable",
+        "[Z" => "JBooleanArray",
+        "[B" => "JByteArray",
+        "[C" => "JCharArray",
+        "[S" => "JShortArray",
+        "[I" => "JIntArray",
+        "[J" => "JLongArray",
+        "[F" => "JFloatArray",
+        "[D" => "JDoubleArray",
+        _ if descriptor.starts_with('[') => "JObjectArray",
+        _ => "JObject",
+    };
+    if ty.starts_with('J') {
+        objects.insert(ty);
+        format!("{ty}<'local>")
+    } else {
+        primitives.insert(ty);
+        ty.into()
+    }
+}
+
+/// 参数名称能否用作 Rust 标识符，且不与 `env`、`this` 和 `class` 冲突
+fn is_parameter_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        && !matches!(
+            name,
+            "_" | "env" | "this" | "class" | "self" | "Self" | "super" | "crate"
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{find_names, rust_stubs},
+        crate::classfile::NativeMethod,
+        std::collections::BTreeSet,
+    };
+
+    #[test]
+    fn referenced_names() {
+        let data = b"\0com/example/app/Callbackjava/lang/String\0com/example/app/Cal\0";
+        let names = [
+            "com/example/app/Callback",
+            "com/example/app/Call",
+            "com/example/app/Other",
+            "a/B",
+        ];
+        assert_eq!(
+            find_names(data, &names),
+            BTreeSet::from(["com/example/app/Call", "com/example/app/Callback"])
+        );
+    }
+
+    #[test]
+    fn stubs() {
+        let method = |name: &str, descriptor: &str, parameters: &[&str]| NativeMethod {
+            class: "com/example/app/MainActivity".into(),
+            name: name.into(),
+            descriptor: descriptor.into(),
+            is_static: name == "add",
+            parameters: parameters.iter().map(|name| name.to_string()).collect(),
+        };
+        let rust = rust_stubs(&BTreeSet::from([
+            method("getMessage", "()Ljava/lang/String;", &[]),
+            method("add", "(II)I", &["a", "type"]),
+            method("add", "([J)J", &[]),
+        ]));
+        assert!(rust.contains("    objects::{JClass, JLongArray, JObject, JString},\n"));
+        assert!(rust.contains("    sys::{jint, jlong},\n"));
+        assert!(rust.contains(
+            "pub extern \"system\" fn Java_com_example_app_MainActivity_getMessage<'local>(\n    \
+            mut env: EnvUnowned<'local>,\n    this: JObject<'local>,\n) -> JString<'local> {"
+        ));
+        assert!(rust.contains(
+            "fn Java_com_example_app_MainActivity_add__II<'local>(\n    \
+            mut env: EnvUnowned<'local>,\n    class: JClass<'local>,\n    a: jint,\n    r#type: jint,\n) -> jint {"
+        ));
+        assert!(rust.contains("fn Java_com_example_app_MainActivity_add___3J<'local>("));
+        assert!(rust.contains("    arg0: JLongArray<'local>,\n) -> jlong {"));
+    }
+}
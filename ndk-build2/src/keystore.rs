@@ -0,0 +1,1382 @@
+//! 读取 `keytool` 生成的 PKCS12 与 JKS keystore，用于内置的 APK 签名器；以及生成新的 keystore：
+//! RSA 密钥的 PKCS12 keystore 在进程内生成，其他情况调用 `keytool`。
+
+use {
+    crate::{
+        der::{self, Der, TAG_INTEGER, TAG_OCTET_STRING, TAG_SEQUENCE},
+        error::NdkError,
+    },
+    cbc::cipher::{BlockDecryptMut, BlockEncryptMut, InnerIvInit, KeyIvInit, block_padding::Pkcs7},
+    hmac::{Hmac, Mac},
+    rsa::{
+        Pkcs1v15Sign, RsaPrivateKey,
+        pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey},
+        rand_core::{OsRng, RngCore},
+    },
+    serde::Deserialize,
+    sha1::{Digest, Sha1},
+    sha2::Sha256,
+    std::{
+        fmt::{self, Display, Formatter},
+        fs::{create_dir_all, read, write},
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+        str::FromStr,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+const JKS_MAGIC: u32 = 0xfeedfeed;
+const JCEKS_MAGIC: u32 = 0xcececece;
+
+const OID_PKCS8_SHROUDED_KEY_BAG: &str = "1.2.840.113549.1.12.10.1.2";
+const OID_KEY_BAG: &str = "1.2.840.113549.1.12.10.1.1";
+const OID_CERT_BAG: &str = "1.2.840.113549.1.12.10.1.3";
+const OID_X509_CERTIFICATE: &str = "1.2.840.113549.1.9.22.1";
+const OID_FRIENDLY_NAME: &str = "1.2.840.113549.1.9.20";
+const OID_LOCAL_KEY_ID: &str = "1.2.840.113549.1.9.21";
+const OID_PBES2: &str = "1.2.840.113549.1.5.13";
+const OID_PBKDF2: &str = "1.2.840.113549.1.5.12";
+const OID_HMAC_SHA1: &str = "1.2.840.113549.2.7";
+const OID_HMAC_SHA256: &str = "1.2.840.113549.2.9";
+const OID_AES128_CBC: &str = "2.16.840.1.101.3.4.1.2";
+const OID_AES192_CBC: &str = "2.16.840.1.101.3.4.1.22";
+const OID_AES256_CBC: &str = "2.16.840.1.101.3.4.1.42";
+const OID_DES_EDE3_CBC: &str = "1.2.840.113549.3.7";
+const OID_PBE_SHA1_3DES: &str = "1.2.840.113549.1.12.1.3";
+const OID_PBE_SHA1_RC2_128: &str = "1.2.840.113549.1.12.1.5";
+const OID_PBE_SHA1_RC2_40: &str = "1.2.840.113549.1.12.1.6";
+const OID_JKS_KEY_PROTECTOR: &str = "1.3.6.1.4.1.42.2.17.1.1";
+const OID_EC_PUBLIC_KEY: &str = "1.2.840.10045.2.1";
+const OID_DSA: &str = "1.2.840.10040.4.1";
+
+const OID_SHA256_WITH_RSA: &str = "1.2.840.113549.1.1.11";
+/// 生成 keystore 时 PBKDF2 与 MAC 的迭代次数，与 `keytool` 相同
+const PBE_ITERATIONS: u32 = 10000;
+
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_PRINTABLE_STRING: u8 = 0x13;
+const TAG_BMP_STRING: u8 = 0x1e;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+
+/// Keystore 的存储格式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum KeystoreType {
+    #[serde(rename = "JKS", alias = "jks")]
+    Jks,
+    #[serde(rename = "PKCS12", alias = "pkcs12")]
+    Pkcs12,
+}
+
+impl Display for KeystoreType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Jks => "JKS",
+            Self::Pkcs12 => "PKCS12",
+        })
+    }
+}
+
+impl FromStr for KeystoreType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("jks") {
+            Ok(Self::Jks)
+        } else if s.eq_ignore_ascii_case("pkcs12") {
+            Ok(Self::Pkcs12)
+        } else {
+            Err(format!(
+                "unknown keystore type `{s}`, expected JKS or PKCS12"
+            ))
+        }
+    }
+}
+
+/// DER 编码的 X.509 证书
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    der: Vec<u8>,
+}
+
+impl Certificate {
+    pub fn from_der(der: Vec<u8>) -> Result<Self, NdkError> {
+        let certificate = Self { der };
+        certificate.tbs_fields()?;
+        Ok(certificate)
+    }
+
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// `keytool -list` 风格的 SHA-256 指纹（以冒号分隔的大写十六进制）
+    pub fn sha256_fingerprint(&self) -> String {
+        fingerprint(&Sha256::digest(&self.der))
+    }
+
+    pub fn sha1_fingerprint(&self) -> String {
+        fingerprint(&Sha1::digest(&self.der))
+    }
+
+    /// `TBSCertificate` 中从序列号开始的字段（跳过可选的版本号）
+    fn tbs_fields(&self) -> Result<Vec<Der<'_>>, NdkError> {
+        let certificate = Der::parse_tagged(&self.der, TAG_SEQUENCE)?;
+        let tbs = certificate
+            .children()?
+            .first()
+            .copied()
+            .ok_or_else(|| NdkError::InvalidDer("empty certificate".into()))?
+            .expect(TAG_SEQUENCE)?;
+        let mut fields = tbs.children()?;
+        if fields.first().is_some_and(|field| field.tag == 0xa0) {
+            fields.remove(0);
+        }
+        if fields.len() < 6 {
+            return Err(NdkError::InvalidDer("truncated TBSCertificate".into()));
+        }
+        fields[0].expect(TAG_INTEGER)?;
+        fields[5].expect(TAG_SEQUENCE)?;
+        Ok(fields)
+    }
+
+    /// 序列号（完整的 DER INTEGER）
+    pub(crate) fn serial_number(&self) -> &[u8] {
+        self.tbs_fields().unwrap()[0].raw
+    }
+
+    /// 颁发者名称（完整的 DER `Name`）
+    pub(crate) fn issuer(&self) -> &[u8] {
+        self.tbs_fields().unwrap()[2].raw
+    }
+
+    /// 主体名称（完整的 DER `Name`）
+    pub(crate) fn subject(&self) -> &[u8] {
+        self.tbs_fields().unwrap()[4].raw
+    }
+
+    /// DER 编码的 `SubjectPublicKeyInfo`
+    pub fn public_key_info(&self) -> &[u8] {
+        self.tbs_fields().unwrap()[5].raw
+    }
+
+    /// `keytool -list -v` 风格的主体名称，例如 `CN=Android Debug, O=Android, C=US`
+    pub fn subject_name(&self) -> String {
+        distinguished_name(self.subject())
+    }
+
+    pub fn issuer_name(&self) -> String {
+        distinguished_name(self.issuer())
+    }
+
+    /// 十六进制的序列号
+    pub fn serial_number_hex(&self) -> String {
+        let serial = Der::parse_tagged(self.serial_number(), TAG_INTEGER).unwrap();
+        let content = match serial.content {
+            [0, rest @ ..] if !rest.is_empty() => rest,
+            content => content,
+        };
+        content.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// 有效期的起止时间（UTC），格式为 `YYYY-MM-DD HH:MM:SS`
+    pub fn validity(&self) -> Result<(String, String), NdkError> {
+        let fields = self.tbs_fields()?;
+        let validity = fields[3].expect(TAG_SEQUENCE)?.children()?;
+        match validity.as_slice() {
+            [not_before, not_after] => Ok((format_time(not_before)?, format_time(not_after)?)),
+            _ => Err(NdkError::InvalidDer("invalid certificate validity".into())),
+        }
+    }
+
+    /// 公钥算法及其参数，例如 `RSA (2048 bits)` 或 `EC (P-256)`
+    pub fn key_algorithm(&self) -> Result<String, NdkError> {
+        let info = Der::parse_tagged(self.public_key_info(), TAG_SEQUENCE)?.children()?;
+        let (Some(algorithm), Some(key)) = (info.first(), info.get(1)) else {
+            return Err(NdkError::InvalidDer(
+                "truncated SubjectPublicKeyInfo".into(),
+            ));
+        };
+        let algorithm = algorithm.children()?;
+        let oid = algorithm
+            .first()
+            .ok_or_else(|| NdkError::InvalidDer("empty AlgorithmIdentifier".into()))?
+            .oid()?;
+        Ok(match oid.as_str() {
+            der::OID_RSA_ENCRYPTION => {
+                // BIT STRING 的第一个字节是未使用的位数，其后为 `RSAPublicKey`
+                let key = key
+                    .expect(TAG_BIT_STRING)?
+                    .content
+                    .get(1..)
+                    .unwrap_or_default();
+                let modulus = Der::parse_tagged(key, TAG_SEQUENCE)?
+                    .children()?
+                    .first()
+                    .ok_or_else(|| NdkError::InvalidDer("empty RSAPublicKey".into()))?
+                    .expect(TAG_INTEGER)?
+                    .content;
+                let modulus = match modulus {
+                    [0, rest @ ..] => rest,
+                    modulus => modulus,
+                };
+                let bits = modulus
+                    .first()
+                    .map_or(0, |b| modulus.len() * 8 - b.leading_zeros() as usize);
+                format!("RSA ({bits} bits)")
+            }
+            OID_EC_PUBLIC_KEY => {
+                let curve = algorithm.get(1).map(|curve| curve.oid()).transpose()?;
+                match curve.as_deref() {
+                    Some("1.2.840.10045.3.1.7") => "EC (P-256)".into(),
+                    Some("1.3.132.0.34") => "EC (P-384)".into(),
+                    Some("1.3.132.0.35") => "EC (P-521)".into(),
+                    _ => "EC".into(),
+                }
+            }
+            OID_DSA => "DSA".into(),
+            other => other.to_owned(),
+        })
+    }
+}
+
+/// 将 DER 编码的 `Name` 格式化为以逗号分隔的字符串，与 `keytool` 一样从最后一个 RDN 开始
+fn distinguished_name(name: &[u8]) -> String {
+    let attribute = |attribute: &Der| -> Option<String> {
+        let children = attribute.children().ok()?;
+        let key = match children.first()?.oid().ok()?.as_str() {
+            "2.5.4.3" => "CN".to_owned(),
+            "2.5.4.6" => "C".to_owned(),
+            "2.5.4.7" => "L".to_owned(),
+            "2.5.4.8" => "ST".to_owned(),
+            "2.5.4.10" => "O".to_owned(),
+            "2.5.4.11" => "OU".to_owned(),
+            "1.2.840.113549.1.9.1" => "EMAILADDRESS".to_owned(),
+            other => other.to_owned(),
+        };
+        let value = children.get(1)?;
+        let value = match value.tag {
+            // BMPString
+            0x1e => String::from_utf16_lossy(
+                &value
+                    .content
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => String::from_utf8_lossy(value.content).into_owned(),
+        };
+        Some(format!("{key}={value}"))
+    };
+    let Ok(rdns) = Der::parse_tagged(name, TAG_SEQUENCE).and_then(|name| name.children()) else {
+        return String::new();
+    };
+    rdns.iter()
+        .rev()
+        .flat_map(|rdn| rdn.children().unwrap_or_default())
+        .filter_map(|attr| attribute(&attr))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 将 `UTCTime` 或 `GeneralizedTime` 格式化为 `YYYY-MM-DD HH:MM:SS`
+fn format_time(time: &Der) -> Result<String, NdkError> {
+    let invalid = || NdkError::InvalidDer("invalid certificate time".into());
+    let text = std::str::from_utf8(time.content).map_err(|_| invalid())?;
+    let text = text.strip_suffix('Z').ok_or_else(invalid)?;
+    let digits = match time.tag {
+        TAG_UTC_TIME if text.len() == 12 => {
+            // RFC 5280：两位年份小于 50 时属于 21 世纪
+            let century = if text[..2] < *"50" { "20" } else { "19" };
+            format!("{century}{text}")
+        }
+        TAG_GENERALIZED_TIME if text.len() == 14 => text.to_owned(),
+        _ => return Err(invalid()),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    Ok(format!(
+        "{}-{}-{} {}:{}:{}",
+        &digits[..4],
+        &digits[4..6],
+        &digits[6..8],
+        &digits[8..10],
+        &digits[10..12],
+        &digits[12..14]
+    ))
+}
+
+/// 生成新 keystore（或调用 `keytool -genkeypair` 向已有 keystore 添加条目）所需的参数
+#[derive(Clone, Debug)]
+pub struct KeyGenOptions {
+    pub path: PathBuf,
+    pub keystore_type: KeystoreType,
+    pub keystore_password: String,
+    pub alias: String,
+    /// 未指定时与 keystore 密码相同；PKCS12 keystore 不支持不同的密钥密码
+    pub key_password: Option<String>,
+    /// 证书的主体名称，例如 `CN=Android Debug,O=Android,C=US`
+    pub dname: String,
+    /// `RSA`、`EC` 或 `DSA`，内置签名器只支持 RSA
+    pub key_algorithm: String,
+    /// 未指定时使用 `keytool` 的默认值，进程内生成的 RSA 密钥为 3072 位
+    pub key_size: Option<u32>,
+    /// 证书有效天数
+    pub validity: u32,
+}
+
+impl KeyGenOptions {
+    //noinspection SpellCheckingInspection
+    pub fn generate(&self) -> Result<(), NdkError> {
+        let key_password = self
+            .key_password
+            .as_deref()
+            .unwrap_or(&self.keystore_password);
+        if self.keystore_type == KeystoreType::Pkcs12 && key_password != self.keystore_password {
+            return Err(NdkError::UnsupportedKeystore(
+                self.path.clone(),
+                "PKCS12 keystores can't use a key password different from the keystore password"
+                    .into(),
+            ));
+        }
+
+        // 新建 RSA 密钥的 PKCS12 keystore 无需 `keytool`
+        if self.keystore_type == KeystoreType::Pkcs12
+            && self.key_algorithm.eq_ignore_ascii_case("RSA")
+            && !self.path.exists()
+        {
+            return self.generate_pkcs12();
+        }
+
+        let mut keytool = keytool()?;
+        keytool
+            .arg("-genkeypair")
+            .arg("-v")
+            .arg("-keystore")
+            .arg(&self.path)
+            .arg("-storetype")
+            .arg(self.keystore_type.to_string())
+            .arg("-storepass")
+            .arg(&self.keystore_password)
+            .arg("-alias")
+            .arg(&self.alias)
+            .arg("-keypass")
+            .arg(key_password)
+            .arg("-dname")
+            .arg(&self.dname)
+            .arg("-keyalg")
+            .arg(&self.key_algorithm)
+            .arg("-validity")
+            .arg(self.validity.to_string());
+        if let Some(key_size) = self.key_size {
+            keytool.arg("-keysize").arg(key_size.to_string());
+        }
+        if !keytool.status()?.success() {
+            return Err(NdkError::CmdFailed(Box::new(keytool)));
+        }
+        Ok(())
+    }
+
+    /// 在进程内生成 RSA 密钥与自签名证书，写入与 `keytool` 默认格式相同的 PKCS12 keystore：
+    /// 私钥以 PBES2（PBKDF2-HMAC-SHA256、AES-256-CBC）加密，整体以 HMAC-SHA256 保护
+    fn generate_pkcs12(&self) -> Result<(), NdkError> {
+        let invalid = |e: &dyn Display| NdkError::InvalidDer(e.to_string());
+        let name = self.distinguished_name()?;
+        let private_key = RsaPrivateKey::new(&mut OsRng, self.key_size.unwrap_or(3072) as usize)?;
+        let public_key = private_key
+            .to_public_key()
+            .to_public_key_der()
+            .map_err(|e| invalid(&e))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let validity = der::sequence(&[
+            &der_time(now),
+            &der_time(now + self.validity as u64 * 24 * 60 * 60),
+        ]);
+        let signature_algorithm = der::algorithm(OID_SHA256_WITH_RSA);
+        // v1 证书，序列号为正的随机数
+        let tbs = der::sequence(&[
+            &der::integer(OsRng.next_u64() >> 1),
+            &signature_algorithm,
+            &name,
+            &validity,
+            &name,
+            public_key.as_bytes(),
+        ]);
+        let signature = private_key.sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(&tbs))?;
+        let certificate = der::sequence(&[
+            &tbs,
+            &signature_algorithm,
+            &der::tlv(TAG_BIT_STRING, &[&[0], signature.as_slice()].concat()),
+        ]);
+
+        // 私钥条目与证书条目以 localKeyId 关联
+        let attributes = der::set(&[
+            &der::sequence(&[
+                &der::oid(OID_FRIENDLY_NAME),
+                &der::set(&[&der::tlv(TAG_BMP_STRING, &utf16_be(&self.alias))]),
+            ]),
+            &der::sequence(&[
+                &der::oid(OID_LOCAL_KEY_ID),
+                &der::set(&[&der::octet_string(&Sha1::digest(&certificate))]),
+            ]),
+        ]);
+        let private_key_info = private_key.to_pkcs8_der().map_err(|e| invalid(&e))?;
+        let key_bag = der::sequence(&[
+            &der::oid(OID_PKCS8_SHROUDED_KEY_BAG),
+            &der::context(
+                0,
+                &encrypt_private_key_info(private_key_info.as_bytes(), &self.keystore_password),
+            ),
+            &attributes,
+        ]);
+        let cert_bag = der::sequence(&[
+            &der::oid(OID_CERT_BAG),
+            &der::context(
+                0,
+                &der::sequence(&[
+                    &der::oid(OID_X509_CERTIFICATE),
+                    &der::context(0, &der::octet_string(&certificate)),
+                ]),
+            ),
+            &attributes,
+        ]);
+        let data = |content: &[u8]| {
+            der::sequence(&[
+                &der::oid(der::OID_DATA),
+                &der::context(0, &der::octet_string(content)),
+            ])
+        };
+        let auth_safe = der::sequence(&[
+            &data(&der::sequence(&[&key_bag])),
+            &data(&der::sequence(&[&cert_bag])),
+        ]);
+
+        let salt = random_bytes::<16>();
+        let key = pkcs12_kdf::<Sha256>(
+            &bmp_password(&self.keystore_password),
+            &salt,
+            3,
+            PBE_ITERATIONS as u64,
+            32,
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key).unwrap();
+        mac.update(&auth_safe);
+        let mac_data = der::sequence(&[
+            &der::sequence(&[
+                &der::algorithm(der::OID_SHA256),
+                &der::octet_string(&mac.finalize().into_bytes()),
+            ]),
+            &der::octet_string(&salt),
+            &der::integer(PBE_ITERATIONS as u64),
+        ]);
+        let pfx = der::sequence(&[&der::integer(3), &data(&auth_safe), &mac_data]);
+
+        if let Some(parent) = self.path.parent() {
+            create_dir_all(parent).map_err(|e| NdkError::IoPathError(parent.to_path_buf(), e))?;
+        }
+        write(&self.path, pfx).map_err(|e| NdkError::IoPathError(self.path.clone(), e))
+    }
+
+    /// 将 `dname` 编码为 DER `Name`；与 `keytool` 一样，最后一个属性成为第一个 RDN
+    fn distinguished_name(&self) -> Result<Vec<u8>, NdkError> {
+        let mut rdns = Vec::new();
+        for attribute in self.dname.split(',') {
+            let unsupported = || {
+                NdkError::UnsupportedKeystore(
+                    self.path.clone(),
+                    format!("unsupported dname attribute `{}`", attribute.trim()),
+                )
+            };
+            let (key, value) = attribute.split_once('=').ok_or_else(unsupported)?;
+            let (oid, tag) = match key.trim().to_ascii_uppercase().as_str() {
+                "CN" => ("2.5.4.3", TAG_UTF8_STRING),
+                "C" => ("2.5.4.6", TAG_PRINTABLE_STRING),
+                "L" => ("2.5.4.7", TAG_UTF8_STRING),
+                "ST" => ("2.5.4.8", TAG_UTF8_STRING),
+                "O" => ("2.5.4.10", TAG_UTF8_STRING),
+                "OU" => ("2.5.4.11", TAG_UTF8_STRING),
+                _ => return Err(unsupported()),
+            };
+            rdns.push(der::set(&[&der::sequence(&[
+                &der::oid(oid),
+                &der::tlv(tag, value.trim().as_bytes()),
+            ])]));
+        }
+        rdns.reverse();
+        Ok(der::tlv(TAG_SEQUENCE, &rdns.concat()))
+    }
+}
+
+/// 在 `PATH` 或 `JAVA_HOME` 中查找 `keytool`
+pub(crate) fn keytool() -> Result<Command, NdkError> {
+    if let Ok(keytool) = which::which(bin!("keytool")) {
+        let mut cmd = Command::new(keytool);
+        cmd.stdin(Stdio::null());
+        return Ok(cmd);
+    }
+    if let Some(java) = android_build::java_home() {
+        let keytool = java.join("bin").join(bin!("keytool"));
+        if keytool.exists() {
+            let mut cmd = Command::new(keytool);
+            cmd.stdin(Stdio::null());
+            return Ok(cmd);
+        }
+    }
+
+    Err(NdkError::CmdNotFound("keytool".to_string()))
+}
+
+/// Keystore 中以别名标识的条目
+pub struct KeystoreEntry {
+    pub alias: String,
+    /// 证书链，第一个为条目自身的证书
+    pub certificates: Vec<Certificate>,
+    key: Option<ProtectedKey>,
+}
+
+impl KeystoreEntry {
+    /// 该条目是否包含私钥（否则为受信任的证书条目）
+    pub fn is_private_key(&self) -> bool {
+        self.key.is_some()
+    }
+}
+
+#[derive(Clone)]
+enum ProtectedKey {
+    /// 未加密的 PKCS#8 `PrivateKeyInfo`
+    Plain(Vec<u8>),
+    /// PKCS#8 `EncryptedPrivateKeyInfo`
+    Encrypted(Vec<u8>),
+}
+
+/// 已解析的 keystore；私钥在被请求之前保持加密状态
+pub struct Keystore {
+    pub path: PathBuf,
+    pub keystore_type: KeystoreType,
+    pub entries: Vec<KeystoreEntry>,
+}
+
+impl Keystore {
+    /// 读取 keystore，格式由文件头自动识别
+    pub fn load(path: &Path, password: &str) -> Result<Self, NdkError> {
+        let data = read(path).map_err(|e| NdkError::IoPathError(path.to_path_buf(), e))?;
+        let magic = data
+            .get(..4)
+            .map(|magic| u32::from_be_bytes(magic.try_into().unwrap()));
+        let (keystore_type, entries) = match magic {
+            Some(JKS_MAGIC) => (KeystoreType::Jks, parse_jks(path, &data, password)?),
+            Some(JCEKS_MAGIC) => {
+                return Err(NdkError::UnsupportedKeystore(
+                    path.to_path_buf(),
+                    "JCEKS keystores are not supported".into(),
+                ));
+            }
+            _ => (KeystoreType::Pkcs12, parse_pkcs12(path, &data, password)?),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            keystore_type,
+            entries,
+        })
+    }
+
+    /// 解密私钥条目；未指定 `alias` 时使用第一个私钥条目
+    pub fn private_key(
+        &self,
+        alias: Option<&str>,
+        key_password: &str,
+    ) -> Result<(RsaPrivateKey, Vec<Certificate>), NdkError> {
+        let entry = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_private_key())
+            .find(|entry| alias.is_none_or(|alias| entry.alias.eq_ignore_ascii_case(alias)))
+            .ok_or_else(|| NdkError::KeyNotFound {
+                path: self.path.clone(),
+                alias: alias.map(ToOwned::to_owned),
+            })?;
+        let private_key_info = match entry.key.as_ref().unwrap() {
+            ProtectedKey::Plain(der) => der.clone(),
+            ProtectedKey::Encrypted(der) => {
+                decrypt_private_key_info(der, key_password).map_err(|e| match e {
+                    NdkError::IncorrectPassword => {
+                        NdkError::IncorrectKeyPassword(entry.alias.clone())
+                    }
+                    e => e,
+                })?
+            }
+        };
+        let algorithm = Der::parse_tagged(&private_key_info, TAG_SEQUENCE)?
+            .children()?
+            .get(1)
+            .ok_or_else(|| NdkError::InvalidDer("truncated PrivateKeyInfo".into()))?
+            .children()?
+            .first()
+            .ok_or_else(|| NdkError::InvalidDer("empty AlgorithmIdentifier".into()))?
+            .oid()?;
+        if algorithm != der::OID_RSA_ENCRYPTION {
+            return Err(NdkError::UnsupportedKeyAlgorithm(
+                match algorithm.as_str() {
+                    "1.2.840.10045.2.1" => "EC",
+                    "1.2.840.10040.4.1" => "DSA",
+                    other => other,
+                }
+                .to_string(),
+            ));
+        }
+        let private_key = RsaPrivateKey::from_pkcs8_der(&private_key_info)
+            .map_err(|e| NdkError::InvalidDer(e.to_string()))?;
+        Ok((private_key, entry.certificates.clone()))
+    }
+}
+
+fn fingerprint(digest: &[u8]) -> String {
+    digest
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn utf16_be(password: &str) -> Vec<u8> {
+    password.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+fn parse_jks(path: &Path, data: &[u8], password: &str) -> Result<Vec<KeystoreEntry>, NdkError> {
+    let truncated = || NdkError::UnsupportedKeystore(path.to_path_buf(), "truncated JKS".into());
+    if data.len() < 32 {
+        return Err(truncated());
+    }
+    // 文件末尾是对密码、固定字符串与全部内容计算的 SHA-1 完整性摘要
+    let (body, digest) = data.split_at(data.len() - 20);
+    let mut hasher = Sha1::new();
+    hasher.update(utf16_be(password));
+    hasher.update(b"Mighty Aphrodite");
+    hasher.update(body);
+    if hasher.finalize().as_slice() != digest {
+        return Err(NdkError::IncorrectKeystorePassword(path.to_path_buf()));
+    }
+
+    let mut reader = JksReader { data: body, pos: 8 };
+    let count = reader.u32().ok_or_else(truncated)?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let tag = reader.u32().ok_or_else(truncated)?;
+        let alias = reader.utf().ok_or_else(truncated)?;
+        reader.bytes(8).ok_or_else(truncated)?;
+        match tag {
+            1 => {
+                let len = reader.u32().ok_or_else(truncated)? as usize;
+                let key = reader.bytes(len).ok_or_else(truncated)?.to_vec();
+                let chain_len = reader.u32().ok_or_else(truncated)?;
+                let mut certificates = Vec::new();
+                for _ in 0..chain_len {
+                    certificates.push(reader.certificate().ok_or_else(truncated)??);
+                }
+                entries.push(KeystoreEntry {
+                    alias,
+                    certificates,
+                    key: Some(ProtectedKey::Encrypted(key)),
+                });
+            }
+            2 => entries.push(KeystoreEntry {
+                alias,
+                certificates: vec![reader.certificate().ok_or_else(truncated)??],
+                key: None,
+            }),
+            tag => {
+                return Err(NdkError::UnsupportedKeystore(
+                    path.to_path_buf(),
+                    format!("unknown JKS entry type {tag}"),
+                ));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+struct JksReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JksReader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn utf(&mut self) -> Option<String> {
+        let len = u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()) as usize;
+        Some(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn certificate(&mut self) -> Option<Result<Certificate, NdkError>> {
+        let _cert_type = self.utf()?;
+        let len = self.u32()? as usize;
+        Some(Certificate::from_der(self.bytes(len)?.to_vec()))
+    }
+}
+
+/// Sun 专有的 JKS 私钥保护算法：以 SHA-1 迭代生成密钥流并进行异或
+fn jks_unprotect(protected: &[u8], password: &str) -> Result<Vec<u8>, NdkError> {
+    if protected.len() < 40 {
+        return Err(NdkError::InvalidDer("truncated JKS key".into()));
+    }
+    let password = utf16_be(password);
+    let (salt, rest) = protected.split_at(20);
+    let (encrypted, check) = rest.split_at(rest.len() - 20);
+    let mut plain = Vec::with_capacity(encrypted.len());
+    let mut digest = salt.to_vec();
+    for chunk in encrypted.chunks(20) {
+        digest = Sha1::new()
+            .chain_update(&password)
+            .chain_update(&digest)
+            .finalize()
+            .to_vec();
+        plain.extend(chunk.iter().zip(&digest).map(|(a, b)| a ^ b));
+    }
+    let expected = Sha1::new()
+        .chain_update(&password)
+        .chain_update(&plain)
+        .finalize();
+    if expected.as_slice() != check {
+        return Err(NdkError::IncorrectPassword);
+    }
+    Ok(plain)
+}
+
+fn parse_pkcs12(path: &Path, data: &[u8], password: &str) -> Result<Vec<KeystoreEntry>, NdkError> {
+    let pfx = Der::parse_tagged(data, TAG_SEQUENCE)
+        .map_err(|_| NdkError::UnsupportedKeystore(path.to_path_buf(), "unknown format".into()))?
+        .children()?;
+    let auth_safe = pfx
+        .get(1)
+        .ok_or_else(|| NdkError::InvalidDer("truncated PFX".into()))?;
+    let auth_safe_data = content_info_data(auth_safe)?;
+
+    if let Some(mac_data) = pfx.get(2) {
+        verify_pkcs12_mac(mac_data, auth_safe_data, password).map_err(|e| match e {
+            NdkError::IncorrectPassword => NdkError::IncorrectKeystorePassword(path.to_path_buf()),
+            e => e,
+        })?;
+    }
+
+    let mut bags = Vec::new();
+    for content_info in Der::parse_tagged(auth_safe_data, TAG_SEQUENCE)?.children()? {
+        let fields = content_info.children()?;
+        let content_type = fields
+            .first()
+            .ok_or_else(|| NdkError::InvalidDer("empty ContentInfo".into()))?
+            .oid()?;
+        let safe_contents = match content_type.as_str() {
+            der::OID_DATA => content_info_data(&content_info)?.to_vec(),
+            der::OID_ENCRYPTED_DATA => {
+                let encrypted_data = fields
+                    .get(1)
+                    .ok_or_else(|| NdkError::InvalidDer("missing EncryptedData".into()))?
+                    .children()?
+                    .first()
+                    .ok_or_else(|| NdkError::InvalidDer("empty EncryptedData".into()))?
+                    .children()?;
+                let encrypted_content_info = encrypted_data
+                    .get(1)
+                    .ok_or_else(|| NdkError::InvalidDer("missing EncryptedContentInfo".into()))?
+                    .children()?;
+                let (algorithm, content) = match encrypted_content_info.as_slice() {
+                    [_, algorithm, content, ..] => (algorithm, content),
+                    _ => {
+                        return Err(NdkError::InvalidDer(
+                            "truncated EncryptedContentInfo".into(),
+                        ));
+                    }
+                };
+                // `[0] IMPLICIT OCTET STRING`，可能是构造形式的分段编码
+                let encrypted = if content.tag == 0xa0 {
+                    content
+                        .children()?
+                        .iter()
+                        .flat_map(|part| part.content.iter().copied())
+                        .collect()
+                } else {
+                    content.content.to_vec()
+                };
+                decrypt(algorithm, &encrypted, password).map_err(|e| match e {
+                    NdkError::IncorrectPassword => {
+                        NdkError::IncorrectKeystorePassword(path.to_path_buf())
+                    }
+                    e => e,
+                })?
+            }
+            other => {
+                return Err(NdkError::UnsupportedKeystore(
+                    path.to_path_buf(),
+                    format!("unsupported content type {other}"),
+                ));
+            }
+        };
+        for bag in Der::parse_tagged(&safe_contents, TAG_SEQUENCE)?.children()? {
+            bags.push(SafeBag::parse(&bag)?);
+        }
+    }
+
+    let certificates = bags
+        .iter()
+        .filter_map(|bag| match &bag.value {
+            BagValue::Certificate(certificate) => Some((bag, certificate)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut entries = Vec::new();
+    for bag in &bags {
+        let key = match &bag.value {
+            BagValue::Key(key) => key,
+            _ => continue,
+        };
+        let leaf = certificates
+            .iter()
+            .find(|(cert_bag, _)| {
+                cert_bag.local_key_id.is_some() && cert_bag.local_key_id == bag.local_key_id
+            })
+            .map(|(_, certificate)| (*certificate).clone());
+        let mut chain = leaf.into_iter().collect::<Vec<_>>();
+        // 按颁发者补全证书链，直到自签名的根证书
+        while let Some(last) = chain.last() {
+            if last.issuer() == last.subject() {
+                break;
+            }
+            match certificates
+                .iter()
+                .find(|(_, certificate)| certificate.subject() == last.issuer())
+            {
+                Some((_, certificate)) if !chain.contains(certificate) => {
+                    chain.push((*certificate).clone())
+                }
+                _ => break,
+            }
+        }
+        entries.push(KeystoreEntry {
+            alias: bag.friendly_name.clone().unwrap_or_default(),
+            certificates: chain,
+            key: Some(key.clone()),
+        });
+    }
+    for (bag, certificate) in certificates {
+        if bag.local_key_id.is_none()
+            && let Some(alias) = &bag.friendly_name
+        {
+            entries.push(KeystoreEntry {
+                alias: alias.clone(),
+                certificates: vec![certificate.clone()],
+                key: None,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// 取出 `ContentInfo { data, [0] EXPLICIT OCTET STRING }` 的内容
+fn content_info_data<'a>(content_info: &Der<'a>) -> Result<&'a [u8], NdkError> {
+    let content = content_info
+        .children()?
+        .get(1)
+        .copied()
+        .ok_or_else(|| NdkError::InvalidDer("ContentInfo has no content".into()))?;
+    Ok(Der::parse_tagged(content.content, TAG_OCTET_STRING)?.content)
+}
+
+/// `AlgorithmIdentifier` 中的算法 OID
+fn algorithm_oid(algorithm: &Der<'_>) -> Result<String, NdkError> {
+    algorithm
+        .children()?
+        .first()
+        .ok_or_else(|| NdkError::InvalidDer("empty AlgorithmIdentifier".into()))?
+        .oid()
+}
+
+fn verify_pkcs12_mac(mac_data: &Der<'_>, content: &[u8], password: &str) -> Result<(), NdkError> {
+    let fields = mac_data.children()?;
+    let digest_info = fields
+        .first()
+        .ok_or_else(|| NdkError::InvalidDer("empty MacData".into()))?
+        .children()?;
+    let (algorithm, expected) = match digest_info.as_slice() {
+        [algorithm, digest] => (algorithm_oid(algorithm)?, digest.content),
+        _ => return Err(NdkError::InvalidDer("malformed DigestInfo".into())),
+    };
+    let salt = fields
+        .get(1)
+        .ok_or_else(|| NdkError::InvalidDer("MacData has no salt".into()))?
+        .content;
+    let iterations = fields.get(2).map(Der::u64).transpose()?.unwrap_or(1);
+    let password = bmp_password(password);
+    let valid = match algorithm.as_str() {
+        der::OID_SHA1 => {
+            let key = pkcs12_kdf::<Sha1>(&password, salt, 3, iterations, 20);
+            let mut mac = Hmac::<Sha1>::new_from_slice(&key).unwrap();
+            mac.update(content);
+            mac.verify_slice(expected).is_ok()
+        }
+        der::OID_SHA256 => {
+            let key = pkcs12_kdf::<Sha256>(&password, salt, 3, iterations, 32);
+            let mut mac = Hmac::<Sha256>::new_from_slice(&key).unwrap();
+            mac.update(content);
+            mac.verify_slice(expected).is_ok()
+        }
+        other => return Err(NdkError::UnsupportedAlgorithm(other.to_string())),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(NdkError::IncorrectPassword)
+    }
+}
+
+struct SafeBag {
+    value: BagValue,
+    friendly_name: Option<String>,
+    local_key_id: Option<Vec<u8>>,
+}
+
+enum BagValue {
+    Key(ProtectedKey),
+    Certificate(Certificate),
+    Other,
+}
+
+impl SafeBag {
+    fn parse(bag: &Der<'_>) -> Result<Self, NdkError> {
+        let fields = bag.children()?;
+        let (bag_id, value) = match fields.as_slice() {
+            [bag_id, value, ..] => (bag_id.oid()?, value.children()?),
+            _ => return Err(NdkError::InvalidDer("truncated SafeBag".into())),
+        };
+        let value = value
+            .first()
+            .ok_or_else(|| NdkError::InvalidDer("empty SafeBag".into()))?;
+        let value = match bag_id.as_str() {
+            OID_PKCS8_SHROUDED_KEY_BAG => {
+                BagValue::Key(ProtectedKey::Encrypted(value.raw.to_vec()))
+            }
+            OID_KEY_BAG => BagValue::Key(ProtectedKey::Plain(value.raw.to_vec())),
+            OID_CERT_BAG => {
+                let cert_bag = value.children()?;
+                match cert_bag.as_slice() {
+                    [cert_id, cert] if cert_id.oid()? == OID_X509_CERTIFICATE => {
+                        let cert = cert
+                            .children()?
+                            .first()
+                            .ok_or_else(|| NdkError::InvalidDer("empty CertBag".into()))?
+                            .expect(TAG_OCTET_STRING)?
+                            .content
+                            .to_vec();
+                        BagValue::Certificate(Certificate::from_der(cert)?)
+                    }
+                    _ => BagValue::Other,
+                }
+            }
+            _ => BagValue::Other,
+        };
+
+        let mut friendly_name = None;
+        let mut local_key_id = None;
+        if let Some(attributes) = fields.get(2) {
+            for attribute in attributes.children()? {
+                let attribute = attribute.children()?;
+                let (oid, values) = match attribute.as_slice() {
+                    [oid, values] => (oid.oid()?, values.children()?),
+                    _ => continue,
+                };
+                let Some(value) = values.first() else {
+                    continue;
+                };
+                match oid.as_str() {
+                    OID_FRIENDLY_NAME => {
+                        let chars = value
+                            .content
+                            .chunks(2)
+                            .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+                            .collect::<Vec<_>>();
+                        friendly_name = Some(String::from_utf16_lossy(&chars));
+                    }
+                    OID_LOCAL_KEY_ID => local_key_id = Some(value.content.to_vec()),
+                    _ => {}
+                }
+            }
+        }
+        Ok(Self {
+            value,
+            friendly_name,
+            local_key_id,
+        })
+    }
+}
+
+/// 解密 PKCS#8 `EncryptedPrivateKeyInfo`，返回 `PrivateKeyInfo`
+fn decrypt_private_key_info(der: &[u8], password: &str) -> Result<Vec<u8>, NdkError> {
+    let fields = Der::parse_tagged(der, TAG_SEQUENCE)?.children()?;
+    let (algorithm, encrypted) = match fields.as_slice() {
+        [algorithm, encrypted] => (algorithm, encrypted.expect(TAG_OCTET_STRING)?.content),
+        _ => {
+            return Err(NdkError::InvalidDer(
+                "malformed EncryptedPrivateKeyInfo".into(),
+            ));
+        }
+    };
+    decrypt(algorithm, encrypted, password)
+}
+
+/// 按 `AlgorithmIdentifier` 指定的基于密码的加密算法解密
+fn decrypt(algorithm: &Der<'_>, data: &[u8], password: &str) -> Result<Vec<u8>, NdkError> {
+    let fields = algorithm.children()?;
+    let oid = fields
+        .first()
+        .ok_or_else(|| NdkError::InvalidDer("empty AlgorithmIdentifier".into()))?
+        .oid()?;
+    let params = fields.get(1);
+    let pbe_params = || -> Result<(&[u8], u64), NdkError> {
+        let params = params
+            .ok_or_else(|| NdkError::InvalidDer("missing PBE parameters".into()))?
+            .children()?;
+        match params.as_slice() {
+            [salt, iterations] => Ok((salt.content, iterations.u64()?)),
+            _ => Err(NdkError::InvalidDer("malformed PBE parameters".into())),
+        }
+    };
+    match oid.as_str() {
+        OID_JKS_KEY_PROTECTOR => jks_unprotect(data, password),
+        OID_PBES2 => {
+            let params = params
+                .ok_or_else(|| NdkError::InvalidDer("missing PBES2 parameters".into()))?
+                .children()?;
+            let (kdf, scheme) = match params.as_slice() {
+                [kdf, scheme] => (kdf.children()?, scheme.children()?),
+                _ => return Err(NdkError::InvalidDer("malformed PBES2 parameters".into())),
+            };
+            if kdf.first().map(Der::oid).transpose()?.as_deref() != Some(OID_PBKDF2) {
+                return Err(NdkError::UnsupportedAlgorithm(
+                    "PBES2 key derivation other than PBKDF2".into(),
+                ));
+            }
+            let kdf_params = kdf
+                .get(1)
+                .ok_or_else(|| NdkError::InvalidDer("missing PBKDF2 parameters".into()))?
+                .children()?;
+            let salt = kdf_params
+                .first()
+                .ok_or_else(|| NdkError::InvalidDer("missing PBKDF2 salt".into()))?
+                .content;
+            let iterations = kdf_params
+                .get(1)
+                .ok_or_else(|| NdkError::InvalidDer("missing PBKDF2 iterations".into()))?
+                .u64()? as u32;
+            let prf = kdf_params
+                .iter()
+                .skip(2)
+                .find(|param| param.tag == TAG_SEQUENCE)
+                .map(algorithm_oid)
+                .transpose()?
+                .unwrap_or_else(|| OID_HMAC_SHA1.to_string());
+            let scheme_oid = scheme
+                .first()
+                .ok_or_else(|| NdkError::InvalidDer("empty encryption scheme".into()))?
+                .oid()?;
+            let iv = scheme
+                .get(1)
+                .ok_or_else(|| NdkError::InvalidDer("missing IV".into()))?
+                .content;
+            let key_len = match scheme_oid.as_str() {
+                OID_AES128_CBC => 16,
+                OID_AES192_CBC | OID_DES_EDE3_CBC => 24,
+                OID_AES256_CBC => 32,
+                other => return Err(NdkError::UnsupportedAlgorithm(other.to_string())),
+            };
+            let mut key = vec![0; key_len];
+            match prf.as_str() {
+                OID_HMAC_SHA1 => {
+                    pbkdf2::pbkdf2_hmac::<Sha1>(password.as_bytes(), salt, iterations, &mut key)
+                }
+                OID_HMAC_SHA256 => {
+                    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut key)
+                }
+                other => return Err(NdkError::UnsupportedAlgorithm(other.to_string())),
+            }
+            match scheme_oid.as_str() {
+                OID_AES128_CBC => cbc_decrypt::<aes::Aes128>(&key, iv, data),
+                OID_AES192_CBC => cbc_decrypt::<aes::Aes192>(&key, iv, data),
+                OID_AES256_CBC => cbc_decrypt::<aes::Aes256>(&key, iv, data),
+                _ => cbc_decrypt::<des::TdesEde3>(&key, iv, data),
+            }
+        }
+        OID_PBE_SHA1_3DES => {
+            let (salt, iterations) = pbe_params()?;
+            let password = bmp_password(password);
+            let key = pkcs12_kdf::<Sha1>(&password, salt, 1, iterations, 24);
+            let iv = pkcs12_kdf::<Sha1>(&password, salt, 2, iterations, 8);
+            cbc_decrypt::<des::TdesEde3>(&key, &iv, data)
+        }
+        OID_PBE_SHA1_RC2_40 | OID_PBE_SHA1_RC2_128 => {
+            let (salt, iterations) = pbe_params()?;
+            let bits = if oid == OID_PBE_SHA1_RC2_40 { 40 } else { 128 };
+            let password = bmp_password(password);
+            let key = pkcs12_kdf::<Sha1>(&password, salt, 1, iterations, bits / 8);
+            let iv = pkcs12_kdf::<Sha1>(&password, salt, 2, iterations, 8);
+            let cipher = rc2::Rc2::new_with_eff_key_len(&key, bits);
+            cbc::Decryptor::<rc2::Rc2>::inner_iv_slice_init(cipher, &iv)
+                .map_err(|_| NdkError::InvalidDer("invalid IV length".into()))?
+                .decrypt_padded_vec_mut::<Pkcs7>(data)
+                .map_err(|_| NdkError::IncorrectPassword)
+        }
+        other => Err(NdkError::UnsupportedAlgorithm(other.to_string())),
+    }
+}
+
+/// 以 PBES2（PBKDF2-HMAC-SHA256、AES-256-CBC）加密 `PrivateKeyInfo`，返回 `EncryptedPrivateKeyInfo`
+fn encrypt_private_key_info(private_key_info: &[u8], password: &str) -> Vec<u8> {
+    let salt = random_bytes::<16>();
+    let iv = random_bytes::<16>();
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, PBE_ITERATIONS, &mut key);
+    let encrypted = cbc::Encryptor::<aes::Aes256>::new(&key.into(), &iv.into())
+        .encrypt_padded_vec_mut::<Pkcs7>(private_key_info);
+    let kdf = der::sequence(&[
+        &der::oid(OID_PBKDF2),
+        &der::sequence(&[
+            &der::octet_string(&salt),
+            &der::integer(PBE_ITERATIONS as u64),
+            &der::algorithm(OID_HMAC_SHA256),
+        ]),
+    ]);
+    let scheme = der::sequence(&[&der::oid(OID_AES256_CBC), &der::octet_string(&iv)]);
+    der::sequence(&[
+        &der::sequence(&[&der::oid(OID_PBES2), &der::sequence(&[&kdf, &scheme])]),
+        &der::octet_string(&encrypted),
+    ])
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// 证书有效期中的时间：2050 年之前为 `UTCTime`，之后为 `GeneralizedTime`
+fn der_time(secs: u64) -> Vec<u8> {
+    // 由 Unix 纪元以来的天数计算公历日期，参见 <https://howardhinnant.github.io/date_algorithms.html>
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    let seconds = secs % 86400;
+    let time = format!(
+        "{month:02}{day:02}{:02}{:02}{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if year < 2050 {
+        der::tlv(TAG_UTC_TIME, format!("{:02}{time}", year % 100).as_bytes())
+    } else {
+        der::tlv(TAG_GENERALIZED_TIME, format!("{year:04}{time}").as_bytes())
+    }
+}
+
+fn cbc_decrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>, NdkError>
+where
+    cbc::Decryptor<C>: KeyIvInit + BlockDecryptMut,
+    C: cbc::cipher::BlockDecryptMut + cbc::cipher::BlockCipher,
+{
+    cbc::Decryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| NdkError::InvalidDer("invalid key or IV length".into()))?
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| NdkError::IncorrectPassword)
+}
+
+/// PKCS#12 使用以 `0x0000` 结尾的 UTF-16BE 密码
+fn bmp_password(password: &str) -> Vec<u8> {
+    let mut bytes = utf16_be(password);
+    bytes.extend_from_slice(&[0, 0]);
+    bytes
+}
+
+/// RFC 7292 附录 B.2 定义的 PKCS#12 密钥派生函数
+fn pkcs12_kdf<D: Digest>(
+    password: &[u8],
+    salt: &[u8],
+    id: u8,
+    iterations: u64,
+    len: usize,
+) -> Vec<u8> {
+    const V: usize = 64;
+    let fill = |input: &[u8]| -> Vec<u8> {
+        if input.is_empty() {
+            return Vec::new();
+        }
+        let len = V * input.len().div_ceil(V);
+        input.iter().copied().cycle().take(len).collect()
+    };
+    let mut i = fill(salt);
+    i.extend(fill(password));
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        let mut a = D::new().chain_update([id; V]).chain_update(&i).finalize();
+        for _ in 1..iterations {
+            a = D::digest(&a);
+        }
+        out.extend_from_slice(&a);
+        let b = a.iter().copied().cycle().take(V).collect::<Vec<_>>();
+        for block in i.chunks_mut(V) {
+            // block = (block + b + 1) mod 2^(8V)
+            let mut carry = 1u16;
+            for (x, y) in block.iter_mut().zip(&b).rev() {
+                let sum = *x as u16 + *y as u16 + carry;
+                *x = sum as u8;
+                carry = sum >> 8;
+            }
+        }
+    }
+    out.truncate(len);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{KeyGenOptions, Keystore, KeystoreType, algorithm_oid, der_time, format_time},
+        crate::{
+            der::{self, Der, TAG_SEQUENCE},
+            error::NdkError,
+            testing::{TempDir, testdata},
+        },
+    };
+
+    #[test]
+    fn generate_pkcs12() {
+        let dir = TempDir::new("ndk-build2-keystore");
+        let options = KeyGenOptions {
+            path: dir.join("debug.keystore"),
+            keystore_type: KeystoreType::Pkcs12,
+            keystore_password: "android".into(),
+            alias: "androiddebugkey".into(),
+            key_password: None,
+            dname: "CN=Android Debug,O=Android,C=US".into(),
+            key_algorithm: "RSA".into(),
+            key_size: Some(1024),
+            validity: 10000,
+        };
+        options.generate().unwrap();
+
+        let keystore = Keystore::load(&options.path, "android").unwrap();
+        assert_eq!(keystore.keystore_type, KeystoreType::Pkcs12);
+        assert_eq!(keystore.entries.len(), 1);
+        assert_eq!(keystore.entries[0].alias, "androiddebugkey");
+        let (_, certificates) = keystore.private_key(None, "android").unwrap();
+        let certificate = &certificates[0];
+        assert_eq!(
+            certificate.subject_name(),
+            "CN=Android Debug, O=Android, C=US"
+        );
+        assert_eq!(certificate.issuer_name(), certificate.subject_name());
+        assert_eq!(certificate.key_algorithm().unwrap(), "RSA (1024 bits)");
+        assert!(matches!(
+            Keystore::load(&options.path, "wrong"),
+            Err(NdkError::IncorrectKeystorePassword(_))
+        ));
+    }
+
+    #[test]
+    fn certificate_times() {
+        let time = |secs| format_time(&Der::parse(&der_time(secs)).unwrap().0).unwrap();
+        assert_eq!(time(0), "1970-01-01 00:00:00");
+        assert_eq!(time(951_782_400), "2000-02-29 00:00:00");
+        assert_eq!(time(2_524_607_999), "2049-12-31 23:59:59");
+        assert_eq!(time(2_524_608_000), "2050-01-01 00:00:00");
+    }
+
+    #[test]
+    fn empty_algorithm_identifier() {
+        let empty = der::sequence(&[]);
+        assert!(matches!(
+            algorithm_oid(&Der::parse_tagged(&empty, TAG_SEQUENCE).unwrap()),
+            Err(NdkError::InvalidDer(_))
+        ));
+    }
+
+    #[test]
+    fn jks() {
+        // keytool -genkeypair -storetype JKS -storepass storepass -keypass keypass -alias release
+        let path = testdata("release.jks");
+        let keystore = Keystore::load(&path, "storepass").unwrap();
+        assert_eq!(keystore.keystore_type, KeystoreType::Jks);
+        assert_eq!(keystore.entries.len(), 1);
+        let entry = &keystore.entries[0];
+        assert_eq!(entry.alias, "release");
+        assert_eq!(
+            entry.certificates[0].sha256_fingerprint(),
+            "CD:65:6C:72:92:80:FB:F0:66:1F:AD:02:5F:A5:F0:B8:8C:1A:78:6A:1E:F6:D3:35:31:AC:FE:78:07:6F:57:15"
+        );
+        assert_eq!(
+            entry.certificates[0].subject_name(),
+            "CN=Test Release, O=Example, C=US"
+        );
+
+        let (_, certificates) = keystore.private_key(Some("RELEASE"), "keypass").unwrap();
+        assert_eq!(certificates, entry.certificates);
+        assert!(matches!(
+            keystore.private_key(None, "storepass"),
+            Err(NdkError::IncorrectKeyPassword(alias)) if alias == "release"
+        ));
+        assert!(matches!(
+            keystore.private_key(Some("other"), "keypass"),
+            Err(NdkError::KeyNotFound { .. })
+        ));
+        assert!(matches!(
+            Keystore::load(&path, "wrong"),
+            Err(NdkError::IncorrectKeystorePassword(p)) if p == path
+        ));
+    }
+
+    #[test]
+    fn pkcs12() {
+        // keytool -genkeypair -storetype PKCS12 -storepass android -alias androiddebugkey
+        let path = testdata("debug.p12");
+        let keystore = Keystore::load(&path, "android").unwrap();
+        assert_eq!(keystore.keystore_type, KeystoreType::Pkcs12);
+        assert_eq!(keystore.entries.len(), 1);
+        let entry = &keystore.entries[0];
+        assert_eq!(entry.alias, "androiddebugkey");
+        assert_eq!(
+            entry.certificates[0].sha256_fingerprint(),
+            "D8:D8:EE:C7:6C:8F:A3:CA:97:43:FA:6C:9F:64:1A:BD:86:6B:1F:62:1F:C3:31:A3:EC:D0:13:18:D8:3F:4F:04"
+        );
+
+        let (_, certificates) = keystore.private_key(None, "android").unwrap();
+        assert_eq!(certificates, entry.certificates);
+        assert!(matches!(
+            keystore.private_key(None, "wrong"),
+            Err(NdkError::IncorrectKeyPassword(alias)) if alias == "androiddebugkey"
+        ));
+        assert!(matches!(
+            Keystore::load(&path, "wrong"),
+            Err(NdkError::IncorrectKeystorePassword(p)) if p == path
+        ));
+    }
+}
@@ -28,11 +28,31 @@ macro_rules! cmd {
     };
 }
 
+pub mod aar;
 pub mod apk;
+pub mod arsc;
+pub mod axml;
 pub mod cargo;
+pub mod classfile;
+mod der;
+pub mod desugar;
 pub mod dylibs;
 pub mod error;
+pub mod icon;
+pub mod inspect;
+pub mod jni;
+pub mod keystore;
 pub mod manifest;
+pub mod manifest_merger;
+pub mod maven;
 pub mod ndk;
 pub mod readelf;
+pub mod resource_ids;
+pub mod signer;
+pub mod size;
+pub mod strings;
 pub mod target;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod verify;
+pub mod zip;
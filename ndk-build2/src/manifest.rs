@@ -74,6 +74,27 @@ impl AndroidManifest {
         file.write_all(buf.as_bytes())?;
         Ok(())
     }
+
+    /// 清单中声明的组件类的完整名称，相对名称（`.MainActivity` 或 `MainActivity`）以 `package` 补全
+    pub fn class_names(&self) -> Vec<String> {
+        let application = &self.application;
+        application
+            .activities
+            .iter()
+            .map(|activity| &activity.name)
+            .chain(application.services.iter().map(|service| &service.name))
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                if name.starts_with('.') {
+                    format!("{}{name}", self.package)
+                } else if !name.contains('.') {
+                    format!("{}.{name}", self.package)
+                } else {
+                    name.clone()
+                }
+            })
+            .collect()
+    }
 }
 
 /// Android [service 元素](https://developer.android.com/guide/topics/manifest/service-element).
@@ -147,6 +168,12 @@ pub struct Application {
     #[serde(rename(serialize = "@android:label"))]
     #[serde(default)]
     pub label: String,
+    /// 按应用设置语言（Android 13 及以上）支持的语言列表，例如 `@xml/locales_config`
+    #[serde(
+        rename(serialize = "@android:localeConfig"),
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub locale_config: Option<String>,
     #[serde(
         rename(serialize = "@android:extractNativeLibs"),
         skip_serializing_if = "Option::is_none"
@@ -0,0 +1,660 @@
+//! 清单合并：以生成的清单为基础，依次合并 `manifest_override` 与 Android 库的清单。
+//!
+//! 元素按元素名与 `android:name` 匹配（`application` 等单例元素只按元素名，没有名称的元素须完全相同）。
+//! `manifest_override` 的优先级高于生成的清单，其中的属性覆盖生成的属性；库的优先级最低，
+//! 库与应用中同一属性的值不同时报告冲突，除非应用的元素通过 `tools:replace` 或 `tools:remove` 指定了处理方式。
+//!
+//! 应用的元素支持以下 `tools:node` 标记：
+//!
+//! - `merge`：默认，合并属性与子元素
+//! - `merge-only-attributes`：只合并属性，不合并子元素
+//! - `replace`：忽略优先级更低的清单中匹配的元素
+//! - `strict`：匹配的元素须完全相同，否则报告冲突
+//! - `remove`：删除优先级更低的清单中匹配的元素，该元素本身也不写入合并后的清单
+//! - `removeAll`：删除优先级更低的清单中父元素下所有同名的元素
+
+use {
+    crate::{
+        axml::{ANDROID_NAMESPACE, Element, Value},
+        error::NdkError,
+    },
+    std::{
+        fs::{read_to_string, write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// `tools:` 命名空间，其中的属性只用于合并，不写入合并后的清单
+pub const TOOLS_NAMESPACE: &str = "http://schemas.android.com/tools";
+
+/// 每个父元素中只能出现一次的元素，按元素名匹配
+const SINGLETONS: &[&str] = &["application", "queries", "supports-screens", "uses-sdk"];
+
+/// 声明类名的元素，其中以 `.` 开头或不含 `.` 的类名相对于清单的包名
+const COMPONENTS: &[&str] = &[
+    "application",
+    "activity",
+    "activity-alias",
+    "service",
+    "receiver",
+    "provider",
+];
+
+/// 组件元素中值为类名的属性
+const CLASS_ATTRIBUTES: &[&str] = &[
+    "name",
+    "targetActivity",
+    "backupAgent",
+    "appComponentFactory",
+];
+
+/// 不从库中合并属性的元素，例如库的权限声明中的 `android:maxSdkVersion` 不应限制应用
+const NO_ATTRIBUTE_MERGE: &[&str] = &["uses-permission", "uses-permission-sdk-23"];
+
+/// 以 `path` 处生成的清单为基础，合并 `manifest_override` 与 `libraries` 中的库清单后写回 `path`
+pub fn merge_manifests(
+    path: &Path,
+    manifest_override: Option<&Path>,
+    libraries: &[PathBuf],
+) -> Result<(), NdkError> {
+    let mut merger = ManifestMerger::new(path)?;
+    if let Some(manifest_override) = manifest_override {
+        merger.merge_override(manifest_override)?;
+    }
+    for library in libraries {
+        merger.merge_library(library)?;
+    }
+    let merged = merger.finish();
+    write(path, merged.to_string()).map_err(|e| NdkError::IoPathError(path.into(), e))
+}
+
+/// 清单合并器，见[模块文档](self)
+#[derive(Debug)]
+pub struct ManifestMerger {
+    merged: Element,
+    /// 构成应用清单的文件，后面的优先，用于定位冲突的属性
+    sources: Vec<(PathBuf, Element)>,
+}
+
+/// 元素的匹配键：元素名与 `android:name`（或 `android:glEsVersion`）
+type Key = (String, Option<String>);
+
+impl ManifestMerger {
+    /// 以 `base` 处的清单（通常为生成的清单）为基础
+    pub fn new(base: &Path) -> Result<Self, NdkError> {
+        let merged = parse_file(base)?;
+        Ok(Self {
+            sources: vec![(base.to_path_buf(), merged.clone())],
+            merged,
+        })
+    }
+
+    /// 合并优先级更高的应用清单：其中的属性覆盖已有的属性，`tools:node` 标记作用于已有的元素
+    pub fn merge_override(&mut self, path: &Path) -> Result<(), NdkError> {
+        let manifest = parse_file(path)?;
+        let mut errors = Vec::new();
+        import_namespaces(&mut self.merged, &manifest);
+        overlay_element(&mut self.merged, &manifest, path, &mut errors);
+        self.sources.push((path.to_path_buf(), manifest));
+        check(errors)
+    }
+
+    /// 合并优先级最低的库清单。
+    ///
+    /// 库中的相对类名按库的包名展开，`${applicationId}` 替换为应用的包名。
+    /// 库要求的 `minSdkVersion` 高于应用时只打印警告，库的 `uses-sdk` 不会合并。
+    pub fn merge_library(&mut self, path: &Path) -> Result<(), NdkError> {
+        let mut library = parse_file(path)?;
+        let library_package = package(&library);
+        expand_class_names(&mut library, &library_package);
+        replace_placeholders(&mut library, &package(&self.merged));
+        import_namespaces(&mut self.merged, &library);
+
+        let mut errors = Vec::new();
+        let mut chain = vec![key(&self.merged)];
+        for child in &library.children {
+            if child.name == "uses-sdk" {
+                let min_sdk = |manifest: &Element| {
+                    manifest
+                        .children_named("uses-sdk")
+                        .find_map(|uses_sdk| android_attribute(uses_sdk, "minSdkVersion"))
+                        .and_then(|version| version.parse::<u32>().ok())
+                        .unwrap_or(1)
+                };
+                let (required, min_sdk) = (min_sdk(&library), min_sdk(&self.merged));
+                if required > min_sdk {
+                    eprintln!(
+                        "Warning: `{library_package}` requires minSdkVersion {required}, \
+                        but the app's is {min_sdk}"
+                    );
+                }
+                continue;
+            }
+            let context = Context {
+                sources: &self.sources,
+                library: path,
+            };
+            context.merge_child(&mut self.merged, child, &mut chain, &mut errors);
+        }
+        check(errors)
+    }
+
+    /// 删除 `tools:node="remove"` 与 `removeAll` 标记的元素以及所有 `tools:` 属性，返回合并后的清单
+    pub fn finish(mut self) -> Element {
+        strip_tools(&mut self.merged);
+        self.merged
+    }
+}
+
+/// 合并库时用于定位冲突的上下文
+struct Context<'a> {
+    sources: &'a [(PathBuf, Element)],
+    library: &'a Path,
+}
+
+impl Context<'_> {
+    fn merge_child(
+        &self,
+        parent: &mut Element,
+        child: &Element,
+        chain: &mut Vec<Key>,
+        errors: &mut Vec<String>,
+    ) {
+        // 库中的标记只作用于优先级更低的库，被删除的元素不加入应用
+        if matches!(tools_attribute(child, "node"), Some("remove" | "removeAll")) {
+            return;
+        }
+        if parent.children.iter().any(|existing| {
+            existing.name == child.name && tools_attribute(existing, "node") == Some("removeAll")
+        }) {
+            return;
+        }
+        let Some(existing) = parent
+            .children
+            .iter_mut()
+            .find(|existing| same_element(existing, child))
+        else {
+            let mut child = child.clone();
+            strip_tools(&mut child);
+            parent.children.push(child);
+            return;
+        };
+
+        chain.push(key(existing));
+        match tools_attribute(existing, "node") {
+            Some("remove" | "replace") => {}
+            Some("strict") => {
+                if !equivalent(existing, child) {
+                    errors.push(format!(
+                        "Element <{}> at {} is different from the one at {}, \
+                        but `tools:node=\"strict\"` requires them to be identical",
+                        child.name,
+                        self.location(chain, None),
+                        location(self.library, child),
+                    ));
+                }
+            }
+            Some("merge-only-attributes") => self.merge_attributes(existing, child, chain, errors),
+            None | Some("merge") => {
+                self.merge_attributes(existing, child, chain, errors);
+                for grandchild in &child.children {
+                    self.merge_child(existing, grandchild, chain, errors);
+                }
+            }
+            Some(node) => errors.push(format!(
+                "Invalid `tools:node=\"{node}\"` at {}",
+                self.location(chain, None)
+            )),
+        }
+        chain.pop();
+    }
+
+    /// 将库元素 `source` 的属性合并到应用的元素 `target` 中
+    fn merge_attributes(
+        &self,
+        target: &mut Element,
+        source: &Element,
+        chain: &[Key],
+        errors: &mut Vec<String>,
+    ) {
+        if NO_ATTRIBUTE_MERGE.contains(&source.name.as_str()) {
+            return;
+        }
+        let replace = attribute_list(target, "replace");
+        let remove = attribute_list(target, "remove");
+        for attr in &source.attributes {
+            if attr.namespace.as_deref() == Some(TOOLS_NAMESPACE) {
+                continue;
+            }
+            let name = prefixed_name(&attr.namespace, &attr.name);
+            if replace.contains(&name) || remove.contains(&name) {
+                continue;
+            }
+            let Some(existing) = target
+                .attributes
+                .iter_mut()
+                .find(|other| other.name == attr.name && other.namespace == attr.namespace)
+            else {
+                target.attributes.push(attr.clone());
+                continue;
+            };
+            if existing.value == attr.value {
+                continue;
+            }
+            // 任何一方要求该特性时，应用都要求该特性
+            if source.name == "uses-feature" && name == "android:required" {
+                if attr.value.to_string() == "true" {
+                    existing.value = attr.value.clone();
+                }
+                continue;
+            }
+            errors.push(format!(
+                "Attribute {}@{name} value=({}) from {} is also present at {} value=({}). \
+                Add `tools:replace=\"{name}\"` to <{}> at {} to override",
+                source.name,
+                attr.value,
+                location(self.library, source),
+                self.location(chain, Some(&name)),
+                existing.value,
+                target.name,
+                self.location(chain, None),
+            ));
+        }
+    }
+
+    /// 应用中由 `chain` 确定的元素（或其属性 `attribute`）所在的文件与行号，优先使用优先级更高的文件
+    fn location(&self, chain: &[Key], attribute: Option<&str>) -> String {
+        let mut fallback = None;
+        for (path, root) in self.sources.iter().rev() {
+            let mut element = root;
+            let mut found = key(root) == chain[0];
+            for key in &chain[1..] {
+                if !found {
+                    break;
+                }
+                match element
+                    .children
+                    .iter()
+                    .find(|child| self::key(child) == *key)
+                {
+                    Some(child) => element = child,
+                    None => found = false,
+                }
+            }
+            if !found {
+                continue;
+            }
+            let has_attribute = attribute.is_none_or(|attribute| {
+                element
+                    .attributes
+                    .iter()
+                    .any(|attr| prefixed_name(&attr.namespace, &attr.name) == attribute)
+            });
+            if has_attribute {
+                return location(path, element);
+            }
+            fallback.get_or_insert_with(|| location(path, element));
+        }
+        fallback.unwrap_or_else(|| "the generated manifest".into())
+    }
+}
+
+/// 将优先级更高的应用清单元素 `source` 覆盖到 `target` 上
+fn overlay_element(target: &mut Element, source: &Element, path: &Path, errors: &mut Vec<String>) {
+    for attr in &source.attributes {
+        match target
+            .attributes
+            .iter_mut()
+            .find(|other| other.name == attr.name && other.namespace == attr.namespace)
+        {
+            Some(existing) => existing.value = attr.value.clone(),
+            None => target.attributes.push(attr.clone()),
+        }
+    }
+    for attr in attribute_list(source, "remove") {
+        target
+            .attributes
+            .retain(|other| prefixed_name(&other.namespace, &other.name) != attr);
+    }
+    for child in &source.children {
+        match tools_attribute(child, "node") {
+            Some("removeAll") => target.children.retain(|other| other.name != child.name),
+            Some("remove" | "replace") => {
+                target.children.retain(|other| !same_element(other, child));
+            }
+            None | Some("merge" | "merge-only-attributes" | "strict") => {}
+            Some(node) => {
+                errors.push(format!(
+                    "Invalid `tools:node=\"{node}\"` at {}",
+                    location(path, child)
+                ));
+                continue;
+            }
+        }
+        match target
+            .children
+            .iter_mut()
+            .find(|existing| same_element(existing, child))
+        {
+            Some(existing) => overlay_element(existing, child, path, errors),
+            None => target.children.push(child.clone()),
+        }
+    }
+}
+
+/// 清单中声明的组件类及其他由系统实例化的类的完整类名
+pub fn component_names(manifest: &Element) -> Vec<String> {
+    let mut names = Vec::new();
+    collect_component_names(manifest, &mut names);
+    names
+}
+
+fn collect_component_names(element: &Element, names: &mut Vec<String>) {
+    if COMPONENTS.contains(&element.name.as_str()) {
+        names.extend(
+            CLASS_ATTRIBUTES
+                .iter()
+                .filter(|name| **name != "targetActivity")
+                .filter_map(|name| android_attribute(element, name)),
+        );
+    }
+    for child in &element.children {
+        collect_component_names(child, names);
+    }
+}
+
+fn parse_file(path: &Path) -> Result<Element, NdkError> {
+    let text = read_to_string(path).map_err(|e| NdkError::IoPathError(path.into(), e))?;
+    Element::parse(&text)
+}
+
+fn check(errors: Vec<String>) -> Result<(), NdkError> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(NdkError::ManifestMergeFailed(errors))
+    }
+}
+
+fn location(path: &Path, element: &Element) -> String {
+    format!("{}:{}", path.display(), element.line)
+}
+
+fn package(manifest: &Element) -> String {
+    manifest
+        .attribute("package")
+        .map(|attr| attr.value.to_string())
+        .unwrap_or_default()
+}
+
+fn android_attribute(element: &Element, name: &str) -> Option<String> {
+    element
+        .attributes
+        .iter()
+        .find(|attr| attr.name == name && attr.namespace.as_deref() == Some(ANDROID_NAMESPACE))
+        .map(|attr| attr.value.to_string())
+}
+
+fn tools_attribute<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|attr| attr.name == name && attr.namespace.as_deref() == Some(TOOLS_NAMESPACE))
+        .and_then(|attr| attr.value.as_str())
+}
+
+/// `tools:replace` 或 `tools:remove` 中以逗号分隔的属性名
+fn attribute_list(element: &Element, name: &str) -> Vec<String> {
+    tools_attribute(element, name)
+        .map(|list| {
+            list.split(',')
+                .map(|name| name.trim().to_owned())
+                .filter(|name| !name.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 属性在清单中的写法，`android:` 命名空间的属性带有前缀
+fn prefixed_name(namespace: &Option<String>, name: &str) -> String {
+    match namespace.as_deref() {
+        Some(ANDROID_NAMESPACE) => format!("android:{name}"),
+        _ => name.to_owned(),
+    }
+}
+
+fn key(element: &Element) -> Key {
+    let name = if SINGLETONS.contains(&element.name.as_str()) {
+        None
+    } else {
+        android_attribute(element, "name").or_else(|| android_attribute(element, "glEsVersion"))
+    };
+    (element.name.clone(), name)
+}
+
+/// 两个元素是否声明同一个对象：单例元素只比较元素名，有 `android:name` 的元素比较该属性，
+/// 其余元素（例如 `intent-filter`）须完全相同
+fn same_element(a: &Element, b: &Element) -> bool {
+    if a.name != b.name || a.namespace != b.namespace {
+        return false;
+    }
+    match (key(a), key(b)) {
+        ((_, Some(a)), (_, Some(b))) => a == b,
+        ((_, None), (_, None)) => SINGLETONS.contains(&a.name.as_str()) || equivalent(a, b),
+        _ => false,
+    }
+}
+
+/// 忽略行号、属性顺序与 `tools:` 属性时两个元素是否相同
+fn equivalent(a: &Element, b: &Element) -> bool {
+    let attributes = |element: &Element| {
+        let mut attributes = element
+            .attributes
+            .iter()
+            .filter(|attr| attr.namespace.as_deref() != Some(TOOLS_NAMESPACE))
+            .map(|attr| {
+                (
+                    prefixed_name(&attr.namespace, &attr.name),
+                    attr.value.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        attributes.sort();
+        attributes
+    };
+    a.name == b.name
+        && a.namespace == b.namespace
+        && a.text == b.text
+        && attributes(a) == attributes(b)
+        && a.children.len() == b.children.len()
+        && a.children
+            .iter()
+            .zip(&b.children)
+            .all(|(a, b)| equivalent(a, b))
+}
+
+/// 将 `source` 根元素上声明而 `target` 中没有的命名空间加入 `target`，前缀冲突时改用新的前缀
+fn import_namespaces(target: &mut Element, source: &Element) {
+    for (prefix, uri) in &source.namespaces {
+        if target
+            .namespaces
+            .iter()
+            .any(|(_, declared)| declared == uri)
+        {
+            continue;
+        }
+        let mut prefix = prefix.clone();
+        while target
+            .namespaces
+            .iter()
+            .any(|(declared, _)| *declared == prefix)
+        {
+            prefix.push('_');
+        }
+        target.namespaces.push((prefix, uri.clone()));
+    }
+}
+
+fn expand_class_names(element: &mut Element, package: &str) {
+    if COMPONENTS.contains(&element.name.as_str()) {
+        for attr in &mut element.attributes {
+            if attr.namespace.as_deref() != Some(ANDROID_NAMESPACE)
+                || !CLASS_ATTRIBUTES.contains(&attr.name.as_str())
+            {
+                continue;
+            }
+            if let Value::String(name) = &mut attr.value {
+                if name.starts_with('.') {
+                    *name = format!("{package}{name}");
+                } else if !name.contains('.') {
+                    *name = format!("{package}.{name}");
+                }
+            }
+        }
+    }
+    for child in &mut element.children {
+        expand_class_names(child, package);
+    }
+}
+
+fn replace_placeholders(element: &mut Element, application_id: &str) {
+    for attr in &mut element.attributes {
+        if let Value::String(value) = &mut attr.value
+            && value.contains("${applicationId}")
+        {
+            *value = value.replace("${applicationId}", application_id);
+        }
+    }
+    for child in &mut element.children {
+        replace_placeholders(child, application_id);
+    }
+}
+
+/// 删除被标记删除的元素、`tools:` 属性与 `tools` 命名空间的声明
+fn strip_tools(element: &mut Element) {
+    element
+        .children
+        .retain(|child| !matches!(tools_attribute(child, "node"), Some("remove" | "removeAll")));
+    element
+        .attributes
+        .retain(|attr| attr.namespace.as_deref() != Some(TOOLS_NAMESPACE));
+    element.namespaces.retain(|(_, uri)| uri != TOOLS_NAMESPACE);
+    for child in &mut element.children {
+        strip_tools(child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ManifestMerger, component_names},
+        crate::{error::NdkError, testing::TempDir},
+        std::fs,
+    };
+
+    const GENERATED: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android" package="com.example.app" android:versionCode="7">
+    <uses-sdk android:minSdkVersion="21" />
+    <uses-permission android:name="android.permission.INTERNET" />
+    <application android:label="App">
+        <activity android:name="android.app.NativeActivity" android:exported="true">
+            <meta-data android:name="android.app.lib_name" android:value="app" />
+        </activity>
+    </application>
+</manifest>"#;
+
+    const LIBRARY: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android"
+    xmlns:tools="http://schemas.android.com/tools" package="com.example.lib">
+    <uses-sdk android:minSdkVersion="19" />
+    <uses-permission android:name="android.permission.INTERNET" android:maxSdkVersion="28" />
+    <uses-permission android:name="android.permission.CAMERA" />
+    <uses-permission android:name="android.permission.WAKE_LOCK" tools:node="remove" />
+    <application android:label="Lib" android:appComponentFactory="com.example.lib.Factory"
+        tools:ignore="MissingApplicationIcon">
+        <provider android:name=".Initializer" android:authorities="${applicationId}.init" />
+        <service android:name="Worker" />
+    </application>
+</manifest>"#;
+
+    fn write_files(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new(&format!("ndk-build2-merger-{name}"));
+        for (file, text) in files {
+            fs::write(dir.join(file), text).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn merge_override_and_library() {
+        let dir = write_files(
+            "merge",
+            &[
+                ("generated.xml", GENERATED),
+                (
+                    "override.xml",
+                    r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android"
+    xmlns:tools="http://schemas.android.com/tools">
+    <application android:label="Override" tools:replace="android:label">
+        <service android:name="com.example.lib.Worker" tools:node="remove" />
+        <activity android:name="com.example.app.Settings" />
+    </application>
+</manifest>"#,
+                ),
+                ("library.xml", LIBRARY),
+            ],
+        );
+        let mut merger = ManifestMerger::new(&dir.join("generated.xml")).unwrap();
+        merger.merge_override(&dir.join("override.xml")).unwrap();
+        merger.merge_library(&dir.join("library.xml")).unwrap();
+        let merged = merger.finish();
+
+        // 生成的清单中的属性与元素保留
+        assert_eq!(
+            merged.attribute("versionCode").unwrap().value.to_string(),
+            "7"
+        );
+        assert_eq!(merged.children_named("uses-sdk").count(), 1);
+        let permissions = merged
+            .children_named("uses-permission")
+            .map(|permission| permission.attributes.len())
+            .collect::<Vec<_>>();
+        assert_eq!(permissions, [1, 1]);
+        let application = merged.children_named("application").next().unwrap();
+        assert_eq!(
+            application.attribute("label").unwrap().value.to_string(),
+            "Override"
+        );
+        let provider = application.children_named("provider").next().unwrap();
+        assert_eq!(
+            provider.attribute("authorities").unwrap().value.to_string(),
+            "com.example.app.init"
+        );
+        assert_eq!(
+            component_names(&merged),
+            [
+                "com.example.lib.Factory",
+                "android.app.NativeActivity",
+                "com.example.app.Settings",
+                "com.example.lib.Initializer",
+            ]
+        );
+        assert!(!merged.to_string().contains("tools"));
+    }
+
+    #[test]
+    fn report_conflicts() {
+        let dir = write_files(
+            "conflict",
+            &[("generated.xml", GENERATED), ("library.xml", LIBRARY)],
+        );
+        let mut merger = ManifestMerger::new(&dir.join("generated.xml")).unwrap();
+        let Err(NdkError::ManifestMergeFailed(errors)) =
+            merger.merge_library(&dir.join("library.xml"))
+        else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Attribute application@android:label value=(Lib)"));
+        assert!(errors[0].contains(&format!("{}:7", dir.join("library.xml").display())));
+        assert!(errors[0].contains(&format!("{}:4", dir.join("generated.xml").display())));
+    }
+}
@@ -0,0 +1,800 @@
+//! 按 Maven 仓库布局解析 JVM 依赖：读取 POM（父 POM、属性、`dependencyManagement` 与导入的 BOM），
+//! 传递解析 `compile` 与 `runtime` 范围的依赖，版本冲突时与 Gradle 一样选择最高的版本。
+
+use {
+    crate::{axml::Element, error::NdkError},
+    sha2::{Digest, Sha256},
+    std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashMap, HashSet, VecDeque},
+        fmt::{self, Display},
+        fs::{create_dir_all, read, read_to_string, write},
+        io::{self, Read, Write},
+        net::{TcpStream, ToSocketAddrs},
+        path::PathBuf,
+        rc::Rc,
+        time::Duration,
+    },
+};
+
+/// 声明的依赖，例如 `androidx.core:core:1.13.0`
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dependency {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+}
+
+impl Dependency {
+    /// 解析 `group:artifact:version`
+    pub fn parse(coordinate: &str) -> Result<Self, NdkError> {
+        match coordinate.split(':').collect::<Vec<_>>()[..] {
+            [group, artifact, version]
+                if [group, artifact, version]
+                    .iter()
+                    .all(|part| !part.is_empty()) =>
+            {
+                Ok(Self {
+                    group: group.to_owned(),
+                    artifact: artifact.to_owned(),
+                    version: version.to_owned(),
+                })
+            }
+            _ => Err(NdkError::InvalidMavenCoordinate(coordinate.to_owned())),
+        }
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.group, self.artifact, self.version)
+    }
+}
+
+/// Maven 仓库：本地目录，或者只支持 `http://` 的镜像（例如局域网或本机上的仓库代理）
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Repository {
+    Local(PathBuf),
+    Http(String),
+}
+
+impl Repository {
+    /// 以 `http://` 开头的位置为 HTTP 镜像，其他为本地目录
+    pub fn new(location: &str) -> Result<Self, NdkError> {
+        if location.starts_with("http://") {
+            Ok(Self::Http(location.trim_end_matches('/').to_owned()))
+        } else if location.contains("://") {
+            Err(NdkError::UnsupportedUrl(location.to_owned()))
+        } else {
+            Ok(Self::Local(location.into()))
+        }
+    }
+
+    /// 本地 Maven 仓库 `~/.m2/repository`
+    pub fn maven_local() -> Option<Self> {
+        dirs::home_dir().map(|home| Self::Local(home.join(".m2").join("repository")))
+    }
+}
+
+/// 解析出的依赖及其文件
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedArtifact {
+    pub dependency: Dependency,
+    /// 文件的扩展名：`jar` 或 `aar`
+    pub packaging: String,
+    pub path: PathBuf,
+}
+
+impl ResolvedArtifact {
+    /// 文件内容的 SHA-256，十六进制小写
+    pub fn sha256(&self) -> Result<String, NdkError> {
+        let data = read(&self.path).map_err(|e| NdkError::IoPathError(self.path.clone(), e))?;
+        Ok(Sha256::digest(data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect())
+    }
+}
+
+/// POM 中的依赖，版本已按属性与 `dependencyManagement` 确定
+#[derive(Clone, Debug)]
+struct PomDependency {
+    group: String,
+    artifact: String,
+    version: Option<String>,
+    scope: String,
+    optional: bool,
+    /// 排除的传递依赖，`*` 匹配任意组或名称
+    exclusions: Vec<(String, String)>,
+}
+
+/// 继承了父 POM 之后的 POM
+#[derive(Clone, Debug, Default)]
+struct Pom {
+    packaging: String,
+    properties: HashMap<String, String>,
+    managed: HashMap<(String, String), String>,
+    dependencies: Vec<PomDependency>,
+}
+
+/// 依次在各个仓库中查找文件，从 HTTP 镜像下载的文件按仓库布局保存在 `download_dir` 中
+pub struct Resolver {
+    repositories: Vec<Repository>,
+    download_dir: PathBuf,
+    poms: HashMap<String, Rc<Pom>>,
+}
+
+impl Resolver {
+    pub fn new(repositories: Vec<Repository>, download_dir: PathBuf) -> Self {
+        Self {
+            repositories,
+            download_dir,
+            poms: HashMap::new(),
+        }
+    }
+
+    /// 传递解析 `dependencies`，按广度优先的顺序返回所有依赖（打包类型为 `pom` 的除外）
+    pub fn resolve(
+        &mut self,
+        dependencies: &[Dependency],
+    ) -> Result<Vec<ResolvedArtifact>, NdkError> {
+        let mut selected = BTreeMap::<(String, String), String>::new();
+        for dependency in dependencies {
+            let key = (dependency.group.clone(), dependency.artifact.clone());
+            select(&mut selected, key, &dependency.version);
+        }
+
+        // 已展开的依赖选择了更高的版本时，其旧版本的依赖可能不再需要，从头重新展开
+        let order = 'resolve: loop {
+            let mut visited = HashSet::new();
+            let mut order = Vec::new();
+            let mut queue = dependencies
+                .iter()
+                .map(|dependency| {
+                    let key = (dependency.group.clone(), dependency.artifact.clone());
+                    (key, Vec::<(String, String)>::new())
+                })
+                .collect::<VecDeque<_>>();
+            while let Some((key, exclusions)) = queue.pop_front() {
+                if !visited.insert(key.clone()) {
+                    continue;
+                }
+                let dependency = Dependency {
+                    group: key.0.clone(),
+                    artifact: key.1.clone(),
+                    version: selected[&key].clone(),
+                };
+                let pom = self.pom(&dependency)?;
+                order.push((dependency.clone(), pom.packaging.clone()));
+
+                for child in &pom.dependencies {
+                    let excluded = exclusions.iter().any(|(group, artifact)| {
+                        (group == "*" || *group == child.group)
+                            && (artifact == "*" || *artifact == child.artifact)
+                    });
+                    if child.optional
+                        || !["compile", "runtime"].contains(&&*child.scope)
+                        || excluded
+                    {
+                        continue;
+                    }
+                    let version = child.version.as_deref().ok_or_else(|| {
+                        NdkError::InvalidPom(
+                            dependency.to_string(),
+                            format!("no version for `{}:{}`", child.group, child.artifact),
+                        )
+                    })?;
+                    let child_key = (child.group.clone(), child.artifact.clone());
+                    let upgraded = select(
+                        &mut selected,
+                        child_key.clone(),
+                        &requested_version(version),
+                    );
+                    if upgraded && visited.contains(&child_key) {
+                        continue 'resolve;
+                    }
+                    let mut exclusions = exclusions.clone();
+                    exclusions.extend(child.exclusions.iter().cloned());
+                    queue.push_back((child_key, exclusions));
+                }
+            }
+            break order;
+        };
+
+        let mut artifacts = Vec::new();
+        for (dependency, packaging) in order {
+            let packaging = match &*packaging {
+                "pom" => continue,
+                "aar" => "aar",
+                // `bundle`（OSGi）等打包类型的文件同样是 jar
+                _ => "jar",
+            };
+            artifacts.push(self.fetch(&dependency, packaging)?);
+        }
+        Ok(artifacts)
+    }
+
+    /// 查找或下载 `dependency` 的文件
+    pub fn fetch(
+        &self,
+        dependency: &Dependency,
+        packaging: &str,
+    ) -> Result<ResolvedArtifact, NdkError> {
+        Ok(ResolvedArtifact {
+            dependency: dependency.clone(),
+            packaging: packaging.to_owned(),
+            path: self.file(&artifact_path(dependency, packaging))?,
+        })
+    }
+
+    fn file(&self, path: &str) -> Result<PathBuf, NdkError> {
+        for repository in &self.repositories {
+            if let Repository::Local(dir) = repository
+                && dir.join(path).is_file()
+            {
+                return Ok(dir.join(path));
+            }
+        }
+        let downloaded = self.download_dir.join(path);
+        if downloaded.is_file() {
+            return Ok(downloaded);
+        }
+        for repository in &self.repositories {
+            if let Repository::Http(url) = repository
+                && let Some(data) = http_get(&format!("{url}/{path}"))?
+            {
+                println!("Downloaded {url}/{path}");
+                create_dir_all(downloaded.parent().unwrap())?;
+                write(&downloaded, data)
+                    .map_err(|e| NdkError::IoPathError(downloaded.clone(), e))?;
+                return Ok(downloaded);
+            }
+        }
+        Err(NdkError::MavenArtifactNotFound(path.to_owned()))
+    }
+
+    fn pom(&mut self, dependency: &Dependency) -> Result<Rc<Pom>, NdkError> {
+        let id = dependency.to_string();
+        if let Some(pom) = self.poms.get(&id) {
+            return Ok(pom.clone());
+        }
+        let path = self.file(&artifact_path(dependency, "pom"))?;
+        let text = read_to_string(&path).map_err(|e| NdkError::IoPathError(path, e))?;
+        let invalid = |message: String| NdkError::InvalidPom(id.clone(), message);
+        let root = Element::parse(&text).map_err(|e| invalid(e.to_string()))?;
+
+        let mut pom = match root.children_named("parent").next() {
+            Some(parent) => {
+                let field = |name| {
+                    text_of(parent, name).ok_or_else(|| invalid(format!("parent has no `{name}`")))
+                };
+                let parent = self.pom(&Dependency {
+                    group: field("groupId")?,
+                    artifact: field("artifactId")?,
+                    version: field("version")?,
+                })?;
+                (*parent).clone()
+            }
+            None => Pom::default(),
+        };
+        pom.packaging = text_of(&root, "packaging").unwrap_or_else(|| "jar".into());
+        if let Some(properties) = root.children_named("properties").next() {
+            for property in &properties.children {
+                let value = property.text.as_deref().unwrap_or_default().trim();
+                pom.properties
+                    .insert(property.name.clone(), value.to_owned());
+            }
+        }
+        for (name, value) in [
+            ("groupId", &dependency.group),
+            ("artifactId", &dependency.artifact),
+            ("version", &dependency.version),
+        ] {
+            pom.properties
+                .insert(format!("project.{name}"), value.clone());
+            pom.properties.insert(format!("pom.{name}"), value.clone());
+        }
+
+        // 直接声明的版本优先于导入的 BOM
+        let mut imports = Vec::new();
+        for element in dependency_elements(&root, true) {
+            let managed = pom_dependency(element, &pom.properties)
+                .map_err(|name| invalid(format!("managed dependency has no `{name}`")))?;
+            let Some(version) = managed.version else {
+                continue;
+            };
+            if managed.scope == "import" {
+                imports.push(Dependency {
+                    group: managed.group,
+                    artifact: managed.artifact,
+                    version,
+                });
+            } else {
+                pom.managed
+                    .insert((managed.group, managed.artifact), version);
+            }
+        }
+        for import in imports {
+            let bom = self.pom(&import)?;
+            for (key, version) in &bom.managed {
+                pom.managed
+                    .entry(key.clone())
+                    .or_insert_with(|| version.clone());
+            }
+        }
+
+        for element in dependency_elements(&root, false) {
+            let mut child = pom_dependency(element, &pom.properties)
+                .map_err(|name| invalid(format!("dependency has no `{name}`")))?;
+            if child.version.is_none() {
+                child.version = pom
+                    .managed
+                    .get(&(child.group.clone(), child.artifact.clone()))
+                    .cloned();
+            }
+            pom.dependencies.push(child);
+        }
+
+        let pom = Rc::new(pom);
+        self.poms.insert(id, pom.clone());
+        Ok(pom)
+    }
+}
+
+/// 为组与名称 `key` 选择版本 `version`，取代了已选择的更低版本时返回 `true`
+fn select(
+    selected: &mut BTreeMap<(String, String), String>,
+    key: (String, String),
+    version: &str,
+) -> bool {
+    match selected.get(&key) {
+        Some(current) if compare_versions(current, version) != Ordering::Less => false,
+        current => {
+            let upgraded = current.is_some();
+            selected.insert(key, version.to_owned());
+            upgraded
+        }
+    }
+}
+
+/// 仓库中的路径，例如 `androidx/core/core/1.13.0/core-1.13.0.pom`
+fn artifact_path(dependency: &Dependency, extension: &str) -> String {
+    let Dependency {
+        group,
+        artifact,
+        version,
+    } = dependency;
+    format!(
+        "{}/{artifact}/{version}/{artifact}-{version}.{extension}",
+        group.replace('.', "/")
+    )
+}
+
+/// 版本范围没有仓库元数据无法确定，`[1.0]` 取其中的版本，`[1.0,2.0)` 等范围取下界
+fn requested_version(version: &str) -> String {
+    let Some(range) = version
+        .strip_prefix(['[', '('])
+        .and_then(|range| range.strip_suffix([']', ')']))
+    else {
+        return version.to_owned();
+    };
+    let (lower, upper) = range.split_once(',').unwrap_or((range, range));
+    if lower.trim().is_empty() {
+        upper.trim().to_owned()
+    } else {
+        lower.trim().to_owned()
+    }
+}
+
+fn text_of(element: &Element, name: &str) -> Option<String> {
+    element
+        .children_named(name)
+        .next()
+        .and_then(|child| child.text.as_deref())
+        .map(|text| text.trim().to_owned())
+}
+
+/// `<dependencies>` 或 `<dependencyManagement><dependencies>` 中的 `<dependency>`
+fn dependency_elements(root: &Element, managed: bool) -> impl Iterator<Item = &Element> {
+    let parent = if managed {
+        root.children_named("dependencyManagement").next()
+    } else {
+        Some(root)
+    };
+    parent
+        .and_then(|parent| parent.children_named("dependencies").next())
+        .into_iter()
+        .flat_map(|dependencies| dependencies.children_named("dependency"))
+}
+
+/// 缺少必需的字段时返回其名称
+fn pom_dependency(
+    element: &Element,
+    properties: &HashMap<String, String>,
+) -> Result<PomDependency, &'static str> {
+    let field = |name| text_of(element, name).map(|text| interpolate(&text, properties));
+    let exclusions = element
+        .children_named("exclusions")
+        .flat_map(|exclusions| exclusions.children_named("exclusion"))
+        .map(|exclusion| {
+            let field = |name| text_of(exclusion, name).unwrap_or_else(|| "*".into());
+            (field("groupId"), field("artifactId"))
+        })
+        .collect();
+    Ok(PomDependency {
+        group: field("groupId").ok_or("groupId")?,
+        artifact: field("artifactId").ok_or("artifactId")?,
+        version: field("version"),
+        scope: field("scope").unwrap_or_else(|| "compile".into()),
+        optional: field("optional").as_deref() == Some("true"),
+        exclusions,
+    })
+}
+
+/// 替换 `${name}` 形式的属性引用，属性的值可以引用其他属性，未知的属性保持不变
+fn interpolate(text: &str, properties: &HashMap<String, String>) -> String {
+    let mut text = text.to_owned();
+    for _ in 0..8 {
+        let mut replaced = String::with_capacity(text.len());
+        let mut rest = &*text;
+        while let Some(start) = rest.find("${") {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let name = &rest[start + 2..start + len];
+            replaced.push_str(&rest[..start]);
+            match properties.get(name) {
+                Some(value) => replaced.push_str(value),
+                None => replaced.push_str(&rest[start..=start + len]),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        replaced.push_str(rest);
+        if replaced == text {
+            break;
+        }
+        text = replaced;
+    }
+    text
+}
+
+/// 与 Maven 的 `ComparableVersion` 一样比较版本号：按 `.`、`-` 以及数字与字母的交界分段，
+/// 数字分段按数值比较且大于限定符；限定符的顺序为
+/// `alpha` < `beta` < `milestone` < `rc` < `snapshot` < 正式版（空、`ga`、`final`、`release`）< `sp`，
+/// 其他限定符排在 `sp` 之后并按字母顺序比较。较短的版本号以正式版补齐，因此 `1.0` 等于 `1.0.0`，
+/// `1.0-rc1` 小于 `1.0`
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a, b) = (version_items(a), version_items(b));
+    for index in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(index), b.get(index)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(a), None) => a.cmp_missing(),
+            (None, Some(b)) => b.cmp_missing().reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// 版本号中的一段。派生的顺序使限定符总是小于数字
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum VersionItem {
+    /// 已知限定符的顺序，未知的限定符排在最后并按名称比较
+    Qualifier(u8, String),
+    Number(u64),
+}
+
+impl VersionItem {
+    const RELEASE: Self = Self::Qualifier(5, String::new());
+
+    /// 与较短版本号缺少的分段比较：缺少的分段相当于 `0` 或正式版
+    fn cmp_missing(&self) -> Ordering {
+        match self {
+            Self::Number(number) => number.cmp(&0),
+            qualifier => qualifier.cmp(&Self::RELEASE),
+        }
+    }
+
+    fn qualifier(name: &str, followed_by_number: bool) -> Self {
+        let rank = match name {
+            "alpha" => 0,
+            "a" if followed_by_number => 0,
+            "beta" => 1,
+            "b" if followed_by_number => 1,
+            "milestone" => 2,
+            "m" if followed_by_number => 2,
+            "rc" | "cr" => 3,
+            "snapshot" => 4,
+            "" | "ga" | "final" | "release" => 5,
+            "sp" => 6,
+            _ => 7,
+        };
+        Self::Qualifier(
+            rank,
+            if rank == 7 {
+                name.to_owned()
+            } else {
+                String::new()
+            },
+        )
+    }
+}
+
+fn version_items(version: &str) -> Vec<VersionItem> {
+    let version = version.to_ascii_lowercase();
+    let mut tokens = Vec::new();
+    for part in version.split(['.', '-']) {
+        let mut start = 0;
+        for (index, c) in part.char_indices().skip(1) {
+            let previous = part[..index].chars().next_back().unwrap();
+            if previous.is_ascii_digit() != c.is_ascii_digit() {
+                tokens.push(&part[start..index]);
+                start = index;
+            }
+        }
+        tokens.push(&part[start..]);
+    }
+    let mut items = tokens
+        .iter()
+        .enumerate()
+        .map(|(index, token)| match token.parse::<u64>() {
+            Ok(number) => VersionItem::Number(number),
+            Err(_) => {
+                let followed_by_number = tokens
+                    .get(index + 1)
+                    .is_some_and(|next| next.starts_with(|c: char| c.is_ascii_digit()));
+                VersionItem::qualifier(token, followed_by_number)
+            }
+        })
+        .collect::<Vec<_>>();
+    // 末尾的 `0` 与正式版限定符不影响比较
+    while items
+        .last()
+        .is_some_and(|item| *item == VersionItem::Number(0) || *item == VersionItem::RELEASE)
+    {
+        items.pop();
+    }
+    items
+}
+
+/// 连接与每次读写的超时时间
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 通过 HTTP/1.1 下载 `url`，不存在（404）时返回 `None`，最多跟随 5 次重定向
+fn http_get(url: &str) -> Result<Option<Vec<u8>>, NdkError> {
+    let mut url = url.to_owned();
+    for _ in 0..5 {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| NdkError::UnsupportedUrl(url.clone()))?;
+        let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let address = if host.contains(':') {
+            host.to_owned()
+        } else {
+            format!("{host}:80")
+        };
+        let mut stream =
+            connect(&address).map_err(|e| NdkError::Http(url.clone(), e.to_string()))?;
+        // 停滞的仓库不会让构建一直挂起
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {host}\r\nUser-Agent: cargo-apk2\r\nConnection: close\r\n\r\n",
+            if path.is_empty() { "/" } else { path }
+        )?;
+        let mut response = Vec::new();
+        stream
+            .read_to_end(&mut response)
+            .map_err(|e| NdkError::Http(url.clone(), e.to_string()))?;
+
+        let invalid = || NdkError::Http(url.clone(), "invalid response".into());
+        let header_len = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(invalid)?;
+        let header = String::from_utf8_lossy(&response[..header_len]);
+        let mut lines = header.lines();
+        let status_line = lines.next().unwrap_or_default().to_owned();
+        let status = status_line.split(' ').nth(1).ok_or_else(invalid)?;
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_owned()))
+            .collect::<HashMap<_, _>>();
+        let body = &response[header_len + 4..];
+        match status {
+            "200" => {}
+            "404" | "410" => return Ok(None),
+            "301" | "302" | "303" | "307" | "308" => {
+                let location = headers.get("location").ok_or_else(invalid)?;
+                url = if location.starts_with('/') {
+                    format!("http://{host}{location}")
+                } else {
+                    location.clone()
+                };
+                continue;
+            }
+            _ => return Err(NdkError::Http(url, status_line)),
+        }
+        if headers
+            .get("transfer-encoding")
+            .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"))
+        {
+            return dechunk(body).map(Some).ok_or_else(invalid);
+        }
+        let len = match headers.get("content-length") {
+            Some(len) => len.parse::<usize>()?.min(body.len()),
+            None => body.len(),
+        };
+        return Ok(Some(body[..len].to_vec()));
+    }
+    Err(NdkError::Http(url, "too many redirects".into()))
+}
+
+/// 依次尝试 `address` 解析出的每个地址，每次连接最多等待 [`HTTP_TIMEOUT`]
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, HTTP_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address")))
+}
+
+/// 解码 `Transfer-Encoding: chunked` 的正文
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    loop {
+        let line_len = body.windows(2).position(|window| window == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_len]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_len + 2..];
+        if size == 0 {
+            return Some(data);
+        }
+        data.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Dependency, Repository, Resolver, compare_versions, dechunk, requested_version},
+        crate::testing::TempDir,
+        std::{cmp::Ordering, fs},
+    };
+
+    #[test]
+    fn versions() {
+        assert_eq!(compare_versions("2.0.4", "2.0.10"), Ordering::Less);
+        assert_eq!(compare_versions("2.1.0", "2.0.10"), Ordering::Greater);
+        assert_eq!(compare_versions("1.1.5", "1.1.5"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0", "2.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.13.0-rc01", "1.13.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-alpha", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-alpha1", "1.0-beta1"), Ordering::Less);
+        assert_eq!(
+            compare_versions("1.0-beta2", "1.0-milestone1"),
+            Ordering::Less
+        );
+        assert_eq!(compare_versions("1.0-M1", "1.0-rc1"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-rc2", "1.0-SNAPSHOT"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-SNAPSHOT", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0-sp1"), Ordering::Less);
+        assert_eq!(compare_versions("1.0-ga", "1.0-final"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0-alpha", "1.0.1"), Ordering::Less);
+        assert_eq!(
+            compare_versions("1.0-alpha10", "1.0-alpha9"),
+            Ordering::Greater
+        );
+        assert_eq!(compare_versions("1.9.0-rc01", "1.8.1"), Ordering::Greater);
+        assert_eq!(requested_version("[1.2.0]"), "1.2.0");
+        assert_eq!(requested_version("[1.0,2.0)"), "1.0");
+        assert_eq!(requested_version("(,2.0]"), "2.0");
+        assert_eq!(
+            dechunk(b"3\r\nabc\r\n2;x\r\nde\r\n0\r\n\r\n").unwrap(),
+            b"abcde"
+        );
+    }
+
+    #[test]
+    fn resolve_transitively() {
+        let repo = TempDir::new("ndk-build2-maven");
+        let publish = |artifact: &str, version: &str, body: &str, packaging: &str| {
+            let dir = repo.join("com/example").join(artifact).join(version);
+            fs::create_dir_all(&dir).unwrap();
+            let pom = format!(
+                "<?xml version=\"1.0\"?>\n<project xmlns=\"http://maven.apache.org/POM/4.0.0\">\
+                <groupId>com.example</groupId><artifactId>{artifact}</artifactId>\
+                <version>{version}</version><packaging>{packaging}</packaging>{body}</project>"
+            );
+            fs::write(dir.join(format!("{artifact}-{version}.pom")), pom).unwrap();
+            if packaging != "pom" {
+                fs::write(dir.join(format!("{artifact}-{version}.{packaging}")), "").unwrap();
+            }
+        };
+        let dependency = |artifact: &str, version: &str, extra: &str| {
+            format!(
+                "<dependency><groupId>com.example</groupId><artifactId>{artifact}</artifactId>\
+                <version>{version}</version>{extra}</dependency>"
+            )
+        };
+        publish(
+            "parent",
+            "1",
+            &format!(
+                "<properties><util.version>1.0</util.version></properties>\
+                <dependencyManagement><dependencies>{}</dependencies></dependencyManagement>",
+                dependency("log", "2.0", "")
+            ),
+            "pom",
+        );
+        publish(
+            "app-lib",
+            "1.0",
+            &format!(
+                "<parent><groupId>com.example</groupId><artifactId>parent</artifactId>\
+                <version>1</version></parent><dependencies>{}{}{}{}</dependencies>",
+                dependency("util", "${util.version}", ""),
+                "<dependency><groupId>com.example</groupId><artifactId>log</artifactId></dependency>",
+                dependency("junit", "4", "<scope>test</scope>"),
+                dependency("extra", "1", "<optional>true</optional>"),
+            ),
+            "aar",
+        );
+        publish(
+            "util",
+            "1.0",
+            &format!(
+                "<dependencies>{}</dependencies>",
+                dependency("old", "1", "")
+            ),
+            "jar",
+        );
+        publish("util", "1.5", "", "jar");
+        publish(
+            "log",
+            "2.0",
+            &format!(
+                "<dependencies>{}</dependencies>",
+                dependency(
+                    "util",
+                    "1.5",
+                    "<exclusions><exclusion><groupId>*</groupId>\
+                    <artifactId>*</artifactId></exclusion></exclusions>"
+                )
+            ),
+            "jar",
+        );
+        publish("old", "1", "", "jar");
+
+        let mut resolver = Resolver::new(
+            vec![Repository::Local(repo.to_path_buf())],
+            repo.join("downloads"),
+        );
+        let artifacts = resolver
+            .resolve(&[Dependency::parse("com.example:app-lib:1.0").unwrap()])
+            .unwrap();
+        let resolved = artifacts
+            .iter()
+            .map(|artifact| format!("{} {}", artifact.dependency, artifact.packaging))
+            .collect::<Vec<_>>();
+        // `util` 1.0 的依赖 `old` 在选择 1.5 后不再需要
+        assert_eq!(
+            resolved,
+            [
+                "com.example:app-lib:1.0 aar",
+                "com.example:util:1.5 jar",
+                "com.example:log:2.0 jar",
+            ]
+        );
+        assert!(Dependency::parse("com.example:app-lib").is_err());
+    }
+}
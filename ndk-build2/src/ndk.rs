@@ -1,5 +1,9 @@
 use {
-    crate::{error::NdkError, target::Target},
+    crate::{
+        error::NdkError,
+        keystore::{KeyGenOptions, KeystoreType, keytool},
+        target::Target,
+    },
     dunce::canonicalize,
     std::{
         collections::HashMap,
@@ -19,7 +23,8 @@ pub struct Ndk {
     user_home: PathBuf,
     ndk_path: PathBuf,
     sdk_path: PathBuf,
-    build_tools_version: String,
+    /// 未安装 build-tools 时为 `None`，不需要 aapt 等工具的 APK 仍可构建
+    build_tools_version: Option<String>,
     build_tag: u32,
     platforms: Vec<u32>,
 }
@@ -67,14 +72,13 @@ impl Ndk {
         };
 
         let build_tools_path = sdk_path.join("build-tools");
-        let build_tools_version = read_dir(&build_tools_path)
-            .or(Err(NdkError::PathNotFound(build_tools_path.clone())))?
-            .filter_map(|path| path.ok())
-            .filter(|path| path.path().is_dir())
-            .filter_map(|path| path.file_name().into_string().ok())
-            .filter(|name| name.chars().next().unwrap().is_ascii_digit())
-            .max()
-            .ok_or(NdkError::BuildToolsNotFound)?;
+        let build_tools_version = read_dir(&build_tools_path).ok().and_then(|dir| {
+            dir.filter_map(|path| path.ok())
+                .filter(|path| path.path().is_dir())
+                .filter_map(|path| path.file_name().into_string().ok())
+                .filter(|name| name.chars().next().unwrap().is_ascii_digit())
+                .max()
+        });
 
         let build_tag = read_to_string(ndk_path.join("source.properties"))
             .expect("Failed to read source.properties");
@@ -142,8 +146,8 @@ impl Ndk {
         &self.ndk_path
     }
 
-    pub fn build_tools_version(&self) -> &str {
-        &self.build_tools_version
+    pub fn build_tools_version(&self) -> Option<&str> {
+        self.build_tools_version.as_deref()
     }
 
     pub fn build_tag(&self) -> u32 {
@@ -158,12 +162,16 @@ impl Ndk {
         &self.sdk_path
     }
 
-    pub fn build_tools(&self) -> PathBuf {
-        self.build_tools_path.join(&self.build_tools_version)
+    pub fn build_tools(&self) -> Result<PathBuf, NdkError> {
+        let version = self
+            .build_tools_version
+            .as_ref()
+            .ok_or(NdkError::BuildToolsNotFound)?;
+        Ok(self.build_tools_path.join(version))
     }
 
     pub fn build_tool(&self, tool: &str) -> Result<Command, NdkError> {
-        let path = self.build_tools().join(tool);
+        let path = self.build_tools()?.join(tool);
         if !path.exists() {
             return Err(NdkError::CmdNotFound(tool.to_string()));
         }
@@ -396,21 +404,7 @@ impl Ndk {
     }
 
     pub fn keytool(&self) -> Result<Command, NdkError> {
-        if let Ok(keytool) = which::which(bin!("keytool")) {
-            let mut cmd = Command::new(keytool);
-            cmd.stdin(Stdio::null());
-            return Ok(cmd);
-        }
-        if let Some(java) = android_build::java_home() {
-            let keytool = java.join("bin").join(bin!("keytool"));
-            if keytool.exists() {
-                let mut cmd = Command::new(keytool);
-                cmd.stdin(Stdio::null());
-                return Ok(cmd);
-            }
-        }
-
-        Err(NdkError::CmdNotFound("keytool".to_string()))
+        keytool()
     }
 
     /// Returns a `Command` for the `java` executable, used to run Java-based tools directly.
@@ -434,7 +428,7 @@ impl Ndk {
     /// Returns the classpath for running d8 directly via Java, bypassing the d8 shell script.
     /// This avoids issues with shell script compatibility (e.g. CRLF line endings) on Linux/macOS.
     pub fn d8_classpath(&self) -> Result<String, NdkError> {
-        let lib_dir = self.build_tools().join("lib");
+        let lib_dir = self.build_tools()?.join("lib");
         let mut classpath = String::new();
         if let Ok(entries) = std::fs::read_dir(&lib_dir) {
             for entry in entries.flatten() {
@@ -453,38 +447,30 @@ impl Ndk {
         Ok(classpath)
     }
 
-    //noinspection SpellCheckingInspection
     pub fn debug_key(&self) -> Result<Key, NdkError> {
         let path = self.android_user_home()?.join("debug.keystore");
         let password = DEFAULT_DEV_KEYSTORE_PASSWORD.to_owned();
 
         if !path.exists() {
-            let mut keytool = self.keytool()?;
-            keytool
-                .arg("-genkey")
-                .arg("-v")
-                .arg("-keystore")
-                .arg(&path)
-                .arg("-storepass")
-                .arg(&password)
-                .arg("-alias")
-                .arg("androiddebugkey")
-                .arg("-keypass")
-                .arg(&password)
-                .arg("-dname")
-                .arg("CN=Android Debug,O=Android,C=US")
-                .arg("-keyalg")
-                .arg("RSA")
-                .arg("-keysize")
-                .arg("2048")
-                .arg("-validity")
-                .arg("10000");
-            if !keytool.status()?.success() {
-                return Err(NdkError::CmdFailed(Box::new(keytool)));
+            KeyGenOptions {
+                path: path.clone(),
+                keystore_type: KeystoreType::Pkcs12,
+                keystore_password: password.clone(),
+                alias: "androiddebugkey".into(),
+                key_password: None,
+                dname: "CN=Android Debug,O=Android,C=US".into(),
+                key_algorithm: "RSA".into(),
+                key_size: Some(2048),
+                validity: 10000,
             }
+            .generate()?;
         }
 
-        Ok(Key { path, password })
+        Ok(Key {
+            path,
+            password,
+            ..Default::default()
+        })
     }
 
     pub fn sysroot_lib_dir(&self, target: Target) -> Result<PathBuf, NdkError> {
@@ -556,9 +542,17 @@ impl Ndk {
     }
 }
 
+#[derive(Clone, Default)]
 pub struct Key {
     pub path: PathBuf,
+    /// keystore 密码
     pub password: String,
+    /// 未指定时使用 keystore 中的第一个私钥条目
+    pub alias: Option<String>,
+    /// 未指定时与 keystore 密码相同
+    pub key_password: Option<String>,
+    /// 未指定时根据文件头自动识别
+    pub keystore_type: Option<KeystoreType>,
 }
 
 #[cfg(test)]
@@ -569,7 +563,7 @@ mod tests {
     #[ignore]
     fn test_detect() {
         let ndk = Ndk::from_env().unwrap();
-        assert_eq!(ndk.build_tools_version(), "29.0.2");
+        assert_eq!(ndk.build_tools_version(), Some("29.0.2"));
         assert_eq!(ndk.platforms(), &[29, 28]);
     }
 }
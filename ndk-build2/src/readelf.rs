@@ -6,6 +6,21 @@ use std::io::BufRead;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
+/// 支持 16 KB 内存页的设备所要求的 LOAD 段最小对齐
+pub const PAGE_SIZE_16K: u64 = 16384;
+
+const PT_LOAD: u32 = 1;
+const PT_DYNAMIC: u32 = 2;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+const DT_STRTAB: u64 = 5;
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNSYM: u32 = 11;
+const STB_LOCAL: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+const SHN_UNDEF: u16 = 0;
+
 impl<'a> UnalignedApk<'a> {
     pub fn add_lib_recursively(
         &mut self,
@@ -65,6 +80,298 @@ impl<'a> UnalignedApk<'a> {
     }
 }
 
+/// ELF 程序头中与打包相关的字段
+#[derive(Clone, Copy, Debug)]
+pub struct ProgramHeader {
+    pub p_type: u32,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub file_size: u64,
+    pub align: u64,
+}
+
+/// 符号表中已定义的函数或数据对象
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+/// 在进程内解析的小端 ELF 文件，无需 NDK 中的 `llvm-readelf`
+pub struct ElfFile<'a> {
+    path: &'a Path,
+    data: &'a [u8],
+    is_64: bool,
+    program_headers: Vec<ProgramHeader>,
+}
+
+impl<'a> ElfFile<'a> {
+    /// `path` 仅用于错误信息，例如 APK 中的条目名称
+    pub fn parse(path: &'a Path, data: &'a [u8]) -> Result<Self, NdkError> {
+        let mut elf = Self {
+            path,
+            data,
+            is_64: false,
+            program_headers: Vec::new(),
+        };
+        if data.len() < 0x34 || data[..4] != *b"\x7fELF" {
+            return Err(elf.invalid("bad magic"));
+        }
+        if data[5] != 1 {
+            return Err(elf.invalid("only little-endian files are supported"));
+        }
+        elf.is_64 = match data[4] {
+            1 => false,
+            2 => true,
+            _ => return Err(elf.invalid("unknown ELF class")),
+        };
+
+        // e_phoff、e_phentsize 与 e_phnum
+        let (ph_offset, ph_entry_size, ph_count) = if elf.is_64 {
+            (elf.u64_at(0x20)?, elf.u16_at(0x36)?, elf.u16_at(0x38)?)
+        } else {
+            (
+                elf.u32_at(0x1c)? as u64,
+                elf.u16_at(0x2a)?,
+                elf.u16_at(0x2c)?,
+            )
+        };
+        for i in 0..ph_count as u64 {
+            let at = (ph_offset + i * ph_entry_size as u64) as usize;
+            let header = if elf.is_64 {
+                ProgramHeader {
+                    p_type: elf.u32_at(at)?,
+                    offset: elf.u64_at(at + 0x08)?,
+                    vaddr: elf.u64_at(at + 0x10)?,
+                    file_size: elf.u64_at(at + 0x20)?,
+                    align: elf.u64_at(at + 0x30)?,
+                }
+            } else {
+                ProgramHeader {
+                    p_type: elf.u32_at(at)?,
+                    offset: elf.u32_at(at + 0x04)? as u64,
+                    vaddr: elf.u32_at(at + 0x08)? as u64,
+                    file_size: elf.u32_at(at + 0x10)? as u64,
+                    align: elf.u32_at(at + 0x1c)? as u64,
+                }
+            };
+            elf.program_headers.push(header);
+        }
+        Ok(elf)
+    }
+
+    pub fn is_64(&self) -> bool {
+        self.is_64
+    }
+
+    pub fn program_headers(&self) -> &[ProgramHeader] {
+        &self.program_headers
+    }
+
+    /// LOAD 段的最小对齐，没有 LOAD 段时返回 `None`
+    pub fn min_load_alignment(&self) -> Option<u64> {
+        self.program_headers
+            .iter()
+            .filter(|header| header.p_type == PT_LOAD)
+            .map(|header| header.align)
+            .min()
+    }
+
+    /// 动态段中的 `DT_NEEDED` 条目，即运行时依赖的动态库
+    pub fn needed_libs(&self) -> Result<Vec<String>, NdkError> {
+        let Some(dynamic) = self
+            .program_headers
+            .iter()
+            .find(|header| header.p_type == PT_DYNAMIC)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let entry_size = if self.is_64 { 16 } else { 8 };
+        let mut needed = Vec::new();
+        let mut string_table = None;
+        for i in 0..dynamic.file_size / entry_size {
+            let at = (dynamic.offset + i * entry_size) as usize;
+            let (tag, value) = if self.is_64 {
+                (self.u64_at(at)?, self.u64_at(at + 8)?)
+            } else {
+                (self.u32_at(at)? as u64, self.u32_at(at + 4)? as u64)
+            };
+            match tag {
+                DT_NULL => break,
+                DT_NEEDED => needed.push(value),
+                DT_STRTAB => string_table = Some(value),
+                _ => {}
+            }
+        }
+        if needed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // DT_STRTAB 是虚拟地址，需要通过 LOAD 段换算为文件偏移
+        let string_table = string_table.ok_or_else(|| self.invalid("missing DT_STRTAB"))?;
+        let string_table = self
+            .program_headers
+            .iter()
+            .find(|header| {
+                header.p_type == PT_LOAD
+                    && (header.vaddr..header.vaddr + header.file_size).contains(&string_table)
+            })
+            .map(|header| string_table - header.vaddr + header.offset)
+            .ok_or_else(|| self.invalid("DT_STRTAB is not mapped by a LOAD segment"))?;
+        needed
+            .into_iter()
+            .map(|offset| {
+                let start = (string_table + offset) as usize;
+                let name = self
+                    .data
+                    .get(start..)
+                    .and_then(|rest| rest.split(|b| *b == 0).next())
+                    .ok_or_else(|| self.invalid("string table out of range"))?;
+                Ok(String::from_utf8_lossy(name).into_owned())
+            })
+            .collect()
+    }
+
+    /// `.symtab` 中已定义且大小不为零的函数与数据对象，库被剥离了符号时返回空列表
+    pub fn symbols(&self) -> Result<Vec<Symbol>, NdkError> {
+        self.symbol_table(SHT_SYMTAB, false)
+    }
+
+    /// `.dynsym` 中导出的函数与数据对象，即可以通过 `dlsym` 找到的符号。剥离符号不会影响它们
+    pub fn dynamic_symbols(&self) -> Result<Vec<Symbol>, NdkError> {
+        self.symbol_table(SHT_DYNSYM, true)
+    }
+
+    /// 类型为 `section_type` 的符号表中已定义且大小不为零的函数与数据对象，
+    /// `exported_only` 为真时跳过局部符号
+    fn symbol_table(
+        &self,
+        section_type: u32,
+        exported_only: bool,
+    ) -> Result<Vec<Symbol>, NdkError> {
+        // e_shoff、e_shentsize 与 e_shnum
+        let (sh_offset, sh_entry_size, sh_count) = if self.is_64 {
+            (self.u64_at(0x28)?, self.u16_at(0x3a)?, self.u16_at(0x3c)?)
+        } else {
+            (
+                self.u32_at(0x20)? as u64,
+                self.u16_at(0x2e)?,
+                self.u16_at(0x30)?,
+            )
+        };
+        // 返回节的类型、偏移、大小与 sh_link
+        let section = |index: u64| -> Result<(u32, u64, u64, u32), NdkError> {
+            let at = (sh_offset + index * sh_entry_size as u64) as usize;
+            Ok(if self.is_64 {
+                (
+                    self.u32_at(at + 0x04)?,
+                    self.u64_at(at + 0x18)?,
+                    self.u64_at(at + 0x20)?,
+                    self.u32_at(at + 0x28)?,
+                )
+            } else {
+                (
+                    self.u32_at(at + 0x04)?,
+                    self.u32_at(at + 0x10)? as u64,
+                    self.u32_at(at + 0x14)? as u64,
+                    self.u32_at(at + 0x18)?,
+                )
+            })
+        };
+        let Some((_, offset, size, link)) = (0..sh_count as u64)
+            .map(section)
+            .find(|section| section.as_ref().is_ok_and(|s| s.0 == section_type))
+            .transpose()?
+        else {
+            return Ok(Vec::new());
+        };
+        let (_, string_table, string_table_size, _) = section(link as u64)?;
+        let string_table = self
+            .data
+            .get(string_table as usize..(string_table + string_table_size) as usize)
+            .ok_or_else(|| self.invalid("string table out of range"))?;
+
+        let entry_size = if self.is_64 { 24 } else { 16 };
+        let mut symbols = Vec::new();
+        for i in 0..size / entry_size {
+            let at = (offset + i * entry_size) as usize;
+            let (name, info, section_index, address, size) = if self.is_64 {
+                (
+                    self.u32_at(at)?,
+                    self.bytes::<1>(at + 4)?[0],
+                    self.u16_at(at + 6)?,
+                    self.u64_at(at + 8)?,
+                    self.u64_at(at + 16)?,
+                )
+            } else {
+                (
+                    self.u32_at(at)?,
+                    self.bytes::<1>(at + 12)?[0],
+                    self.u16_at(at + 14)?,
+                    self.u32_at(at + 4)? as u64,
+                    self.u32_at(at + 8)? as u64,
+                )
+            };
+            if !matches!(info & 0xf, STT_OBJECT | STT_FUNC)
+                || section_index == SHN_UNDEF
+                || size == 0
+                || (exported_only && info >> 4 == STB_LOCAL)
+            {
+                continue;
+            }
+            let name = string_table
+                .get(name as usize..)
+                .and_then(|rest| rest.split(|b| *b == 0).next())
+                .ok_or_else(|| self.invalid("symbol name out of range"))?;
+            symbols.push(Symbol {
+                name: String::from_utf8_lossy(name).into_owned(),
+                address,
+                size,
+            });
+        }
+        Ok(symbols)
+    }
+
+    fn invalid(&self, msg: &str) -> NdkError {
+        NdkError::InvalidElf(self.path.into(), msg.into())
+    }
+
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N], NdkError> {
+        self.data
+            .get(offset..offset + N)
+            .map(|b| b.try_into().unwrap())
+            .ok_or_else(|| self.invalid("unexpected end of file"))
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, NdkError> {
+        self.bytes(offset).map(u16::from_le_bytes)
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32, NdkError> {
+        self.bytes(offset).map(u32::from_le_bytes)
+    }
+
+    fn u64_at(&self, offset: usize) -> Result<u64, NdkError> {
+        self.bytes(offset).map(u64::from_le_bytes)
+    }
+}
+
+/// 返回 ELF 文件中 LOAD 段的最小对齐，没有 LOAD 段时返回 `None`
+pub fn min_load_alignment(path: &Path) -> Result<Option<u64>, NdkError> {
+    let data = std::fs::read(path).map_err(|e| NdkError::IoPathError(path.into(), e))?;
+    Ok(ElfFile::parse(path, &data)?.min_load_alignment())
+}
+
+/// 检查 64 位目标的动态库是否兼容 16 KB 内存页，不兼容时返回其 LOAD 段的最小对齐
+pub(crate) fn check_page_alignment(path: &Path, target: Target) -> Result<Option<u64>, NdkError> {
+    if !matches!(target, Target::Arm64V8a | Target::X86_64) {
+        return Ok(None);
+    }
+    Ok(min_load_alignment(path)?.filter(|align| *align < PAGE_SIZE_16K))
+}
+
 /// List all linked shared libraries
 fn list_needed_libs(readelf_path: &Path, library_path: &Path) -> Result<HashSet<String>, NdkError> {
     let mut readelf = Command::new(readelf_path);
@@ -0,0 +1,110 @@
+//! 读取 aapt2 `--emit-ids` 写入的资源 ID，生成可以通过 `include!` 引入的 Rust 模块。
+
+use {
+    crate::error::NdkError,
+    std::{
+        collections::BTreeMap,
+        fmt::Write as _,
+        fs::{read, read_to_string, write},
+        path::Path,
+    },
+};
+
+/// Rust 关键字，用作标识符时需要写成 `r#name`
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+/// 不能写成原始标识符的关键字
+const RESERVED: &[&str] = &["_", "crate", "self", "Self", "super"];
+
+/// 按类型分组的资源 ID，例如 `string` 中的 `app_name`
+#[derive(Clone, Debug, Default)]
+pub struct ResourceIds {
+    pub types: BTreeMap<String, BTreeMap<String, u32>>,
+}
+
+impl ResourceIds {
+    /// 读取 `--emit-ids` 格式的文件，每行形如 `com.example:string/app_name = 0x7f0e0000`
+    pub fn read(path: &Path) -> Result<Self, NdkError> {
+        let text = read_to_string(path).map_err(|e| NdkError::IoPathError(path.into(), e))?;
+        Self::parse(&text).map_err(|line| NdkError::InvalidResourceId(path.into(), line))
+    }
+
+    /// 解析 `--emit-ids` 格式的文本，出错时返回无法解析的行
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut ids = Self::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || line.to_owned();
+            let (name, id) = line.split_once(" = ").ok_or_else(invalid)?;
+            let name = name.rsplit_once(':').map_or(name, |(_, name)| name);
+            let (ty, name) = name.split_once('/').ok_or_else(invalid)?;
+            let id = u32::from_str_radix(id.trim().trim_start_matches("0x"), 16)
+                .map_err(|_| invalid())?;
+            // 与 R.java 相同，名称中的 `.` 替换为 `_`
+            ids.types
+                .entry(ty.to_owned())
+                .or_default()
+                .insert(name.replace('.', "_"), id);
+        }
+        Ok(ids)
+    }
+
+    /// 生成 Rust 源码：每种资源类型一个模块，其中每个资源一个 `pub const`
+    pub fn to_rust(&self) -> String {
+        let mut rust =
+            String::from("// @generated from the resource IDs assigned by aapt2, do not edit\n");
+        for (ty, names) in &self.types {
+            rust.push_str("\n#[allow(dead_code, non_upper_case_globals)]\n");
+            writeln!(rust, "pub mod {} {{", ident(ty)).unwrap();
+            for (name, id) in names {
+                writeln!(rust, "    pub const {}: i32 = {id:#010x};", ident(name)).unwrap();
+            }
+            rust.push_str("}\n");
+        }
+        rust
+    }
+
+    /// 将 [`Self::to_rust`] 写入 `path`。内容没有变化时不写入，避免 cargo 因修改时间变化而重新编译
+    pub fn write_rust(&self, path: &Path) -> Result<(), NdkError> {
+        let rust = self.to_rust();
+        if read(path).is_ok_and(|old| old == rust.as_bytes()) {
+            return Ok(());
+        }
+        write(path, rust).map_err(|e| NdkError::IoPathError(path.into(), e))
+    }
+}
+
+/// 将名称转换为 Rust 标识符：关键字写成原始标识符，不能写成原始标识符的关键字后加 `_`
+pub(crate) fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else if RESERVED.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResourceIds;
+
+    #[test]
+    fn resource_ids_to_rust() {
+        let rust = ResourceIds::parse(
+            "com.example:string/app_name = 0x7f0e0000\n\
+            com.example:style/Theme.App = 0x7f100001\n\
+            com.example:id/type = 0x7f080002\n",
+        )
+        .unwrap()
+        .to_rust();
+        assert!(rust.contains("pub mod string {\n    pub const app_name: i32 = 0x7f0e0000;\n}"));
+        assert!(rust.contains("pub const Theme_App: i32 = 0x7f100001;"));
+        assert!(rust.contains("pub const r#type: i32 = 0x7f080002;"));
+        assert!(ResourceIds::parse("string/app_name 0x7f0e0000").is_err());
+    }
+}
@@ -0,0 +1,1067 @@
+//! 内置的 APK 签名器，实现 JAR 签名（v1）以及 APK 签名方案 v2/v3，无需 JDK 与 `apksigner`。
+//!
+//! 参见 <https://source.android.com/docs/security/features/apksigning>。
+
+use {
+    crate::{
+        der::{self, Der},
+        error::NdkError,
+        keystore::{Certificate, Keystore},
+        ndk::Key,
+        zip::{ZipArchive, ZipEntry, ZipWriter, default_alignment, u32_at},
+    },
+    base64::{Engine, engine::general_purpose::STANDARD as BASE64},
+    rsa::{Pkcs1v15Sign, Pss, RsaPrivateKey, RsaPublicKey, pkcs8::DecodePublicKey},
+    sha1::Sha1,
+    sha2::{Digest, Sha256, Sha384, Sha512},
+    std::collections::{BTreeMap, HashSet},
+};
+
+const APK_SIG_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+const V2_BLOCK_ID: u32 = 0x7109871a;
+const V3_BLOCK_ID: u32 = 0xf05368c0;
+/// v2 签名数据中的附加属性，声明该 APK 同时带有 v3 签名，防止 v3 签名块被剥离
+const STRIPPING_PROTECTION_ATTR_ID: u32 = 0xbeeff00d;
+const RSA_PSS_WITH_SHA256: u32 = 0x0101;
+const RSA_PSS_WITH_SHA512: u32 = 0x0102;
+const RSA_PKCS1_V1_5_WITH_SHA256: u32 = 0x0103;
+const RSA_PKCS1_V1_5_WITH_SHA512: u32 = 0x0104;
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// Android 7.0 之前的设备只验证 JAR 签名
+pub(crate) const MIN_SDK_WITH_V2: u32 = 24;
+/// Android 9 开始验证 v3 签名
+pub(crate) const MIN_SDK_WITH_V3: u32 = 28;
+const CREATED_BY: &str = "1.0 (Android)";
+const OID_MESSAGE_DIGEST: &str = "1.2.840.113549.1.9.4";
+const OID_SHA384: &str = "2.16.840.1.101.3.4.2.2";
+const OID_SHA512: &str = "2.16.840.1.101.3.4.2.3";
+/// PKCS#7 `SignerInfo` 中可用于 RSA 签名的算法标识
+const RSA_SIGNATURE_OIDS: &[&str] = &[
+    der::OID_RSA_ENCRYPTION,
+    "1.2.840.113549.1.1.5",
+    "1.2.840.113549.1.1.11",
+    "1.2.840.113549.1.1.12",
+    "1.2.840.113549.1.1.13",
+];
+
+/// 持有私钥与证书链，对 APK 或 JAR 进行签名
+pub struct Signer {
+    private_key: RsaPrivateKey,
+    certificates: Vec<Certificate>,
+}
+
+impl Signer {
+    pub fn new(private_key: RsaPrivateKey, certificates: Vec<Certificate>) -> Self {
+        Self {
+            private_key,
+            certificates,
+        }
+    }
+
+    /// 从 [`Key`] 指向的 keystore 中读取私钥条目，未指定别名时使用第一个私钥条目
+    pub fn from_key(key: &Key) -> Result<Self, NdkError> {
+        let keystore = Keystore::load(&key.path, &key.password)?;
+        if let Some(expected) = key.keystore_type
+            && expected != keystore.keystore_type
+        {
+            return Err(NdkError::KeystoreTypeMismatch {
+                path: key.path.clone(),
+                expected,
+                found: keystore.keystore_type,
+            });
+        }
+        let (private_key, certificates) = keystore.private_key(
+            key.alias.as_deref(),
+            key.key_password.as_deref().unwrap_or(&key.password),
+        )?;
+        if certificates.is_empty() {
+            return Err(NdkError::KeyNotFound {
+                path: key.path.clone(),
+                alias: key.alias.clone(),
+            });
+        }
+        Ok(Self::new(private_key, certificates))
+    }
+
+    pub fn certificates(&self) -> &[Certificate] {
+        &self.certificates
+    }
+
+    /// 签名 APK：始终写入 v2 与 v3 签名块，`min_sdk_version` 低于 24 时还会添加 JAR 签名
+    pub fn sign_apk(&self, apk: Vec<u8>, min_sdk_version: u32) -> Result<Vec<u8>, NdkError> {
+        let mut archive = ZipArchive::from_bytes(apk)?;
+        if min_sdk_version < MIN_SDK_WITH_V2 {
+            archive = ZipArchive::from_bytes(self.jar_sign(&archive, true)?)?;
+        }
+        self.add_signing_block(&archive)
+    }
+
+    /// 仅添加 JAR 签名（例如用于 Android App Bundle）
+    pub fn sign_jar(&self, jar: Vec<u8>) -> Result<Vec<u8>, NdkError> {
+        self.jar_sign(&ZipArchive::from_bytes(jar)?, false)
+    }
+
+    fn sign_data(&self, data: &[u8]) -> Result<Vec<u8>, NdkError> {
+        Ok(self
+            .private_key
+            .sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(data))?)
+    }
+
+    /// 生成 `META-INF/MANIFEST.MF`、`CERT.SF` 与 `CERT.RSA`，旧的签名文件会被丢弃
+    fn jar_sign(&self, archive: &ZipArchive, apk_signed: bool) -> Result<Vec<u8>, NdkError> {
+        let mut writer = ZipWriter::new();
+        let mut sections = Vec::new();
+        for entry in archive.entries() {
+            if is_jar_signature_file(&entry.name) {
+                continue;
+            }
+            writer.copy_entry(archive, entry, default_alignment(&entry.name))?;
+            if entry.is_dir() {
+                continue;
+            }
+            let digest = BASE64.encode(Sha256::digest(archive.read(entry)?));
+            let section = format!(
+                "{}{}\r\n",
+                manifest_attribute("Name", &entry.name),
+                manifest_attribute("SHA-256-Digest", &digest)
+            );
+            sections.push((entry.name.clone(), section));
+        }
+        sections.sort();
+
+        let mut manifest = format!(
+            "{}{}\r\n",
+            manifest_attribute("Manifest-Version", "1.0"),
+            manifest_attribute("Created-By", CREATED_BY)
+        );
+        let mut signature_file = String::new();
+        for (name, section) in &sections {
+            manifest.push_str(section);
+            signature_file.push_str(&manifest_attribute("Name", name));
+            signature_file.push_str(&manifest_attribute(
+                "SHA-256-Digest",
+                &BASE64.encode(Sha256::digest(section.as_bytes())),
+            ));
+            signature_file.push_str("\r\n");
+        }
+        let mut header = format!(
+            "{}{}{}",
+            manifest_attribute("Signature-Version", "1.0"),
+            manifest_attribute("Created-By", CREATED_BY),
+            manifest_attribute(
+                "SHA-256-Digest-Manifest",
+                &BASE64.encode(Sha256::digest(manifest.as_bytes()))
+            )
+        );
+        if apk_signed {
+            // 让支持 v2/v3 的设备拒绝被剥离了签名块的 APK
+            header.push_str(&manifest_attribute("X-Android-APK-Signed", "2, 3"));
+        }
+        let signature_file = format!("{header}\r\n{signature_file}");
+        let signature_block = self.pkcs7_signed_data(signature_file.as_bytes())?;
+
+        writer.add_entry("META-INF/MANIFEST.MF", manifest.as_bytes(), true, 4)?;
+        writer.add_entry("META-INF/CERT.SF", signature_file.as_bytes(), true, 4)?;
+        writer.add_entry("META-INF/CERT.RSA", &signature_block, true, 4)?;
+        writer.finish()
+    }
+
+    /// 分离式 PKCS#7 `SignedData`，不带签名属性
+    fn pkcs7_signed_data(&self, content: &[u8]) -> Result<Vec<u8>, NdkError> {
+        let signature = self.sign_data(content)?;
+        let certificate = &self.certificates[0];
+        let signer_info = der::sequence(&[
+            &der::integer(1),
+            &der::sequence(&[certificate.issuer(), certificate.serial_number()]),
+            &der::algorithm(der::OID_SHA256),
+            &der::algorithm(der::OID_RSA_ENCRYPTION),
+            &der::octet_string(&signature),
+        ]);
+        let certificates = self
+            .certificates
+            .iter()
+            .flat_map(|certificate| certificate.der().iter().copied())
+            .collect::<Vec<_>>();
+        let signed_data = der::sequence(&[
+            &der::integer(1),
+            &der::set(&[&der::algorithm(der::OID_SHA256)]),
+            &der::sequence(&[&der::oid(der::OID_DATA)]),
+            &der::context(0, &certificates),
+            &der::set(&[&signer_info]),
+        ]);
+        Ok(der::sequence(&[
+            &der::oid(der::OID_SIGNED_DATA),
+            &der::context(0, &signed_data),
+        ]))
+    }
+
+    /// 在中央目录之前插入 APK 签名块，并更新 EOCD 中的中央目录偏移
+    fn add_signing_block(&self, archive: &ZipArchive) -> Result<Vec<u8>, NdkError> {
+        let data = archive.data();
+        let central_directory_offset = archive.central_directory_offset();
+        let eocd_offset = archive.eocd_offset();
+        // 重新签名时丢弃旧的签名块
+        let contents_end = find_signing_block(data, central_directory_offset)
+            .map_or(central_directory_offset, |(start, _)| start);
+
+        let mut eocd = data[eocd_offset..].to_vec();
+        eocd[16..20].copy_from_slice(&(contents_end as u32).to_le_bytes());
+        let digest = content_digest::<Sha256>(&[
+            &data[..contents_end],
+            &data[central_directory_offset..eocd_offset],
+            &eocd,
+        ]);
+
+        let block = signing_block(&[
+            (V2_BLOCK_ID, self.v2_block(&digest)?),
+            (V3_BLOCK_ID, self.v3_block(&digest)?),
+        ]);
+        let new_offset = u32::try_from(contents_end + block.len())
+            .map_err(|_| NdkError::InvalidZip("APK is too large".into()))?;
+        eocd[16..20].copy_from_slice(&new_offset.to_le_bytes());
+
+        let mut out = Vec::with_capacity(data.len() + block.len());
+        out.extend_from_slice(&data[..contents_end]);
+        out.extend_from_slice(&block);
+        out.extend_from_slice(&data[central_directory_offset..eocd_offset]);
+        out.extend_from_slice(&eocd);
+        Ok(out)
+    }
+
+    fn digests_and_certificates(&self, digest: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let digests = length_prefixed_sequence(&[[
+            RSA_PKCS1_V1_5_WITH_SHA256.to_le_bytes().as_slice(),
+            &length_prefixed(digest),
+        ]
+        .concat()]);
+        let certificates = length_prefixed_sequence(
+            &self
+                .certificates
+                .iter()
+                .map(|certificate| certificate.der().to_vec())
+                .collect::<Vec<_>>(),
+        );
+        (digests, certificates)
+    }
+
+    fn signatures(&self, signed_data: &[u8]) -> Result<Vec<u8>, NdkError> {
+        Ok(length_prefixed_sequence(&[[
+            RSA_PKCS1_V1_5_WITH_SHA256.to_le_bytes().as_slice(),
+            &length_prefixed(&self.sign_data(signed_data)?),
+        ]
+        .concat()]))
+    }
+
+    fn v2_block(&self, digest: &[u8]) -> Result<Vec<u8>, NdkError> {
+        let (digests, certificates) = self.digests_and_certificates(digest);
+        let attributes = length_prefixed_sequence(&[[
+            STRIPPING_PROTECTION_ATTR_ID.to_le_bytes(),
+            3u32.to_le_bytes(),
+        ]
+        .concat()]);
+        let signed_data = [digests, certificates, attributes].concat();
+        let signer = [
+            length_prefixed(&signed_data),
+            self.signatures(&signed_data)?,
+            length_prefixed(self.certificates[0].public_key_info()),
+        ]
+        .concat();
+        Ok(length_prefixed_sequence(&[signer]))
+    }
+
+    fn v3_block(&self, digest: &[u8]) -> Result<Vec<u8>, NdkError> {
+        let (digests, certificates) = self.digests_and_certificates(digest);
+        let sdk_range = [
+            MIN_SDK_WITH_V3.to_le_bytes(),
+            (i32::MAX as u32).to_le_bytes(),
+        ]
+        .concat();
+        let signed_data = [
+            digests,
+            certificates,
+            sdk_range.clone(),
+            length_prefixed_sequence(&[]),
+        ]
+        .concat();
+        let signer = [
+            length_prefixed(&signed_data),
+            sdk_range,
+            self.signatures(&signed_data)?,
+            length_prefixed(self.certificates[0].public_key_info()),
+        ]
+        .concat();
+        Ok(length_prefixed_sequence(&[signer]))
+    }
+}
+
+/// APK 中各签名方案携带的签名者证书
+#[derive(Clone, Debug, Default)]
+pub struct ApkSignatures {
+    /// JAR 签名（`META-INF/*.RSA` 等）中的证书
+    pub v1: Vec<Certificate>,
+    pub v2: Vec<Certificate>,
+    pub v3: Vec<Certificate>,
+}
+
+/// 读取 APK 中每个签名者的证书，不校验签名本身
+pub fn read_signatures(archive: &ZipArchive) -> Result<ApkSignatures, NdkError> {
+    let mut signatures = ApkSignatures::default();
+
+    for entry in archive.entries() {
+        let upper = entry.name.to_ascii_uppercase();
+        if is_jar_signature_file(&entry.name)
+            && [".RSA", ".DSA", ".EC"]
+                .iter()
+                .any(|ext| upper.ends_with(ext))
+        {
+            signatures
+                .v1
+                .extend(pkcs7_certificates(&archive.read(entry)?)?);
+        }
+    }
+
+    let data = archive.data();
+    if let Some((_, pairs)) = find_signing_block(data, archive.central_directory_offset()) {
+        for (id, value) in signing_block_pairs(&data[pairs])? {
+            let certificates = match id {
+                V2_BLOCK_ID => &mut signatures.v2,
+                V3_BLOCK_ID => &mut signatures.v3,
+                _ => continue,
+            };
+            // v2 与 v3 的签名者均以 signed data 开头，其中第二项为证书序列
+            for signer in split_length_prefixed_sequence(value)? {
+                let (signed_data, _) = split_length_prefixed(signer)?;
+                let (_digests, rest) = split_length_prefixed(signed_data)?;
+                let (encoded, _) = split_length_prefixed(rest)?;
+                if let Some(certificate) = split_length_prefixed_items(encoded)?.first() {
+                    certificates.push(Certificate::from_der(certificate.to_vec())?);
+                }
+            }
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// PKCS#7 `SignedData` 中携带的证书
+fn pkcs7_certificates(content_info: &[u8]) -> Result<Vec<Certificate>, NdkError> {
+    let content_info = Der::parse_tagged(content_info, der::TAG_SEQUENCE)?.children()?;
+    let signed_data = content_info
+        .get(1)
+        .ok_or_else(|| NdkError::InvalidDer("missing PKCS#7 content".into()))?
+        .children()?;
+    let signed_data = signed_data
+        .first()
+        .ok_or_else(|| NdkError::InvalidDer("missing PKCS#7 SignedData".into()))?
+        .expect(der::TAG_SEQUENCE)?;
+    let Some(certificates) = signed_data
+        .children()?
+        .into_iter()
+        .find(|field| field.tag == 0xa0)
+    else {
+        return Ok(Vec::new());
+    };
+    certificates
+        .children()?
+        .into_iter()
+        .map(|certificate| Certificate::from_der(certificate.raw.to_vec()))
+        .collect()
+}
+
+/// 解析签名块中的 ID-值对
+fn signing_block_pairs(data: &[u8]) -> Result<Vec<(u32, &[u8])>, NdkError> {
+    let mut pairs = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let len = rest
+            .get(..8)
+            .map(|len| u64::from_le_bytes(len.try_into().unwrap()) as usize)
+            .filter(|len| *len >= 4 && rest.len() - 8 >= *len)
+            .ok_or_else(|| invalid_signature("truncated ID-value pair"))?;
+        pairs.push((u32_at(rest, 8), &rest[12..8 + len]));
+        rest = &rest[8 + len..];
+    }
+    Ok(pairs)
+}
+
+/// 拆分以 u32 长度为前缀的数据，返回内容与剩余部分
+fn split_length_prefixed(data: &[u8]) -> Result<(&[u8], &[u8]), NdkError> {
+    let len = data
+        .get(..4)
+        .map(|len| u32::from_le_bytes(len.try_into().unwrap()) as usize)
+        .filter(|len| data.len() - 4 >= *len)
+        .ok_or_else(|| invalid_signature("truncated length-prefixed data"))?;
+    Ok((&data[4..4 + len], &data[4 + len..]))
+}
+
+/// 拆分一个连续排列的长度前缀元素序列
+fn split_length_prefixed_items(mut data: &[u8]) -> Result<Vec<&[u8]>, NdkError> {
+    let mut items = Vec::new();
+    while !data.is_empty() {
+        let (item, rest) = split_length_prefixed(data)?;
+        items.push(item);
+        data = rest;
+    }
+    Ok(items)
+}
+
+/// 拆分自身也带长度前缀的元素序列（[`length_prefixed_sequence`] 的逆操作）
+fn split_length_prefixed_sequence(data: &[u8]) -> Result<Vec<&[u8]>, NdkError> {
+    let (items, _) = split_length_prefixed(data)?;
+    split_length_prefixed_items(items)
+}
+
+fn invalid_signature(msg: &str) -> NdkError {
+    NdkError::InvalidApkSignature(msg.into())
+}
+
+/// JAR 签名相关的文件：`META-INF/MANIFEST.MF`、`*.SF`、`*.RSA` 等
+fn is_jar_signature_file(name: &str) -> bool {
+    let Some(file) = name.strip_prefix("META-INF/") else {
+        return false;
+    };
+    if file.contains('/') {
+        return false;
+    }
+    let upper = file.to_ascii_uppercase();
+    upper == "MANIFEST.MF"
+        || upper.starts_with("SIG-")
+        || [".SF", ".RSA", ".DSA", ".EC"]
+            .iter()
+            .any(|ext| upper.ends_with(ext))
+}
+
+/// JAR 清单属性，每行最多 72 字节，续行以空格开头
+fn manifest_attribute(name: &str, value: &str) -> String {
+    let line = format!("{name}: {value}");
+    let mut out = String::new();
+    let mut current = 0;
+    for ch in line.chars() {
+        let limit = if out.is_empty() { 72 } else { 71 };
+        if current + ch.len_utf8() > limit {
+            out.push_str("\r\n ");
+            current = 0;
+        }
+        out.push(ch);
+        current += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+    out
+}
+
+/// 按 1 MiB 分块计算 v2/v3 签名所使用的内容摘要
+fn content_digest<D: Digest>(sections: &[&[u8]]) -> Vec<u8> {
+    let mut chunk_digests = Vec::new();
+    let mut chunk_count = 0u32;
+    for section in sections {
+        for chunk in section.chunks(CHUNK_SIZE) {
+            chunk_digests.extend(
+                D::new()
+                    .chain_update([0xa5])
+                    .chain_update((chunk.len() as u32).to_le_bytes())
+                    .chain_update(chunk)
+                    .finalize(),
+            );
+            chunk_count += 1;
+        }
+    }
+    D::new()
+        .chain_update([0x5a])
+        .chain_update(chunk_count.to_le_bytes())
+        .chain_update(chunk_digests)
+        .finalize()
+        .to_vec()
+}
+
+fn signing_block(pairs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (id, value) in pairs {
+        body.extend_from_slice(&(4 + value.len() as u64).to_le_bytes());
+        body.extend_from_slice(&id.to_le_bytes());
+        body.extend_from_slice(value);
+    }
+    let size = (body.len() + 8 + APK_SIG_BLOCK_MAGIC.len()) as u64;
+    let mut block = Vec::with_capacity(body.len() + 32);
+    block.extend_from_slice(&size.to_le_bytes());
+    block.extend_from_slice(&body);
+    block.extend_from_slice(&size.to_le_bytes());
+    block.extend_from_slice(APK_SIG_BLOCK_MAGIC);
+    block
+}
+
+/// 若中央目录之前存在 APK 签名块，返回其起始偏移与 ID-值对所在的区间
+pub(crate) fn find_signing_block(
+    data: &[u8],
+    central_directory_offset: usize,
+) -> Option<(usize, std::ops::Range<usize>)> {
+    let magic_offset = central_directory_offset.checked_sub(APK_SIG_BLOCK_MAGIC.len())?;
+    if &data[magic_offset..central_directory_offset] != APK_SIG_BLOCK_MAGIC {
+        return None;
+    }
+    let size_offset = magic_offset.checked_sub(8)?;
+    let size = u64::from_le_bytes(data[size_offset..magic_offset].try_into().unwrap()) as usize;
+    let start = central_directory_offset.checked_sub(size.checked_add(8)?)?;
+    if u64::from_le_bytes(data[start..start + 8].try_into().unwrap()) as usize != size {
+        return None;
+    }
+    Some((start, start + 8..size_offset))
+}
+
+fn length_prefixed(data: &[u8]) -> Vec<u8> {
+    let mut out = (data.len() as u32).to_le_bytes().to_vec();
+    out.extend_from_slice(data);
+    out
+}
+
+fn length_prefixed_sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    length_prefixed(
+        &items
+            .iter()
+            .flat_map(|item| length_prefixed(item))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// 签名校验的结果
+#[derive(Clone, Debug, Default)]
+pub struct SignatureVerification {
+    /// 通过校验的各签名方案的签名者证书
+    pub verified: ApkSignatures,
+    /// 校验失败的原因
+    pub errors: Vec<String>,
+    /// 无法在进程内校验的签名，例如使用了 EC 或 DSA 密钥
+    pub warnings: Vec<String>,
+}
+
+/// 校验 APK 中的 JAR 签名与 v2/v3 签名块。只支持 RSA 签名，其他算法记为警告
+pub fn verify_signatures(archive: &ZipArchive) -> Result<SignatureVerification, NdkError> {
+    let mut verification = SignatureVerification::default();
+    let mut declared_schemes = Vec::new();
+    match verify_jar_signature(archive, &mut declared_schemes, &mut verification.warnings) {
+        Ok(certificates) => verification.verified.v1 = certificates,
+        Err(e) => verification.errors.push(format!("v1: {}", failure(e))),
+    }
+
+    let data = archive.data();
+    let central_directory_offset = archive.central_directory_offset();
+    let eocd_offset = archive.eocd_offset();
+    let mut present = Vec::new();
+    if let Some((start, pairs)) = find_signing_block(data, central_directory_offset) {
+        // 计算摘要时，EOCD 中的中央目录偏移指向签名块的起始位置
+        let mut eocd = data[eocd_offset..].to_vec();
+        eocd[16..20].copy_from_slice(&(start as u32).to_le_bytes());
+        let sections = [
+            &data[..start],
+            &data[central_directory_offset..eocd_offset],
+            &eocd,
+        ];
+        let mut digests = BTreeMap::new();
+        let mut content_digest = |algorithm: DigestAlgorithm| {
+            digests
+                .entry(algorithm)
+                .or_insert_with(|| match algorithm {
+                    DigestAlgorithm::Sha512 => content_digest::<Sha512>(&sections),
+                    _ => content_digest::<Sha256>(&sections),
+                })
+                .clone()
+        };
+        for (id, value) in signing_block_pairs(&data[pairs])? {
+            let (scheme, certificates) = match id {
+                V2_BLOCK_ID => ("v2", &mut verification.verified.v2),
+                V3_BLOCK_ID => ("v3", &mut verification.verified.v3),
+                _ => continue,
+            };
+            present.push(scheme);
+            match verify_signature_scheme_block(
+                value,
+                id == V3_BLOCK_ID,
+                &mut content_digest,
+                &mut verification.warnings,
+            ) {
+                Ok(verified) => *certificates = verified,
+                Err(e) => verification
+                    .errors
+                    .push(format!("{scheme}: {}", failure(e))),
+            }
+        }
+    }
+
+    // `X-Android-APK-Signed` 声明的签名块被剥离时，支持该方案的设备会拒绝安装
+    for scheme in declared_schemes {
+        if !present.contains(&scheme.as_str()) {
+            verification.errors.push(format!(
+                "v1: the JAR signature declares a {scheme} signature, but the APK has none"
+            ));
+        }
+    }
+    Ok(verification)
+}
+
+/// 签名校验使用的摘要算法
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    fn from_oid(oid: &str) -> Option<Self> {
+        match oid {
+            der::OID_SHA1 => Some(Self::Sha1),
+            der::OID_SHA256 => Some(Self::Sha256),
+            OID_SHA384 => Some(Self::Sha384),
+            OID_SHA512 => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// JAR 清单中 `<算法>-Digest` 属性的算法名称
+    fn from_jar_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "SHA1" | "SHA-1" => Some(Self::Sha1),
+            "SHA-256" => Some(Self::Sha256),
+            "SHA-384" => Some(Self::Sha384),
+            "SHA-512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha384 => Sha384::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn pkcs1v15(self) -> Pkcs1v15Sign {
+        match self {
+            Self::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+            Self::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+            Self::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+            Self::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+        }
+    }
+}
+
+/// 校验 v2 或 v3 签名块中的每个签名者，返回其证书
+fn verify_signature_scheme_block(
+    block: &[u8],
+    v3: bool,
+    content_digest: &mut dyn FnMut(DigestAlgorithm) -> Vec<u8>,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<Certificate>, NdkError> {
+    let signers = split_length_prefixed_sequence(block)?;
+    if signers.is_empty() {
+        return Err(invalid_signature("no signers"));
+    }
+    let mut certificates = Vec::new();
+    for signer in signers {
+        let (signed_data, mut rest) = split_length_prefixed(signer)?;
+        let sdk_range = if v3 {
+            let range = rest
+                .get(..8)
+                .ok_or_else(|| invalid_signature("truncated signer"))?;
+            rest = &rest[8..];
+            Some(range)
+        } else {
+            None
+        };
+        let (signatures, rest) = split_length_prefixed(rest)?;
+        let (public_key, _) = split_length_prefixed(rest)?;
+
+        let mut chosen = None;
+        let mut unsupported = Vec::new();
+        for signature in split_length_prefixed_items(signatures)? {
+            if signature.len() < 4 {
+                return Err(invalid_signature("truncated signature"));
+            }
+            let algorithm = u32_at(signature, 0);
+            let (signature, _) = split_length_prefixed(&signature[4..])?;
+            match algorithm {
+                RSA_PSS_WITH_SHA256
+                | RSA_PSS_WITH_SHA512
+                | RSA_PKCS1_V1_5_WITH_SHA256
+                | RSA_PKCS1_V1_5_WITH_SHA512 => {
+                    chosen.get_or_insert((algorithm, signature));
+                }
+                _ => unsupported.push(format!("0x{algorithm:04x}")),
+            }
+        }
+        let Some((algorithm, signature)) = chosen else {
+            warnings.push(format!(
+                "{}: signature algorithm {} can't be verified, only RSA signatures are supported",
+                if v3 { "v3" } else { "v2" },
+                unsupported.join(", ")
+            ));
+            continue;
+        };
+        let digest_algorithm = match algorithm {
+            RSA_PSS_WITH_SHA512 | RSA_PKCS1_V1_5_WITH_SHA512 => DigestAlgorithm::Sha512,
+            _ => DigestAlgorithm::Sha256,
+        };
+        let key = RsaPublicKey::from_public_key_der(public_key)
+            .map_err(|e| invalid_signature(&e.to_string()))?;
+        let hashed = digest_algorithm.digest(signed_data);
+        let result = match algorithm {
+            RSA_PSS_WITH_SHA256 => key.verify(Pss::new::<Sha256>(), &hashed, signature),
+            RSA_PSS_WITH_SHA512 => key.verify(Pss::new::<Sha512>(), &hashed, signature),
+            _ => key.verify(digest_algorithm.pkcs1v15(), &hashed, signature),
+        };
+        result.map_err(|_| invalid_signature("signature over the signed data does not verify"))?;
+
+        let (digests, rest) = split_length_prefixed(signed_data)?;
+        let (encoded_certificates, rest) = split_length_prefixed(rest)?;
+        if let Some(range) = sdk_range
+            && rest.get(..8) != Some(range)
+        {
+            return Err(invalid_signature(
+                "signed SDK version range does not match the signer",
+            ));
+        }
+        let mut expected = None;
+        for digest in split_length_prefixed_items(digests)? {
+            if digest.len() >= 4 && u32_at(digest, 0) == algorithm {
+                expected = Some(split_length_prefixed(&digest[4..])?.0);
+            }
+        }
+        let expected =
+            expected.ok_or_else(|| invalid_signature("no digest for the signature algorithm"))?;
+        if expected != content_digest(digest_algorithm) {
+            return Err(invalid_signature(
+                "APK contents do not match the signed digest",
+            ));
+        }
+        let certificate = split_length_prefixed_items(encoded_certificates)?
+            .first()
+            .map(|certificate| Certificate::from_der(certificate.to_vec()))
+            .transpose()?
+            .ok_or_else(|| invalid_signature("no certificates"))?;
+        if certificate.public_key_info() != public_key {
+            return Err(invalid_signature(
+                "public key does not match the signer certificate",
+            ));
+        }
+        certificates.push(certificate);
+    }
+    Ok(certificates)
+}
+
+/// 校验 JAR 签名并返回签名者证书，APK 没有 JAR 签名时返回空列表。
+/// `X-Android-APK-Signed` 声明的签名方案（如 `v2`）添加到 `declared_schemes`
+fn verify_jar_signature(
+    archive: &ZipArchive,
+    declared_schemes: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Result<Vec<Certificate>, NdkError> {
+    let signature_files = archive
+        .entries()
+        .iter()
+        .filter(|entry| {
+            is_jar_signature_file(&entry.name) && entry.name.to_ascii_uppercase().ends_with(".SF")
+        })
+        .collect::<Vec<_>>();
+    if signature_files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let manifest = archive
+        .entries()
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case("META-INF/MANIFEST.MF"))
+        .ok_or_else(|| invalid_signature("META-INF/MANIFEST.MF is missing"))?;
+    let manifest = archive.read(manifest)?;
+    let manifest_sections = jar_sections(&manifest);
+    let named_sections = manifest_sections
+        .iter()
+        .skip(1)
+        .filter_map(|section| Some((jar_attribute(section, "Name")?, *section)))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut certificates = Vec::new();
+    for signature_file in signature_files {
+        let base = &signature_file.name[..signature_file.name.len() - 3];
+        let block = archive
+            .entries()
+            .iter()
+            .find(|entry| {
+                let upper = entry.name.to_ascii_uppercase();
+                entry.name.len() > base.len()
+                    && entry.name[..base.len()].eq_ignore_ascii_case(base)
+                    && [".RSA", ".DSA", ".EC"].contains(&&upper[base.len()..])
+            })
+            .ok_or_else(|| {
+                invalid_signature(&format!("no signature block for `{}`", signature_file.name))
+            })?;
+        let content = archive.read(signature_file)?;
+        let Some(certificate) = verify_pkcs7(&archive.read(block)?, &content, warnings)? else {
+            continue;
+        };
+
+        // 优先校验整个清单的摘要，不匹配时逐个校验条目节
+        let sections = jar_sections(&content);
+        let main = sections.first().copied().unwrap_or_default();
+        let manifest_digest = jar_digest(main, "-Digest-Manifest");
+        if manifest_digest.is_none_or(|(algorithm, digest)| algorithm.digest(&manifest) != digest) {
+            for section in sections.iter().skip(1) {
+                let name = jar_attribute(section, "Name")
+                    .ok_or_else(|| invalid_signature("signature file section without a name"))?;
+                let (algorithm, digest) = jar_digest(section, "-Digest").ok_or_else(|| {
+                    invalid_signature(&format!("no supported digest for `{name}`"))
+                })?;
+                if named_sections
+                    .get(&name)
+                    .is_none_or(|manifest_section| algorithm.digest(manifest_section) != digest)
+                {
+                    return Err(invalid_signature(&format!(
+                        "`{}` does not match MANIFEST.MF for `{name}`",
+                        signature_file.name
+                    )));
+                }
+            }
+        }
+        if let Some(schemes) = jar_attribute(main, "X-Android-APK-Signed") {
+            declared_schemes.extend(
+                schemes
+                    .split(',')
+                    .map(|scheme| format!("v{}", scheme.trim()))
+                    .filter(|scheme| scheme == "v2" || scheme == "v3"),
+            );
+        }
+        certificates.push(certificate);
+    }
+
+    // 清单中的摘要必须与条目内容一致，且除签名文件外的所有条目都必须被签名
+    let mut signed = HashSet::new();
+    for (name, section) in &named_sections {
+        let Some((algorithm, digest)) = jar_digest(section, "-Digest") else {
+            continue;
+        };
+        let entry = archive
+            .entry(name)
+            .ok_or_else(|| invalid_signature(&format!("`{name}` is signed but missing")))?;
+        if algorithm.digest(&archive.read(entry)?) != digest {
+            return Err(invalid_signature(&format!(
+                "`{name}` does not match its digest in MANIFEST.MF"
+            )));
+        }
+        signed.insert(name.as_str());
+    }
+    if let Some(entry) = archive.entries().iter().find(|entry: &&ZipEntry| {
+        !entry.is_dir()
+            && !is_jar_signature_file(&entry.name)
+            && !signed.contains(entry.name.as_str())
+    }) {
+        return Err(invalid_signature(&format!(
+            "`{}` is not covered by the JAR signature",
+            entry.name
+        )));
+    }
+    Ok(certificates)
+}
+
+/// 校验分离式 PKCS#7 `SignedData` 对 `content` 的签名，返回签名者证书；
+/// 签名算法不受支持时记录警告并返回 `None`
+fn verify_pkcs7(
+    content_info: &[u8],
+    content: &[u8],
+    warnings: &mut Vec<String>,
+) -> Result<Option<Certificate>, NdkError> {
+    let certificates = pkcs7_certificates(content_info)?;
+    let content_info = Der::parse_tagged(content_info, der::TAG_SEQUENCE)?.children()?;
+    let signed_data = content_info
+        .get(1)
+        .ok_or_else(|| NdkError::InvalidDer("missing PKCS#7 content".into()))?
+        .children()?;
+    let signer_info = signed_data
+        .first()
+        .ok_or_else(|| NdkError::InvalidDer("missing PKCS#7 SignedData".into()))?
+        .children()?
+        .last()
+        .ok_or_else(|| NdkError::InvalidDer("empty PKCS#7 SignedData".into()))?
+        .expect(der::TAG_SET)?
+        .children()?
+        .first()
+        .ok_or_else(|| invalid_signature("no SignerInfo"))?
+        .children()?;
+    // version、issuerAndSerialNumber、digestAlgorithm、[0] signedAttrs（可选）、signatureAlgorithm、signature
+    let [_, signer_id, digest_algorithm, rest @ ..] = signer_info.as_slice() else {
+        return Err(NdkError::InvalidDer("truncated SignerInfo".into()));
+    };
+    let (signed_attributes, rest) = match rest {
+        [attributes, rest @ ..] if attributes.tag == 0xa0 => (Some(attributes), rest),
+        rest => (None, rest),
+    };
+    let [signature_algorithm, signature, ..] = rest else {
+        return Err(NdkError::InvalidDer("truncated SignerInfo".into()));
+    };
+    let signer_id = signer_id.children()?;
+    let certificate = certificates
+        .into_iter()
+        .find(|certificate| {
+            signer_id.len() == 2
+                && certificate.issuer() == signer_id[0].raw
+                && certificate.serial_number() == signer_id[1].raw
+        })
+        .ok_or_else(|| invalid_signature("signer certificate not found"))?;
+
+    let algorithm_oid = |algorithm: &Der| -> Result<String, NdkError> {
+        algorithm
+            .children()?
+            .first()
+            .ok_or_else(|| NdkError::InvalidDer("empty AlgorithmIdentifier".into()))?
+            .oid()
+    };
+    let digest_oid = algorithm_oid(digest_algorithm)?;
+    let signature_oid = algorithm_oid(signature_algorithm)?;
+    let Some(digest_algorithm) = DigestAlgorithm::from_oid(&digest_oid)
+        .filter(|_| RSA_SIGNATURE_OIDS.contains(&signature_oid.as_str()))
+    else {
+        warnings.push(format!(
+            "v1: signature algorithm {signature_oid} with digest {digest_oid} can't be verified, \
+            only RSA signatures are supported"
+        ));
+        return Ok(None);
+    };
+
+    let signed = match signed_attributes {
+        Some(attributes) => {
+            let message_digest = attributes.children()?.into_iter().find_map(|attribute| {
+                let fields = attribute.children().ok()?;
+                (fields.first()?.oid().ok()? == OID_MESSAGE_DIGEST)
+                    .then(|| Some(fields.get(1)?.children().ok()?.first()?.content))?
+            });
+            if message_digest != Some(digest_algorithm.digest(content).as_slice()) {
+                return Err(invalid_signature(
+                    "messageDigest attribute does not match the signature file",
+                ));
+            }
+            // 签名计算于以 SET 标签编码的签名属性之上
+            let mut attributes = attributes.raw.to_vec();
+            attributes[0] = der::TAG_SET;
+            attributes
+        }
+        None => content.to_vec(),
+    };
+    RsaPublicKey::from_public_key_der(certificate.public_key_info())
+        .map_err(|e| invalid_signature(&e.to_string()))?
+        .verify(
+            digest_algorithm.pkcs1v15(),
+            &digest_algorithm.digest(&signed),
+            signature.expect(der::TAG_OCTET_STRING)?.content,
+        )
+        .map_err(|_| invalid_signature("signature over the signature file does not verify"))?;
+    Ok(Some(certificate))
+}
+
+/// 按空行拆分 JAR 清单或签名文件的节，每节包含其结尾的空行
+fn jar_sections(data: &[u8]) -> Vec<&[u8]> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut line_start = 0;
+    while line_start < data.len() {
+        let line_end = data[line_start..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map_or(data.len(), |i| line_start + i + 1);
+        let line = &data[line_start..line_end];
+        if line == b"\r\n" || line == b"\n" {
+            sections.push(&data[start..line_end]);
+            start = line_end;
+        }
+        line_start = line_end;
+    }
+    if start < data.len() {
+        sections.push(&data[start..]);
+    }
+    sections
+}
+
+/// 读取一节中的属性值，续行（以空格开头）会被拼接
+fn jar_attribute(section: &[u8], name: &str) -> Option<String> {
+    jar_attributes(section)
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+fn jar_attributes(section: &[u8]) -> Vec<(String, String)> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in String::from_utf8_lossy(section).lines() {
+        match line.strip_prefix(' ') {
+            Some(continuation) => {
+                if let Some(last) = lines.last_mut() {
+                    last.push_str(continuation);
+                }
+            }
+            None if !line.is_empty() => lines.push(line.to_owned()),
+            None => {}
+        }
+    }
+    lines
+        .into_iter()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(": ")?;
+            Some((key.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// 节中第一个受支持的 `<算法><suffix>` 摘要属性
+fn jar_digest(section: &[u8], suffix: &str) -> Option<(DigestAlgorithm, Vec<u8>)> {
+    jar_attributes(section)
+        .into_iter()
+        .find_map(|(key, value)| {
+            let algorithm = DigestAlgorithm::from_jar_name(key.strip_suffix(suffix)?)?;
+            Some((algorithm, BASE64.decode(value).ok()?))
+        })
+}
+
+/// 签名校验失败的说明，去掉 [`NdkError::InvalidApkSignature`] 的前缀
+fn failure(error: NdkError) -> String {
+    match error {
+        NdkError::InvalidApkSignature(msg) => msg,
+        error => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{MIN_SDK_WITH_V2, verify_signatures},
+        crate::{
+            testing::{debug_signer, unsigned_apk},
+            zip::ZipArchive,
+        },
+    };
+
+    #[test]
+    fn sign_and_verify() {
+        let signer = debug_signer();
+        for min_sdk_version in [21, 24, 28] {
+            let apk = signer
+                .sign_apk(unsigned_apk(min_sdk_version), min_sdk_version)
+                .unwrap();
+            let verification = verify_signatures(&ZipArchive::from_bytes(apk).unwrap()).unwrap();
+            assert!(verification.errors.is_empty(), "{:?}", verification.errors);
+            assert!(verification.warnings.is_empty());
+            let verified = verification.verified;
+            if min_sdk_version < MIN_SDK_WITH_V2 {
+                assert_eq!(verified.v1, signer.certificates());
+            } else {
+                assert!(verified.v1.is_empty());
+            }
+            assert_eq!(verified.v2, signer.certificates());
+            assert_eq!(verified.v3, signer.certificates());
+        }
+
+        // App Bundle 只有 JAR 签名
+        let jar = signer.sign_jar(unsigned_apk(21)).unwrap();
+        let verification = verify_signatures(&ZipArchive::from_bytes(jar).unwrap()).unwrap();
+        assert!(verification.errors.is_empty(), "{:?}", verification.errors);
+        assert_eq!(verification.verified.v1, signer.certificates());
+        assert!(verification.verified.v2.is_empty() && verification.verified.v3.is_empty());
+    }
+}
@@ -0,0 +1,309 @@
+//! 统计 APK 中各部分占用的空间：各 ABI 的动态库（符号表可用时细分到 crate）、dex、资源、资产与签名。
+
+use {
+    crate::{
+        error::NdkError,
+        readelf::ElfFile,
+        signer::find_signing_block,
+        zip::{ZipArchive, ZipEntry},
+    },
+    serde::Serialize,
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// 不属于任何 Rust crate 的符号（C/C++ 代码、汇编等）
+const NON_RUST: &str = "[non-Rust]";
+/// 动态库中没有对应符号的部分：ELF 头、字符串表、重定位与展开信息等
+const UNATTRIBUTED: &str = "[unattributed]";
+
+/// APK 的空间占用
+#[derive(Debug, Serialize)]
+pub struct ApkSize {
+    pub path: PathBuf,
+    /// APK 文件大小，等于 `parts` 中所有压缩后大小之和
+    pub size: u64,
+    /// 按类别汇总的条目，例如 `lib/arm64-v8a`、`dex`、`resources.arsc` 与签名块
+    pub parts: Vec<PartSize>,
+    /// 以 ABI 分组的动态库
+    pub abis: BTreeMap<String, AbiSize>,
+}
+
+/// 一类条目的未压缩大小与在 APK 中的大小
+#[derive(Debug, Serialize)]
+pub struct PartSize {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+}
+
+/// 一个 ABI 的所有动态库
+#[derive(Debug, Default, Serialize)]
+pub struct AbiSize {
+    pub size: u64,
+    pub compressed_size: u64,
+    pub libs: Vec<LibSize>,
+}
+
+/// 一个动态库
+#[derive(Debug, Serialize)]
+pub struct LibSize {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    /// 按 crate 汇总的符号大小（未压缩），从大到小排列；没有符号表时为空
+    pub crates: Vec<CrateSize>,
+}
+
+/// 一个 crate 在动态库中的代码与数据大小
+#[derive(Debug, Serialize)]
+pub struct CrateSize {
+    pub name: String,
+    pub size: u64,
+}
+
+impl ApkSize {
+    pub fn read(path: &Path) -> Result<Self, NdkError> {
+        let archive = ZipArchive::open(path)?;
+        let mut parts = BTreeMap::<(u8, String), PartSize>::new();
+        let mut abis = BTreeMap::<String, AbiSize>::new();
+        let mut add = |name: &str, entry: &ZipEntry| {
+            let part = parts.entry(part_key(name)).or_insert_with(|| PartSize {
+                name: name.to_owned(),
+                size: 0,
+                compressed_size: 0,
+            });
+            part.size += entry.uncompressed_size as u64;
+            part.compressed_size += entry.compressed_size as u64;
+        };
+
+        for entry in archive.entries() {
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name.as_str();
+            if let Some((abi, file)) = name
+                .strip_prefix("lib/")
+                .and_then(|rest| rest.split_once('/'))
+            {
+                let abi_size = abis.entry(abi.to_owned()).or_default();
+                abi_size.size += entry.uncompressed_size as u64;
+                abi_size.compressed_size += entry.compressed_size as u64;
+                let crates = if file.ends_with(".so") {
+                    let data = archive.read(entry)?;
+                    crate_sizes(&ElfFile::parse(Path::new(name), &data)?, data.len() as u64)?
+                } else {
+                    Vec::new()
+                };
+                abi_size.libs.push(LibSize {
+                    name: file.to_owned(),
+                    size: entry.uncompressed_size as u64,
+                    compressed_size: entry.compressed_size as u64,
+                    crates,
+                });
+                add(&format!("lib/{abi}"), entry);
+            } else if !name.contains('/') && name.ends_with(".dex") {
+                add("dex", entry);
+            } else if name == "resources.arsc" || name == "AndroidManifest.xml" {
+                add(name, entry);
+            } else if name.starts_with("res/") {
+                add("res", entry);
+            } else if name.starts_with("assets/") {
+                add("assets", entry);
+            } else if name.starts_with("META-INF/") {
+                add("META-INF", entry);
+            } else {
+                add("other", entry);
+            }
+        }
+
+        let data = archive.data();
+        if let Some((start, _)) = find_signing_block(data, archive.central_directory_offset()) {
+            let signing_block = (archive.central_directory_offset() - start) as u64;
+            parts.insert(
+                part_key("APK Signing Block"),
+                PartSize {
+                    name: "APK Signing Block".into(),
+                    size: signing_block,
+                    compressed_size: signing_block,
+                },
+            );
+        }
+        // 本地文件头、对齐填充、中央目录与目录结束记录；`parts` 中已包含签名块
+        let entries = parts.values().map(|part| part.compressed_size).sum::<u64>();
+        let overhead = data.len() as u64 - entries;
+        parts.insert(
+            part_key("ZIP structure"),
+            PartSize {
+                name: "ZIP structure".into(),
+                size: overhead,
+                compressed_size: overhead,
+            },
+        );
+
+        for abi in abis.values_mut() {
+            abi.libs
+                .sort_by_key(|lib| std::cmp::Reverse(lib.compressed_size));
+        }
+        Ok(Self {
+            path: path.to_owned(),
+            size: data.len() as u64,
+            parts: parts.into_values().collect(),
+            abis,
+        })
+    }
+}
+
+impl LibSize {
+    /// 用未剥离符号的同一个库（例如 cargo 输出目录中的 `.so`）补充按 crate 的细分
+    pub fn attribute_symbols(&mut self, unstripped: &Path) -> Result<(), NdkError> {
+        let data =
+            std::fs::read(unstripped).map_err(|e| NdkError::IoPathError(unstripped.into(), e))?;
+        // 未剥离的库中额外包含符号表与调试信息，未归属部分按 APK 中的库计算
+        self.crates = crate_sizes(&ElfFile::parse(unstripped, &data)?, self.size)?;
+        Ok(())
+    }
+}
+
+/// 类别的排序键：动态库在前，其后依次为 dex、资源、资产与签名
+fn part_key(name: &str) -> (u8, String) {
+    let rank = match name {
+        name if name.starts_with("lib/") => 0,
+        "dex" => 1,
+        "resources.arsc" => 2,
+        "res" => 3,
+        "AndroidManifest.xml" => 4,
+        "assets" => 5,
+        "other" => 6,
+        "META-INF" => 7,
+        "APK Signing Block" => 8,
+        _ => 9,
+    };
+    (rank, name.to_owned())
+}
+
+/// 按 crate 汇总符号大小，没有符号表时返回空列表。`lib_size` 与符号大小之差记为未归属部分
+fn crate_sizes(elf: &ElfFile, lib_size: u64) -> Result<Vec<CrateSize>, NdkError> {
+    // 别名符号共享同一地址，只计算一次
+    let mut symbols = BTreeMap::new();
+    for symbol in elf.symbols()? {
+        let size = symbols.entry(symbol.address).or_insert((0, String::new()));
+        if symbol.size > size.0 {
+            *size = (symbol.size, symbol.name);
+        }
+    }
+    if symbols.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut crates = BTreeMap::<String, u64>::new();
+    for (size, name) in symbols.into_values() {
+        let name = crate_name(&name).unwrap_or_else(|| NON_RUST.into());
+        *crates.entry(name).or_default() += size;
+    }
+    let mut crates = crates
+        .into_iter()
+        .map(|(name, size)| CrateSize { name, size })
+        .collect::<Vec<_>>();
+    crates.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    let attributed = crates.iter().map(|c| c.size).sum::<u64>();
+    if lib_size > attributed {
+        crates.push(CrateSize {
+            name: UNATTRIBUTED.into(),
+            size: lib_size - attributed,
+        });
+    }
+    Ok(crates)
+}
+
+/// Rust 符号所属的 crate，即第一个完整路径的首段，例如 `<alloc::vec::Vec<T> as core::ops::Drop>::drop`
+/// 属于 `alloc`，原始类型上的实现 `<u8 as core::fmt::Debug>::fmt` 属于 `core`；不是 Rust 符号时返回 `None`
+fn crate_name(symbol: &str) -> Option<String> {
+    let demangled = format!("{:#}", rustc_demangle::try_demangle(symbol).ok()?);
+    let bytes = demangled.as_bytes();
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut start = 0;
+    while start < bytes.len() {
+        let end = start + bytes[start..].iter().take_while(|b| is_ident(**b)).count();
+        let is_path_start = start == 0 || b"<&*([ ,".contains(&bytes[start - 1]);
+        if end > start && is_path_start && demangled[end..].starts_with("::") {
+            return Some(demangled[start..end].to_owned());
+        }
+        start = end + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ApkSize, crate_name},
+        crate::testing::{TempDir, debug_signer, unsigned_apk},
+        std::fs,
+    };
+
+    #[test]
+    fn parts_add_up() {
+        let dir = TempDir::new("ndk-build2-size");
+        let path = dir.join("app.apk");
+        let apk = debug_signer().sign_apk(unsigned_apk(21), 21).unwrap();
+        fs::write(&path, &apk).unwrap();
+
+        let size = ApkSize::read(&path).unwrap();
+        assert_eq!(size.size, apk.len() as u64);
+        assert_eq!(
+            size.parts
+                .iter()
+                .map(|part| part.compressed_size)
+                .sum::<u64>(),
+            size.size
+        );
+        let names = size
+            .parts
+            .iter()
+            .map(|part| part.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "dex",
+                "AndroidManifest.xml",
+                "META-INF",
+                "APK Signing Block",
+                "ZIP structure"
+            ]
+        );
+    }
+
+    #[test]
+    fn crate_names() {
+        assert_eq!(
+            crate_name("_ZN4core3fmt5write17h0123456789abcdefE").as_deref(),
+            Some("core")
+        );
+        assert_eq!(
+            crate_name(
+                "_ZN70_$LT$alloc..vec..Vec$LT$T$C$A$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h0123456789abcdefE"
+            )
+            .as_deref(),
+            Some("alloc")
+        );
+        assert_eq!(
+            crate_name("_ZN4core3fmt3num49_$LT$impl$u20$core..fmt..Debug$u20$for$u20$u8$GT$3fmt17h0123456789abcdefE")
+                .as_deref(),
+            Some("core")
+        );
+        assert_eq!(
+            crate_name("_RNvCs1234_7mycrate4main").as_deref(),
+            Some("mycrate")
+        );
+        assert_eq!(
+            crate_name("_RINvXs_NvMNtCslNYArtu3iFV_5alloc5sliceSp9to_vec_inhNtB5_10ConvertVec6to_vecNtNtBa_5alloc6GlobalECsjrHSEGnQ3l9_3std").as_deref(),
+            Some("alloc")
+        );
+        assert_eq!(crate_name("memcpy"), None);
+        assert_eq!(crate_name("_ZN3foo3barEv"), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! 由按语言区域分组的字符串生成 `values*/strings.xml`，以及 Android 13 按应用设置语言所需的
+//! `xml/locales_config.xml`。
+
+use {
+    crate::error::NdkError,
+    quick_xml::escape::escape,
+    std::{
+        collections::BTreeMap,
+        fs::{create_dir_all, remove_dir_all, write},
+        path::Path,
+    },
+};
+
+/// 生成的语言配置在清单 `android:localeConfig` 中的引用
+pub const LOCALES_CONFIG: &str = "@xml/locales_config";
+
+/// 按语言区域分组的字符串资源
+#[derive(Clone, Debug, Default)]
+pub struct LocalizedStrings {
+    /// 写入默认资源 `values/strings.xml` 的语言区域，其中须包含所有字符串
+    pub default_locale: String,
+    /// 语言区域（BCP 47 标签，例如 `en`、`zh-CN`、`sr-Latn`）到字符串名称与值的映射
+    pub strings: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl LocalizedStrings {
+    /// 清空资源目录 `res_dir`，写入每个语言区域的 `strings.xml` 与 `xml/locales_config.xml`
+    pub fn write_resources(&self, res_dir: &Path) -> Result<(), NdkError> {
+        let _ = remove_dir_all(res_dir);
+        let default = self
+            .strings
+            .get(&self.default_locale)
+            .ok_or_else(|| NdkError::MissingDefaultLocale(self.default_locale.clone()))?;
+
+        let mut locales = vec![self.default_locale.replace('_', "-")];
+        for (locale, strings) in &self.strings {
+            // 设备的语言没有对应的字符串时使用默认资源，缺少默认值会导致运行时崩溃
+            if let Some(name) = strings.keys().find(|name| !default.contains_key(*name)) {
+                return Err(NdkError::MissingDefaultString {
+                    name: name.clone(),
+                    locale: locale.clone(),
+                    default_locale: self.default_locale.clone(),
+                });
+            }
+            let dir = if *locale == self.default_locale {
+                "values".into()
+            } else {
+                let tag = locale.replace('_', "-");
+                let dir = format!("values-{}", qualifier(&tag)?);
+                locales.push(tag);
+                dir
+            };
+            let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+            for (name, value) in strings {
+                xml.push_str(&format!(
+                    "    <string name=\"{}\">{}</string>\n",
+                    escape(name),
+                    escape(escape_string(value))
+                ));
+            }
+            xml.push_str("</resources>\n");
+            write_file(&res_dir.join(dir).join("strings.xml"), xml)?;
+        }
+
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+            <locale-config xmlns:android=\"http://schemas.android.com/apk/res/android\">\n",
+        );
+        for locale in locales {
+            xml.push_str(&format!("    <locale android:name=\"{locale}\" />\n"));
+        }
+        xml.push_str("</locale-config>\n");
+        write_file(&res_dir.join("xml").join("locales_config.xml"), xml)
+    }
+}
+
+fn write_file(path: &Path, contents: String) -> Result<(), NdkError> {
+    create_dir_all(path.parent().unwrap())?;
+    write(path, contents).map_err(|e| NdkError::IoPathError(path.into(), e))
+}
+
+/// BCP 47 标签对应的资源限定符：`en`、`zh-rCN`，带有文字或变体时为 `b+sr+Latn`
+fn qualifier(tag: &str) -> Result<String, NdkError> {
+    let parts = tag.split('-').collect::<Vec<_>>();
+    let is_alpha = |part: &str| part.bytes().all(|b| b.is_ascii_alphabetic());
+    let language = parts[0];
+    if !(2..=3).contains(&language.len())
+        || !is_alpha(language)
+        || parts
+            .iter()
+            .any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_alphanumeric()))
+    {
+        return Err(NdkError::InvalidLocale(tag.into()));
+    }
+    Ok(match parts[1..] {
+        [] => language.to_ascii_lowercase(),
+        [region]
+            if (region.len() == 2 && is_alpha(region))
+                || (region.len() == 3 && region.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            format!(
+                "{}-r{}",
+                language.to_ascii_lowercase(),
+                region.to_ascii_uppercase()
+            )
+        }
+        _ => format!("b+{}", parts.join("+")),
+    })
+}
+
+/// 转义 Android 字符串资源中有特殊含义的字符，使值按原样显示
+fn escape_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            // 以 `@` 或 `?` 开头的值会被当作资源引用
+            '@' | '?' if index == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_string, qualifier};
+
+    #[test]
+    fn locale_qualifiers() {
+        assert_eq!(qualifier("en").unwrap(), "en");
+        assert_eq!(qualifier("zh-CN").unwrap(), "zh-rCN");
+        assert_eq!(qualifier("pt-br").unwrap(), "pt-rBR");
+        assert_eq!(qualifier("es-419").unwrap(), "es-r419");
+        assert_eq!(qualifier("sr-Latn").unwrap(), "b+sr+Latn");
+        assert_eq!(qualifier("zh-Hant-TW").unwrap(), "b+zh+Hant+TW");
+        assert!(qualifier("english").is_err());
+        assert!(qualifier("en-").is_err());
+    }
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(escape_string("Don't \"quote\""), "Don\\'t \\\"quote\\\"");
+        assert_eq!(escape_string("@home?"), "\\@home?");
+        assert_eq!(escape_string("a\\b\nc"), "a\\\\b\\nc");
+    }
+}
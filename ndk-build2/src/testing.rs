@@ -0,0 +1,83 @@
+//! 测试辅助工具，供本 crate 与 `cargo-apk2` 的单元测试共用。
+
+use {
+    crate::{
+        axml::{self, Element},
+        ndk::Key,
+        signer::Signer,
+        zip::ZipWriter,
+    },
+    std::{
+        env::temp_dir,
+        fs::{create_dir_all, remove_dir_all},
+        ops::Deref,
+        path::{Path, PathBuf},
+        process,
+    },
+};
+
+/// 系统临时目录下的一个空目录，离开作用域时删除
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// 创建 `<临时目录>/<name>-<进程 id>`，已存在时先清空
+    pub fn new(name: &str) -> Self {
+        let dir = temp_dir().join(format!("{name}-{}", process::id()));
+        let _ = remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0);
+    }
+}
+
+/// `ndk-build2/testdata` 中的测试文件
+pub fn testdata(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata")
+        .join(name)
+}
+
+/// 使用 `testdata/debug.p12`（由 `keytool` 生成，密码为 `android`）的签名器
+pub fn debug_signer() -> Signer {
+    Signer::from_key(&Key {
+        path: testdata("debug.p12"),
+        password: "android".into(),
+        alias: None,
+        key_password: None,
+        keystore_type: None,
+    })
+    .unwrap()
+}
+
+/// 只包含二进制清单与 `classes.dex` 的未签名 APK
+pub fn unsigned_apk(min_sdk_version: u32) -> Vec<u8> {
+    let mut manifest = Element::parse(&format!(
+        r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android" package="com.example.app" android:versionCode="1">
+            <uses-sdk android:minSdkVersion="{min_sdk_version}" android:targetSdkVersion="35" />
+        </manifest>"#
+    ))
+    .unwrap();
+    manifest.compile_android_attributes(&|_| None).unwrap();
+    let mut writer = ZipWriter::new();
+    writer
+        .add_entry("AndroidManifest.xml", &axml::encode(&manifest), true, 4)
+        .unwrap();
+    writer
+        .add_entry("classes.dex", &[0; 4096], true, 4)
+        .unwrap();
+    writer.finish().unwrap()
+}
@@ -0,0 +1,330 @@
+//! 校验已签名的 APK：签名、条目对齐、清单以及动态库的 `DT_NEEDED` 依赖。
+
+use {
+    crate::{
+        axml,
+        error::NdkError,
+        readelf::{ElfFile, PAGE_SIZE_16K},
+        signer::{MIN_SDK_WITH_V2, MIN_SDK_WITH_V3, read_signatures, verify_signatures},
+        zip::{STORED, ZipArchive},
+    },
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        path::{Path, PathBuf},
+    },
+};
+
+/// 以 Android 11 为目标的 APK 必须带有 v2 或更高版本的签名，且 `resources.arsc` 不能压缩
+const TARGET_SDK_REQUIRING_V2: u32 = 30;
+
+/// NDK 稳定 API 提供的系统库，以及首次提供它们的 API 级别
+const PLATFORM_LIBS: &[(&str, u32)] = &[
+    ("libc.so", 1),
+    ("libdl.so", 1),
+    ("libm.so", 1),
+    ("liblog.so", 1),
+    ("libstdc++.so", 1),
+    ("libz.so", 1),
+    ("libGLESv1_CM.so", 4),
+    ("libGLESv2.so", 5),
+    ("libjnigraphics.so", 8),
+    ("libandroid.so", 9),
+    ("libEGL.so", 9),
+    ("libOpenSLES.so", 9),
+    ("libOpenMAXAL.so", 14),
+    ("libGLESv3.so", 18),
+    ("libmediandk.so", 21),
+    ("libcamera2ndk.so", 24),
+    ("libvulkan.so", 24),
+    ("libaaudio.so", 26),
+    ("libnativewindow.so", 26),
+    ("libsync.so", 26),
+    ("libneuralnetworks.so", 27),
+    ("libamidi.so", 29),
+    ("libbinder_ndk.so", 29),
+    ("libicu.so", 31),
+];
+
+/// 清单中应有的值，通常来自 `Cargo.toml`
+#[derive(Clone, Debug)]
+pub struct ExpectedManifest {
+    pub package: String,
+    pub version_code: Option<u32>,
+    pub version_name: Option<String>,
+}
+
+/// APK 的校验结果
+#[derive(Debug, Default)]
+pub struct ApkVerification {
+    pub path: PathBuf,
+    /// 通过校验的签名方案，例如 `["v2", "v3"]`
+    pub schemes: Vec<&'static str>,
+    /// 会导致 APK 无法安装或运行的问题
+    pub errors: Vec<String>,
+    /// 无法校验的内容
+    pub warnings: Vec<String>,
+}
+
+impl ApkVerification {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// 校验 APK。`expected` 为 `Some` 时还会检查清单中的包名与版本
+pub fn verify_apk(
+    path: &Path,
+    expected: Option<&ExpectedManifest>,
+) -> Result<ApkVerification, NdkError> {
+    let archive = ZipArchive::open(path)?;
+    let mut verification = ApkVerification {
+        path: path.to_owned(),
+        ..Default::default()
+    };
+    let errors = &mut verification.errors;
+
+    let manifest = match archive.entry("AndroidManifest.xml") {
+        Some(entry) => match archive.read(entry).and_then(|data| axml::decode(&data)) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                errors.push(format!("AndroidManifest.xml: {e}"));
+                None
+            }
+        },
+        None => {
+            errors.push("AndroidManifest.xml is missing".into());
+            None
+        }
+    };
+    let int = |element: &axml::Element, name: &str| {
+        element
+            .attribute(name)
+            .and_then(|attr| attr.value.as_int())
+            .map(|value| value as u32)
+    };
+    let sdk = manifest
+        .as_ref()
+        .and_then(|manifest| manifest.children_named("uses-sdk").next());
+    // 清单中未声明 minSdkVersion 时，系统按 1 处理；targetSdkVersion 默认与其相同
+    let min_sdk_version = sdk.and_then(|sdk| int(sdk, "minSdkVersion")).unwrap_or(1);
+    let target_sdk_version = sdk
+        .and_then(|sdk| int(sdk, "targetSdkVersion"))
+        .unwrap_or(min_sdk_version);
+    let extract_native_libs = manifest
+        .as_ref()
+        .and_then(|manifest| manifest.children_named("application").next())
+        .and_then(|application| application.attribute("extractNativeLibs"))
+        .is_none_or(|attr| !matches!(attr.value, axml::Value::Bool(false)));
+    if let (Some(manifest), Some(expected)) = (&manifest, expected) {
+        let string = |name: &str| manifest.attribute(name).map(|attr| attr.value.to_string());
+        if string("package").as_deref() != Some(expected.package.as_str()) {
+            errors.push(format!(
+                "manifest package is {}, but Cargo.toml expects `{}`",
+                describe(string("package")),
+                expected.package
+            ));
+        }
+        if let Some(version_code) = expected.version_code
+            && int(manifest, "versionCode") != Some(version_code)
+        {
+            errors.push(format!(
+                "manifest versionCode is {}, but Cargo.toml expects `{version_code}`",
+                describe(int(manifest, "versionCode")),
+            ));
+        }
+        if let Some(version_name) = &expected.version_name
+            && string("versionName").as_ref() != Some(version_name)
+        {
+            errors.push(format!(
+                "manifest versionName is {}, but Cargo.toml expects `{version_name}`",
+                describe(string("versionName")),
+            ));
+        }
+    }
+
+    let signatures = verify_signatures(&archive)?;
+    errors.extend(signatures.errors);
+    verification.warnings.extend(signatures.warnings);
+    for (scheme, certificates) in [
+        ("v1", &signatures.verified.v1),
+        ("v2", &signatures.verified.v2),
+        ("v3", &signatures.verified.v3),
+    ] {
+        if !certificates.is_empty() {
+            verification.schemes.push(scheme);
+        }
+    }
+    // 按 APK 中实际存在的签名方案检查设备兼容性，不论其能否在进程内校验
+    let present = read_signatures(&archive)?;
+    let (v1, v2, v3) = (
+        !present.v1.is_empty(),
+        !present.v2.is_empty(),
+        !present.v3.is_empty(),
+    );
+    if !v1 && !v2 && !v3 {
+        errors.push("the APK is not signed".into());
+    } else {
+        if min_sdk_version < MIN_SDK_WITH_V2 && !v1 {
+            errors.push(format!(
+                "min_sdk_version {min_sdk_version} requires a v1 (JAR) signature, \
+                devices before Android 7.0 don't support v2/v3 signatures"
+            ));
+        }
+        if min_sdk_version < MIN_SDK_WITH_V3 && v3 && !v2 && !v1 {
+            errors.push(format!(
+                "min_sdk_version {min_sdk_version} requires a v2 signature, \
+                devices before Android 9 don't support v3 signatures"
+            ));
+        }
+        if target_sdk_version >= TARGET_SDK_REQUIRING_V2 && !v2 && !v3 {
+            errors.push(format!(
+                "target_sdk_version {target_sdk_version} requires a v2 or v3 signature"
+            ));
+        }
+    }
+
+    let mut libs = BTreeMap::<&str, BTreeSet<&str>>::new();
+    for entry in archive.entries() {
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name.as_str();
+        let is_lib = name.starts_with("lib/") && name.ends_with(".so");
+        if entry.method == STORED {
+            let alignment = if is_lib { PAGE_SIZE_16K as usize } else { 4 };
+            if entry.data_offset() % alignment != 0 {
+                errors.push(format!(
+                    "`{name}` is stored at offset {}, which is not aligned to {alignment} bytes",
+                    entry.data_offset()
+                ));
+            }
+        } else if name == "resources.arsc" && target_sdk_version >= TARGET_SDK_REQUIRING_V2 {
+            errors.push(format!(
+                "`resources.arsc` must be stored uncompressed when targeting API level {target_sdk_version}"
+            ));
+        } else if is_lib && !extract_native_libs {
+            errors.push(format!(
+                "`{name}` must be stored uncompressed when extractNativeLibs is false"
+            ));
+        }
+        if is_lib && let Some((abi, file)) = name["lib/".len()..].split_once('/') {
+            libs.entry(abi).or_default().insert(file);
+        }
+    }
+
+    for (abi, files) in &libs {
+        for file in files {
+            let name = format!("lib/{abi}/{file}");
+            let data = match archive.read(archive.entry(&name).unwrap()) {
+                Ok(data) => data,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            let elf = match ElfFile::parse(Path::new(&name), &data) {
+                Ok(elf) => elf,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            if elf.is_64()
+                && let Some(alignment) = elf.min_load_alignment()
+                && alignment < PAGE_SIZE_16K
+            {
+                errors.push(format!(
+                    "`{name}` has LOAD segments aligned to {alignment} bytes, \
+                    16 KB page size devices require {PAGE_SIZE_16K}"
+                ));
+            }
+            let needed_libs = match elf.needed_libs() {
+                Ok(needed_libs) => needed_libs,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            for needed in needed_libs {
+                if !files.contains(needed.as_str()) && !is_platform_lib(&needed, min_sdk_version) {
+                    errors.push(format!(
+                        "`{name}` needs `{needed}`, which is neither in the APK \
+                        nor provided by Android at API level {min_sdk_version}"
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(verification)
+}
+
+/// `name` 是否为 `api_level` 及以上版本的系统中可用的 NDK 稳定库
+fn is_platform_lib(name: &str, api_level: u32) -> bool {
+    PLATFORM_LIBS
+        .iter()
+        .any(|(lib, since)| *lib == name && *since <= api_level)
+}
+
+fn describe(value: Option<impl std::fmt::Display>) -> String {
+    value.map_or_else(|| "missing".into(), |value| format!("`{value}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{ExpectedManifest, verify_apk},
+        crate::{
+            testing::{TempDir, debug_signer, unsigned_apk},
+            zip::ZipArchive,
+        },
+        std::fs,
+    };
+
+    #[test]
+    fn signed_and_tampered() {
+        let dir = TempDir::new("ndk-build2-verify");
+        let expected = ExpectedManifest {
+            package: "com.example.app".into(),
+            version_code: Some(1),
+            version_name: None,
+        };
+        let signer = debug_signer();
+
+        let path = dir.join("app.apk");
+        let apk = signer.sign_apk(unsigned_apk(21), 21).unwrap();
+        fs::write(&path, &apk).unwrap();
+        let verification = verify_apk(&path, Some(&expected)).unwrap();
+        assert!(verification.is_ok(), "{:?}", verification.errors);
+        assert_eq!(verification.schemes, ["v1", "v2", "v3"]);
+
+        // 修改 `classes.dex` 的一个字节后所有签名都不再匹配
+        let offset = ZipArchive::from_bytes(apk.clone())
+            .unwrap()
+            .entry("classes.dex")
+            .unwrap()
+            .data_offset();
+        let mut tampered = apk;
+        tampered[offset] ^= 1;
+        fs::write(&path, &tampered).unwrap();
+        let verification = verify_apk(&path, Some(&expected)).unwrap();
+        assert!(!verification.is_ok());
+        assert!(verification.schemes.is_empty());
+        for scheme in ["v1", "v2", "v3"] {
+            assert!(
+                verification
+                    .errors
+                    .iter()
+                    .any(|error| error.starts_with(&format!("{scheme}: "))),
+                "{:?}",
+                verification.errors
+            );
+        }
+
+        // v2/v3 签名不能用于 Android 7.0 之前的设备
+        fs::write(&path, signer.sign_apk(unsigned_apk(21), 24).unwrap()).unwrap();
+        let verification = verify_apk(&path, None).unwrap();
+        assert_eq!(verification.schemes, ["v2", "v3"]);
+        assert!(verification.errors[0].starts_with("min_sdk_version 21 requires a v1"));
+    }
+}
@@ -0,0 +1,487 @@
+//! 组装与签名 APK 所需的 ZIP 读写实现。
+//!
+//! 与通用的 ZIP 库不同，这里可以精确控制每个条目的数据偏移（对齐）以及
+//! 中央目录的位置，这是 APK 签名方案 v2/v3 所要求的。
+
+use {
+    crate::error::NdkError,
+    flate2::{Compression, Crc, read::DeflateDecoder, write::DeflateEncoder},
+    std::{
+        collections::HashSet,
+        fs::read,
+        io::{Read, Write},
+        path::Path,
+    },
+};
+
+pub const STORED: u16 = 0;
+pub const DEFLATED: u16 = 8;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const EOCD_SIGNATURE: u32 = 0x06054b50;
+const LOCAL_HEADER_LEN: usize = 30;
+const CENTRAL_HEADER_LEN: usize = 46;
+const EOCD_LEN: usize = 22;
+/// `apksigner` 与 `zipalign` 使用的对齐用扩展字段 ID
+const ALIGNMENT_EXTRA_ID: u16 = 0xd935;
+/// 未设置 `SOURCE_DATE_EPOCH` 时使用的 DOS 时间戳（1981-01-01 00:00:00），与 AOSP 的工具保持一致
+const DEFAULT_DOS_DATE: u16 = (1 << 9) | (1 << 5) | 1;
+
+/// ZIP 中央目录中的一个条目
+#[derive(Clone, Debug)]
+pub struct ZipEntry {
+    pub name: String,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u32,
+    pub uncompressed_size: u32,
+    pub dos_time: u16,
+    pub dos_date: u16,
+    pub external_attributes: u32,
+    header_offset: usize,
+    data_offset: usize,
+}
+
+impl ZipEntry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    /// 本地文件头在归档中的偏移
+    pub fn header_offset(&self) -> usize {
+        self.header_offset
+    }
+
+    /// 条目数据在归档中的偏移
+    pub fn data_offset(&self) -> usize {
+        self.data_offset
+    }
+}
+
+/// 完全读入内存的 ZIP 归档
+pub struct ZipArchive {
+    data: Vec<u8>,
+    entries: Vec<ZipEntry>,
+    central_directory_offset: usize,
+    eocd_offset: usize,
+}
+
+impl ZipArchive {
+    pub fn open(path: &Path) -> Result<Self, NdkError> {
+        let data = read(path).map_err(|e| NdkError::IoPathError(path.to_path_buf(), e))?;
+        Self::from_bytes(data)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, NdkError> {
+        let eocd_offset = find_eocd(&data)?;
+        let entry_count = u16_at(&data, eocd_offset + 10) as usize;
+        let central_directory_size = u32_at(&data, eocd_offset + 12) as usize;
+        let central_directory_offset = u32_at(&data, eocd_offset + 16) as usize;
+        if central_directory_offset + central_directory_size > eocd_offset {
+            return Err(NdkError::InvalidZip(
+                "central directory overlaps the end of central directory record".into(),
+            ));
+        }
+
+        let mut entries = Vec::with_capacity(entry_count);
+        let mut offset = central_directory_offset;
+        for _ in 0..entry_count {
+            if offset + CENTRAL_HEADER_LEN > eocd_offset
+                || u32_at(&data, offset) != CENTRAL_HEADER_SIGNATURE
+            {
+                return Err(NdkError::InvalidZip("malformed central directory".into()));
+            }
+            let name_len = u16_at(&data, offset + 28) as usize;
+            let extra_len = u16_at(&data, offset + 30) as usize;
+            let comment_len = u16_at(&data, offset + 32) as usize;
+            let name_start = offset + CENTRAL_HEADER_LEN;
+            let name = data
+                .get(name_start..name_start + name_len)
+                .ok_or_else(|| NdkError::InvalidZip("truncated entry name".into()))?;
+            let header_offset = u32_at(&data, offset + 42) as usize;
+            if header_offset + LOCAL_HEADER_LEN > data.len()
+                || u32_at(&data, header_offset) != LOCAL_HEADER_SIGNATURE
+            {
+                return Err(NdkError::InvalidZip(format!(
+                    "missing local header for `{}`",
+                    String::from_utf8_lossy(name)
+                )));
+            }
+            let data_offset = header_offset
+                + LOCAL_HEADER_LEN
+                + u16_at(&data, header_offset + 26) as usize
+                + u16_at(&data, header_offset + 28) as usize;
+            let entry = ZipEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                method: u16_at(&data, offset + 10),
+                dos_time: u16_at(&data, offset + 12),
+                dos_date: u16_at(&data, offset + 14),
+                crc32: u32_at(&data, offset + 16),
+                compressed_size: u32_at(&data, offset + 20),
+                uncompressed_size: u32_at(&data, offset + 24),
+                external_attributes: u32_at(&data, offset + 38),
+                header_offset,
+                data_offset,
+            };
+            if entry.compressed_size == u32::MAX || entry.header_offset == u32::MAX as usize {
+                return Err(NdkError::InvalidZip(
+                    "ZIP64 archives are not supported".into(),
+                ));
+            }
+            if data_offset + entry.compressed_size as usize > central_directory_offset {
+                return Err(NdkError::InvalidZip(format!(
+                    "data of `{}` overlaps the central directory",
+                    entry.name
+                )));
+            }
+            entries.push(entry);
+            offset = name_start + name_len + extra_len + comment_len;
+        }
+
+        Ok(Self {
+            data,
+            entries,
+            central_directory_offset,
+            eocd_offset,
+        })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// 归档的全部字节
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn central_directory_offset(&self) -> usize {
+        self.central_directory_offset
+    }
+
+    pub fn eocd_offset(&self) -> usize {
+        self.eocd_offset
+    }
+
+    /// 条目的原始（可能已压缩的）数据
+    pub fn raw_data(&self, entry: &ZipEntry) -> &[u8] {
+        &self.data[entry.data_offset..entry.data_offset + entry.compressed_size as usize]
+    }
+
+    /// 读取并解压条目的内容
+    pub fn read(&self, entry: &ZipEntry) -> Result<Vec<u8>, NdkError> {
+        let raw = self.raw_data(entry);
+        let data = match entry.method {
+            STORED => raw.to_vec(),
+            DEFLATED => {
+                let mut data = Vec::with_capacity(entry.uncompressed_size as usize);
+                DeflateDecoder::new(raw).read_to_end(&mut data)?;
+                data
+            }
+            method => {
+                return Err(NdkError::InvalidZip(format!(
+                    "unsupported compression method {method} for `{}`",
+                    entry.name
+                )));
+            }
+        };
+        let mut crc = Crc::new();
+        crc.update(&data);
+        if crc.sum() != entry.crc32 {
+            return Err(NdkError::InvalidZip(format!(
+                "CRC mismatch for `{}`",
+                entry.name
+            )));
+        }
+        Ok(data)
+    }
+}
+
+/// 按写入顺序生成 ZIP 归档，并为每个条目单独指定压缩方式与对齐。
+///
+/// 为了使构建可重现，所有条目（包括复制的条目）都使用同一个固定的时间戳，
+/// 该时间戳取自 `SOURCE_DATE_EPOCH` 环境变量，未设置时为 1981-01-01。
+pub struct ZipWriter {
+    out: Vec<u8>,
+    central_directory: Vec<u8>,
+    names: HashSet<String>,
+    count: usize,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+impl Default for ZipWriter {
+    fn default() -> Self {
+        let (dos_time, dos_date) = source_date_epoch()
+            .map(dos_date_time)
+            .unwrap_or((0, DEFAULT_DOS_DATE));
+        Self {
+            out: Vec::new(),
+            central_directory: Vec::new(),
+            names: HashSet::new(),
+            count: 0,
+            dos_time,
+            dos_date,
+        }
+    }
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 写入一个新条目；`alignment` 仅对未压缩的条目生效
+    pub fn add_entry(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        compress: bool,
+        alignment: usize,
+    ) -> Result<(), NdkError> {
+        let mut crc = Crc::new();
+        crc.update(data);
+        let (method, raw) = if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            (DEFLATED, encoder.finish()?)
+        } else {
+            (STORED, data.to_vec())
+        };
+        let entry = ZipEntry {
+            name: name.to_string(),
+            method,
+            crc32: crc.sum(),
+            compressed_size: raw.len() as u32,
+            uncompressed_size: data.len() as u32,
+            dos_time: self.dos_time,
+            dos_date: self.dos_date,
+            external_attributes: 0,
+            header_offset: 0,
+            data_offset: 0,
+        };
+        self.write_entry(entry, &raw, alignment)
+    }
+
+    /// 从另一个归档原样复制条目（不重新压缩）
+    pub fn copy_entry(
+        &mut self,
+        archive: &ZipArchive,
+        entry: &ZipEntry,
+        alignment: usize,
+    ) -> Result<(), NdkError> {
+        self.copy_entry_as(archive, entry, &entry.name, alignment)
+    }
+
+    /// 以新的名称复制条目
+    pub fn copy_entry_as(
+        &mut self,
+        archive: &ZipArchive,
+        entry: &ZipEntry,
+        name: &str,
+        alignment: usize,
+    ) -> Result<(), NdkError> {
+        let renamed = ZipEntry {
+            name: name.to_owned(),
+            ..entry.clone()
+        };
+        self.write_entry(renamed, archive.raw_data(entry), alignment)
+    }
+
+    fn write_entry(
+        &mut self,
+        mut entry: ZipEntry,
+        raw: &[u8],
+        alignment: usize,
+    ) -> Result<(), NdkError> {
+        if !self.names.insert(entry.name.clone()) {
+            return Err(NdkError::InvalidZip(format!(
+                "duplicate entry `{}`",
+                entry.name
+            )));
+        }
+        if u32::try_from(self.out.len() + raw.len()).is_err() {
+            return Err(NdkError::InvalidZip(
+                "ZIP64 archives are not supported".into(),
+            ));
+        }
+
+        entry.dos_time = self.dos_time;
+        entry.dos_date = self.dos_date;
+        let name = entry.name.as_bytes();
+        let header_offset = self.out.len();
+        let mut extra = Vec::new();
+        if entry.method == STORED && alignment > 1 {
+            // 扩展字段至少包含 ID、长度与对齐值共 6 个字节，其余用零填充
+            let unpadded = header_offset + LOCAL_HEADER_LEN + name.len() + 6;
+            let padding = (alignment - unpadded % alignment) % alignment;
+            extra.extend_from_slice(&ALIGNMENT_EXTRA_ID.to_le_bytes());
+            extra.extend_from_slice(&(2 + padding as u16).to_le_bytes());
+            extra.extend_from_slice(&(alignment.min(u16::MAX as usize) as u16).to_le_bytes());
+            extra.resize(6 + padding, 0);
+        }
+
+        let version: u16 = if entry.method == DEFLATED { 20 } else { 10 };
+        let flags: u16 = if entry.name.is_ascii() { 0 } else { 0x0800 };
+        let out = &mut self.out;
+        out.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&version.to_le_bytes());
+        out.extend_from_slice(&flags.to_le_bytes());
+        out.extend_from_slice(&entry.method.to_le_bytes());
+        out.extend_from_slice(&entry.dos_time.to_le_bytes());
+        out.extend_from_slice(&entry.dos_date.to_le_bytes());
+        out.extend_from_slice(&entry.crc32.to_le_bytes());
+        out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        out.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        out.extend_from_slice(name);
+        out.extend_from_slice(&extra);
+        entry.header_offset = header_offset;
+        entry.data_offset = out.len();
+        out.extend_from_slice(raw);
+
+        let cd = &mut self.central_directory;
+        cd.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        cd.extend_from_slice(&version.to_le_bytes());
+        cd.extend_from_slice(&version.to_le_bytes());
+        cd.extend_from_slice(&flags.to_le_bytes());
+        cd.extend_from_slice(&entry.method.to_le_bytes());
+        cd.extend_from_slice(&entry.dos_time.to_le_bytes());
+        cd.extend_from_slice(&entry.dos_date.to_le_bytes());
+        cd.extend_from_slice(&entry.crc32.to_le_bytes());
+        cd.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        cd.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+        cd.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        // 扩展字段长度、注释长度、起始磁盘号、内部属性
+        cd.extend_from_slice(&[0; 8]);
+        cd.extend_from_slice(&entry.external_attributes.to_le_bytes());
+        cd.extend_from_slice(&(header_offset as u32).to_le_bytes());
+        cd.extend_from_slice(name);
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// 写出中央目录，返回完整的归档字节
+    pub fn finish(mut self) -> Result<Vec<u8>, NdkError> {
+        let count = u16::try_from(self.count)
+            .map_err(|_| NdkError::InvalidZip("too many entries".into()))?;
+        let central_directory_offset = self.out.len() as u32;
+        let central_directory_size = self.central_directory.len() as u32;
+        self.out.append(&mut self.central_directory);
+        self.out.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]);
+        self.out.extend_from_slice(&count.to_le_bytes());
+        self.out.extend_from_slice(&count.to_le_bytes());
+        self.out
+            .extend_from_slice(&central_directory_size.to_le_bytes());
+        self.out
+            .extend_from_slice(&central_directory_offset.to_le_bytes());
+        self.out.extend_from_slice(&[0; 2]);
+        Ok(self.out)
+    }
+}
+
+/// `zipalign -P 16 4` 的对齐规则：未压缩的 `.so` 按 16 KB 内存页对齐，其余未压缩条目按 4 字节对齐
+pub fn default_alignment(name: &str) -> usize {
+    if name.ends_with(".so") { 16384 } else { 4 }
+}
+
+/// 读取 [`SOURCE_DATE_EPOCH`](https://reproducible-builds.org/specs/source-date-epoch/) 环境变量
+pub fn source_date_epoch() -> Option<u64> {
+    std::env::var("SOURCE_DATE_EPOCH").ok()?.trim().parse().ok()
+}
+
+/// 将 Unix 时间戳（UTC）转换为 DOS 时间与日期，超出 DOS 可表示范围（1980-2107）的值会被截断
+fn dos_date_time(secs: u64) -> (u16, u16) {
+    const MIN: u64 = 315532800; // 1980-01-01
+    const MAX: u64 = 4354819199; // 2107-12-31 23:59:59
+    let secs = secs.clamp(MIN, MAX);
+    let days = (secs / 86400) as i64;
+    let secs_of_day = secs % 86400;
+
+    // 参见 <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let time = (secs_of_day / 3600) << 11 | (secs_of_day % 3600 / 60) << 5 | (secs_of_day % 60 / 2);
+    let date = (year - 1980) << 9 | month << 5 | day;
+    (time as u16, date as u16)
+}
+
+/// 从归档末尾向前查找 EOCD 记录（其后可能跟随最长 65535 字节的注释）
+fn find_eocd(data: &[u8]) -> Result<usize, NdkError> {
+    if data.len() < EOCD_LEN {
+        return Err(NdkError::InvalidZip("file is too small".into()));
+    }
+    let lowest = data.len().saturating_sub(EOCD_LEN + u16::MAX as usize);
+    (lowest..=data.len() - EOCD_LEN)
+        .rev()
+        .find(|&offset| {
+            u32_at(data, offset) == EOCD_SIGNATURE
+                && offset + EOCD_LEN + u16_at(data, offset + 20) as usize == data.len()
+        })
+        .ok_or_else(|| NdkError::InvalidZip("end of central directory not found".into()))
+}
+
+pub(crate) fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+pub(crate) fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read_back() {
+        let mut writer = ZipWriter::new();
+        writer
+            .add_entry("AndroidManifest.xml", b"<manifest/>", true, 4)
+            .unwrap();
+        writer
+            .add_entry("lib/arm64-v8a/libfoo.so", &[0x7f; 100], false, 16384)
+            .unwrap();
+        assert!(
+            writer
+                .add_entry("AndroidManifest.xml", b"", true, 4)
+                .is_err()
+        );
+        let archive = ZipArchive::from_bytes(writer.finish().unwrap()).unwrap();
+
+        let manifest = archive.entry("AndroidManifest.xml").unwrap();
+        assert_eq!(manifest.method, DEFLATED);
+        assert_eq!(archive.read(manifest).unwrap(), b"<manifest/>");
+
+        let lib = archive.entry("lib/arm64-v8a/libfoo.so").unwrap();
+        assert_eq!(lib.method, STORED);
+        assert_eq!(lib.data_offset() % 16384, 0);
+        assert_eq!(archive.read(lib).unwrap(), vec![0x7f; 100]);
+    }
+
+    #[test]
+    fn dos_timestamps() {
+        // 2024-01-02 03:04:06 UTC
+        assert_eq!(
+            dos_date_time(1704164646),
+            (3 << 11 | 4 << 5 | 3, 44 << 9 | 1 << 5 | 2)
+        );
+        assert_eq!(dos_date_time(0), (0, 1 << 5 | 1));
+    }
+}